@@ -4,6 +4,7 @@ use crate::file_fetcher::TextDocument;
 use crate::media_type::MediaType;
 
 use deno_core::error::AnyError;
+use deno_core::serde_json;
 use deno_core::ModuleSpecifier;
 use std::error::Error;
 use std::fmt;
@@ -19,13 +20,25 @@ use swc_common::errors::DiagnosticBuilder;
 use swc_common::errors::Emitter;
 use swc_common::errors::Handler;
 use swc_common::errors::HandlerFlags;
+use swc_common::BytePos;
 use swc_common::FileName;
 use swc_common::Globals;
 use swc_common::Loc;
 use swc_common::SourceMap;
 use swc_common::Span;
+use swc_common::Spanned;
+use swc_common::DUMMY_SP;
+use swc_ecmascript::ast::BinExpr;
+use swc_ecmascript::ast::BinaryOp;
+use swc_ecmascript::ast::CondExpr;
+use swc_ecmascript::ast::IfStmt;
+use swc_ecmascript::ast::Invalid;
 use swc_ecmascript::ast::Module;
+use swc_ecmascript::ast::Pat;
 use swc_ecmascript::ast::Program;
+use swc_ecmascript::ast::SwitchStmt;
+use swc_ecmascript::ast::VarDecl;
+use swc_ecmascript::ast::VarDeclKind;
 use swc_ecmascript::codegen::text_writer::JsWriter;
 use swc_ecmascript::codegen::Node;
 use swc_ecmascript::dep_graph::analyze_dependencies;
@@ -43,6 +56,9 @@ use swc_ecmascript::transforms::proposals::decorators;
 use swc_ecmascript::transforms::react;
 use swc_ecmascript::transforms::typescript;
 use swc_ecmascript::visit::FoldWith;
+use swc_ecmascript::visit::Node as VisitNode;
+use swc_ecmascript::visit::Visit;
+use swc_ecmascript::visit::VisitWith;
 
 type Result<V> = result::Result<V, AnyError>;
 
@@ -189,6 +205,10 @@ pub struct TranspileOptions {
   pub jsx_fragment_factory: String,
   /// Should JSX be transformed or preserved.  Defaults to `true`.
   pub transform_jsx: bool,
+  /// Should the helpers that the emitted code calls, like those applying
+  /// decorators, be defined in the emitted code, for it to run on its own.
+  /// Defaults to `false`.
+  pub inject_helpers: bool,
 }
 
 impl Default for TranspileOptions {
@@ -199,10 +219,131 @@ impl Default for TranspileOptions {
       jsx_factory: "React.createElement".into(),
       jsx_fragment_factory: "React.Fragment".into(),
       transform_jsx: true,
+      inject_helpers: false,
     }
   }
 }
 
+/// A `let` declaration where every declarator binds a single identifier and
+/// has an initializer, so it can be turned into a `const` declaration.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LetDeclaration {
+  /// Byte offset of the `let` keyword in the source.
+  pub keyword_offset: usize,
+  /// Byte offsets of the declared identifiers in the source.
+  pub binding_offsets: Vec<usize>,
+}
+
+struct LetDeclarationCollector<'a> {
+  source_map: &'a SourceMap,
+  declarations: Vec<LetDeclaration>,
+}
+
+impl<'a> Visit for LetDeclarationCollector<'a> {
+  fn visit_var_decl(&mut self, var_decl: &VarDecl, _parent: &dyn VisitNode) {
+    if var_decl.kind == VarDeclKind::Let {
+      let binding_offsets: Option<Vec<usize>> = var_decl
+        .decls
+        .iter()
+        .map(|decl| match &decl.name {
+          Pat::Ident(ident) if decl.init.is_some() => Some(
+            self.source_map.lookup_byte_offset(ident.span.lo).pos.0 as usize,
+          ),
+          _ => None,
+        })
+        .collect();
+      if let Some(binding_offsets) = binding_offsets {
+        let keyword_offset =
+          self.source_map.lookup_byte_offset(var_decl.span.lo).pos.0 as usize;
+        self.declarations.push(LetDeclaration {
+          keyword_offset,
+          binding_offsets,
+        });
+      }
+    }
+    var_decl.visit_children_with(self);
+  }
+}
+
+/// A point where control flow splits, such as an `if` statement or a `&&`
+/// operator, and the code of its branches.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BranchPoint {
+  /// Byte offset of the branch point in the source.
+  pub offset: usize,
+  /// Byte offsets of the start of the branches that have code.
+  pub branch_offsets: Vec<usize>,
+  /// Whether there is another branch without code, taken whenever none of
+  /// the others are, like the missing `else` of an `if` statement.
+  pub has_implicit_branch: bool,
+}
+
+struct BranchPointCollector<'a> {
+  source_map: &'a SourceMap,
+  branch_points: Vec<BranchPoint>,
+}
+
+impl<'a> BranchPointCollector<'a> {
+  fn add(&mut self, span: Span, branches: &[Span], has_implicit_branch: bool) {
+    let offset_of =
+      |span: &Span| self.source_map.lookup_byte_offset(span.lo).pos.0 as usize;
+    let branch_point = BranchPoint {
+      offset: offset_of(&span),
+      branch_offsets: branches.iter().map(offset_of).collect(),
+      has_implicit_branch,
+    };
+    self.branch_points.push(branch_point);
+  }
+}
+
+impl<'a> Visit for BranchPointCollector<'a> {
+  fn visit_if_stmt(&mut self, if_stmt: &IfStmt, _parent: &dyn VisitNode) {
+    match &if_stmt.alt {
+      Some(alt) => {
+        self.add(if_stmt.span, &[if_stmt.cons.span(), alt.span()], false)
+      }
+      None => self.add(if_stmt.span, &[if_stmt.cons.span()], true),
+    }
+    if_stmt.visit_children_with(self);
+  }
+
+  fn visit_cond_expr(&mut self, cond_expr: &CondExpr, _parent: &dyn VisitNode) {
+    self.add(
+      cond_expr.span,
+      &[cond_expr.cons.span(), cond_expr.alt.span()],
+      false,
+    );
+    cond_expr.visit_children_with(self);
+  }
+
+  fn visit_bin_expr(&mut self, bin_expr: &BinExpr, _parent: &dyn VisitNode) {
+    // The right operand is skipped when the left one short-circuits.
+    if matches!(
+      bin_expr.op,
+      BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing
+    ) {
+      self.add(bin_expr.span, &[bin_expr.right.span()], true);
+    }
+    bin_expr.visit_children_with(self);
+  }
+
+  fn visit_switch_stmt(
+    &mut self,
+    switch_stmt: &SwitchStmt,
+    _parent: &dyn VisitNode,
+  ) {
+    // Cases without statements fall through to the next case.
+    let cases = switch_stmt
+      .cases
+      .iter()
+      .filter_map(|case| case.cons.first().map(|stmt| stmt.span()))
+      .collect::<Vec<_>>();
+    let has_default = switch_stmt.cases.iter().any(|case| case.test.is_none());
+    self.add(switch_stmt.span, &cases, !has_default);
+    switch_stmt.visit_children_with(self);
+  }
+}
+
 /// A logical structure to hold the value of a parsed module for further
 /// processing.
 #[derive(Clone)]
@@ -235,11 +376,62 @@ impl ParsedModule {
     self.leading_comments.clone()
   }
 
+  /// Get all the comments of the module, in the order they appear in the
+  /// source, such as the JSDoc comments of its declarations.
+  pub fn get_comments(&self) -> Vec<Comment> {
+    let (leading, trailing) = self.comments.clone().take_all();
+    let mut comments = leading
+      .borrow()
+      .values()
+      .chain(trailing.borrow().values())
+      .flatten()
+      .cloned()
+      .collect::<Vec<_>>();
+    comments.sort_by_key(|comment| comment.span.lo);
+    comments
+  }
+
   /// Get a location for a given span within the module.
   pub fn get_location(&self, span: &Span) -> Location {
     self.source_map.lookup_char_pos(span.lo).into()
   }
 
+  /// Get the byte offset in the module's source of a position of a span.
+  pub fn get_byte_offset(&self, pos: BytePos) -> usize {
+    self.source_map.lookup_byte_offset(pos).pos.0 as usize
+  }
+
+  /// Serialize the module's AST to JSON, as exposed to lint plugins.
+  pub fn to_json(&self) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(&self.module)?)
+  }
+
+  /// Get the `let` declarations of the module that could be turned into
+  /// `const` declarations, if none of their bindings is reassigned.
+  pub fn get_let_declarations(&self) -> Vec<LetDeclaration> {
+    let mut collector = LetDeclarationCollector {
+      source_map: &self.source_map,
+      declarations: Vec::new(),
+    };
+    self
+      .module
+      .visit_with(&Invalid { span: DUMMY_SP } as _, &mut collector);
+    collector.declarations
+  }
+
+  /// Get the branch points of the module, in the order they appear in the
+  /// source.
+  pub fn get_branch_points(&self) -> Vec<BranchPoint> {
+    let mut collector = BranchPointCollector {
+      source_map: &self.source_map,
+      branch_points: Vec::new(),
+    };
+    self
+      .module
+      .visit_with(&Invalid { span: DUMMY_SP } as _, &mut collector);
+    collector.branch_points
+  }
+
   /// Transform a TypeScript file into a JavaScript file, based on the supplied
   /// options.
   ///
@@ -268,6 +460,7 @@ impl ParsedModule {
         legacy: true,
         emit_metadata: options.emit_metadata
       }),
+      Optional::new(helpers::inject_helpers(), options.inject_helpers),
       typescript::strip(),
       fixer(Some(&self.comments)),
     );
@@ -373,11 +566,76 @@ pub fn parse(
   })
 }
 
+/// Get the byte offset of the end of the span of the syntax error of
+/// `source`, if it can't be parsed.
+fn get_syntax_error_end(source: &str, media_type: &MediaType) -> Option<usize> {
+  let source_map = SourceMap::default();
+  let source_file =
+    source_map.new_source_file(FileName::Anon, source.to_string());
+  let error_buffer = ErrorBuffer::new();
+  let handler = Handler::with_emitter_and_flags(
+    Box::new(error_buffer.clone()),
+    HandlerFlags {
+      dont_buffer_diagnostics: true,
+      ..HandlerFlags::default()
+    },
+  );
+
+  let lexer = Lexer::new(
+    get_syntax(media_type),
+    TARGET,
+    StringInput::from(&*source_file),
+    None,
+  );
+  let mut parser = swc_ecmascript::parser::Parser::new_from(lexer);
+  let err = parser.parse_module().err()?;
+  err.into_diagnostic(&handler).emit();
+
+  let diagnostics = error_buffer.0.read().unwrap();
+  let span = diagnostics.first()?.span.primary_span()?;
+  Some((span.hi - source_file.start_pos).0 as usize)
+}
+
+/// Check whether `source` can't be parsed only because it ends too early,
+/// like a line of the REPL opening a block that the next lines close.
+///
+/// The source is incomplete if more source moves its syntax error past its
+/// end, or fixes it.
+pub fn is_incomplete(source: &str, media_type: &MediaType) -> bool {
+  if get_syntax_error_end(source, media_type).is_none() {
+    return false;
+  }
+  let continued_source = format!("{}\n;", source);
+  get_syntax_error_end(&continued_source, media_type)
+    .map_or(true, |end| end > source.len())
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use swc_ecmascript::dep_graph::DependencyKind;
 
+  #[test]
+  fn test_parsed_module_get_comments() {
+    let specifier =
+      ModuleSpecifier::resolve_url_or_path("https://deno.land/x/mod.ts")
+        .unwrap();
+    let source = r#"// leading
+/** Add numbers. */
+export function add(a: number, b: number) {
+  return a + b; // trailing
+}
+"#;
+    let parsed_module = parse(&specifier, source, &MediaType::TypeScript)
+      .expect("could not parse module");
+    let comments = parsed_module
+      .get_comments()
+      .into_iter()
+      .map(|comment| comment.text.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(comments, vec![" leading", "* Add numbers. ", " trailing"]);
+  }
+
   #[test]
   fn test_parsed_module_analyze_dependencies() {
     let specifier =
@@ -412,6 +670,59 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_parsed_module_get_let_declarations() {
+    let specifier =
+      ModuleSpecifier::resolve_url_or_path("https://deno.land/x/mod.ts")
+        .unwrap();
+    let source = "let a = 1, b = 2;\nlet c;\nfunction f() {\n  let d = [1];\n}\nlet { e } = {};\n";
+    let parsed_module = parse(&specifier, source, &MediaType::TypeScript)
+      .expect("could not parse module");
+    assert_eq!(
+      parsed_module.get_let_declarations(),
+      vec![
+        LetDeclaration {
+          keyword_offset: 0,
+          binding_offsets: vec![4, 11],
+        },
+        LetDeclaration {
+          keyword_offset: 42,
+          binding_offsets: vec![46],
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parsed_module_get_branch_points() {
+    let specifier =
+      ModuleSpecifier::resolve_url_or_path("https://deno.land/x/mod.js")
+        .unwrap();
+    let source = "if (a) b();\nconst c = a ? 1 : 2;\nd && e();\n";
+    let parsed_module = parse(&specifier, source, &MediaType::JavaScript)
+      .expect("could not parse module");
+    assert_eq!(
+      parsed_module.get_branch_points(),
+      vec![
+        BranchPoint {
+          offset: 0,
+          branch_offsets: vec![7],
+          has_implicit_branch: true,
+        },
+        BranchPoint {
+          offset: 22,
+          branch_offsets: vec![26, 30],
+          has_implicit_branch: false,
+        },
+        BranchPoint {
+          offset: 33,
+          branch_offsets: vec![38],
+          has_implicit_branch: true,
+        },
+      ]
+    );
+  }
+
   #[test]
   fn test_transpile() {
     let specifier =
@@ -496,14 +807,35 @@ mod tests {
       }
     }
     "#;
-    let module = parse(&specifier, source, &MediaType::TypeScript)
-      .expect("could not parse module");
-    let (code, _) = module
-      .transpile(&TranspileOptions::default())
-      .expect("could not strip types");
-    assert!(code
-      .to_string()
-      .unwrap()
-      .contains("_applyDecoratedDescriptor("));
+    let transpile = |options: &TranspileOptions| {
+      let module = parse(&specifier, source, &MediaType::TypeScript)
+        .expect("could not parse module");
+      let (code, _) = module.transpile(options).expect("could not strip types");
+      code.to_string().unwrap()
+    };
+    let code = transpile(&TranspileOptions::default());
+    assert!(code.contains("_applyDecoratedDescriptor("));
+    assert!(!code.contains("function _applyDecoratedDescriptor("));
+
+    let code = transpile(&TranspileOptions {
+      inject_helpers: true,
+      ..TranspileOptions::default()
+    });
+    assert!(code.contains("function _applyDecoratedDescriptor("));
+  }
+
+  #[test]
+  fn test_is_incomplete() {
+    let media_type = MediaType::TypeScript;
+    assert!(is_incomplete(
+      "function add(a: number, b: number) {",
+      &media_type
+    ));
+    assert!(is_incomplete("const total = 1 +", &media_type));
+    assert!(is_incomplete("if (ok)", &media_type));
+    assert!(is_incomplete("enum Color {\n  Red,", &media_type));
+    assert!(!is_incomplete("const a: number = 1;", &media_type));
+    assert!(!is_incomplete("add(1, 2))", &media_type));
+    assert!(!is_incomplete("const = 1", &media_type));
   }
 }
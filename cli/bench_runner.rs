@@ -0,0 +1,93 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Discovery of the modules run by `deno bench`, and generation of the module
+//! that runs the benchmarks they register with `Deno.bench()`.
+
+use crate::fs::is_supported_test_path;
+use crate::test_runner::prepare_urls;
+use deno_core::error::AnyError;
+use deno_core::serde_json::json;
+use deno_core::url::Url;
+use std::path::Path;
+use std::path::PathBuf;
+
+fn is_supported(p: &Path) -> bool {
+  is_supported_test_path(p, "bench")
+}
+
+pub fn prepare_bench_modules_urls(
+  include: Vec<String>,
+  root_path: &PathBuf,
+) -> Result<Vec<Url>, AnyError> {
+  prepare_urls(include, root_path, is_supported)
+}
+
+/// Options of a benchmark run, passed to the bench runner of the worker.
+#[derive(Clone, Debug, Default)]
+pub struct BenchOptions {
+  pub filter: Option<String>,
+  /// Print the results as a JSON document instead of a human-readable report.
+  pub json: bool,
+}
+
+/// Render the module that imports the bench `modules` and runs their
+/// benchmarks.
+pub fn render_bench_file(modules: Vec<Url>, options: &BenchOptions) -> String {
+  let mut bench_file = "".to_string();
+
+  for module in modules {
+    bench_file.push_str(&format!("import \"{}\";\n", module.to_string()));
+  }
+
+  let mut run_options = json!({ "json": options.json });
+  if let Some(filter) = &options.filter {
+    run_options["filter"] = json!(filter);
+  }
+
+  let run_benchmarks_cmd = format!(
+    "// @ts-ignore\nDeno[Deno.internal].runBenchmarks({});\n",
+    run_options
+  );
+  bench_file.push_str(&run_benchmarks_cmd);
+
+  bench_file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_supported() {
+    assert!(is_supported(Path::new("benches/subdir/foo_bench.ts")));
+    assert!(is_supported(Path::new("benches/subdir/foo_bench.tsx")));
+    assert!(is_supported(Path::new("benches/subdir/foo_bench.js")));
+    assert!(is_supported(Path::new("benches/subdir/foo_bench.mjs")));
+    assert!(is_supported(Path::new("benches/subdir/foo_bench.jsx")));
+    assert!(is_supported(Path::new("bar/foo.bench.ts")));
+    assert!(is_supported(Path::new("bar/foo.bench.tsx")));
+    assert!(is_supported(Path::new("bar/foo.bench.js")));
+    assert!(is_supported(Path::new("bar/foo.bench.jsx")));
+    assert!(is_supported(Path::new("foo/bar/bench.js")));
+    assert!(is_supported(Path::new("foo/bar/bench.ts")));
+    assert!(!is_supported(Path::new("README.md")));
+    assert!(!is_supported(Path::new("foo/bar_test.ts")));
+    assert!(!is_supported(Path::new("notabench.js")));
+    assert!(!is_supported(Path::new("benchmarks.ts")));
+  }
+
+  #[test]
+  fn test_render_bench_file() {
+    let modules = vec![Url::parse("file:///dev/foo_bench.ts").unwrap()];
+    let options = BenchOptions {
+      filter: Some("parse".to_string()),
+      json: true,
+    };
+    assert_eq!(
+      render_bench_file(modules, &options),
+      "import \"file:///dev/foo_bench.ts\";\n\
+       // @ts-ignore\n\
+       Deno[Deno.internal].runBenchmarks({\"filter\":\"parse\",\"json\":true});\n"
+    );
+  }
+}
@@ -0,0 +1,334 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Tool configuration read from the file passed via `--config`.
+//!
+//! The same file may also hold `compilerOptions`, which are consumed by
+//! `tsc_config`. Top level keys other than the ones declared here are
+//! ignored.
+
+use crate::tsc_config::parse_raw_config;
+use deno_core::error::AnyError;
+use deno_core::serde_json;
+use deno_core::serde_json::Value;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// How markdown paragraphs are wrapped by the formatter.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProseWrap {
+  Always,
+  Never,
+  Preserve,
+}
+
+impl std::str::FromStr for ProseWrap {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "always" => Ok(ProseWrap::Always),
+      "never" => Ok(ProseWrap::Never),
+      "preserve" => Ok(ProseWrap::Preserve),
+      _ => Err(format!("Unknown prose wrap option: {}", s)),
+    }
+  }
+}
+
+/// Options of `deno fmt`. A `None` value means that the formatter default is
+/// used.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct FmtOptionsConfig {
+  pub use_tabs: Option<bool>,
+  pub line_width: Option<u32>,
+  pub indent_width: Option<u8>,
+  pub single_quote: Option<bool>,
+  pub prose_wrap: Option<ProseWrap>,
+}
+
+impl FmtOptionsConfig {
+  /// Overwrite the options that are set in `other`, so that options passed
+  /// on the command line take precedence over the configuration file.
+  pub fn merge(&mut self, other: &FmtOptionsConfig) {
+    if other.use_tabs.is_some() {
+      self.use_tabs = other.use_tabs;
+    }
+    if other.line_width.is_some() {
+      self.line_width = other.line_width;
+    }
+    if other.indent_width.is_some() {
+      self.indent_width = other.indent_width;
+    }
+    if other.single_quote.is_some() {
+      self.single_quote = other.single_quote;
+    }
+    if other.prose_wrap.is_some() {
+      self.prose_wrap = other.prose_wrap;
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FmtConfig {
+  pub options: FmtOptionsConfig,
+}
+
+/// Selection of lint rules. Rules are enabled if they have one of `tags`
+/// (defaults to `["recommended"]`) or are listed in `include`, unless they
+/// are listed in `exclude`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintRulesConfig {
+  pub tags: Option<Vec<String>>,
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+}
+
+impl LintRulesConfig {
+  /// Apply `other` on top of this selection. Tags are replaced if `other`
+  /// specifies them, rule names are added to the respective list and removed
+  /// from the opposite one.
+  pub fn merge(&mut self, other: &LintRulesConfig) {
+    if other.tags.is_some() {
+      self.tags = other.tags.clone();
+    }
+    for code in &other.include {
+      self.exclude.retain(|c| c != code);
+      if !self.include.contains(code) {
+        self.include.push(code.clone());
+      }
+    }
+    for code in &other.exclude {
+      self.include.retain(|c| c != code);
+      if !self.exclude.contains(code) {
+        self.exclude.push(code.clone());
+      }
+    }
+  }
+}
+
+/// Paths, relative to the configuration file, that a tool processes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilesConfig {
+  pub include: Vec<String>,
+  pub exclude: Vec<String>,
+}
+
+/// Rules applied to the files and directories listed in `files`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintOverrideConfig {
+  pub files: Vec<String>,
+  pub rules: LintRulesConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+  pub rules: LintRulesConfig,
+  pub files: FilesConfig,
+  pub overrides: Vec<LintOverrideConfig>,
+  /// Modules, relative to the configuration file, defining additional rules.
+  pub plugins: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFileJson {
+  fmt: Option<Value>,
+  lint: Option<Value>,
+}
+
+/// A parsed configuration file.
+#[derive(Debug)]
+pub struct ConfigFile {
+  pub path: PathBuf,
+  json: ConfigFileJson,
+}
+
+impl ConfigFile {
+  /// Read and parse the configuration file at `path`, which is resolved
+  /// relative to the current directory.
+  pub fn read(path: &str) -> Result<ConfigFile, AnyError> {
+    let cwd = std::env::current_dir()?;
+    let config_file = cwd.join(path);
+    let config_path = config_file.canonicalize().map_err(|_| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!(
+          "Could not find the config file: {}",
+          config_file.to_string_lossy()
+        ),
+      )
+    })?;
+    let config_text = std::fs::read_to_string(&config_path)?;
+    Self::new(&config_text, config_path)
+  }
+
+  pub fn new(text: &str, path: PathBuf) -> Result<ConfigFile, AnyError> {
+    let value = parse_raw_config(text)?;
+    let json: ConfigFileJson = serde_json::from_value(value)?;
+    Ok(ConfigFile { path, json })
+  }
+
+  /// Return the `fmt` section of the configuration file, if any.
+  pub fn to_fmt_config(&self) -> Result<Option<FmtConfig>, AnyError> {
+    if let Some(config) = self.json.fmt.clone() {
+      let fmt_config: FmtConfig = serde_json::from_value(config)?;
+      Ok(Some(fmt_config))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Return the `lint` section of the configuration file, if any.
+  pub fn to_lint_config(&self) -> Result<Option<LintConfig>, AnyError> {
+    if let Some(config) = self.json.lint.clone() {
+      let lint_config: LintConfig = serde_json::from_value(config)?;
+      Ok(Some(lint_config))
+    } else {
+      Ok(None)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_fmt_config() {
+    let config_text = r#"{
+      "compilerOptions": {
+        "strict": true
+      },
+      "fmt": {
+        // comments are allowed
+        "options": {
+          "useTabs": true,
+          "lineWidth": 100,
+          "singleQuote": true,
+          "proseWrap": "preserve"
+        }
+      }
+    }"#;
+    let config_file =
+      ConfigFile::new(config_text, PathBuf::from("/deno/deno.jsonc")).unwrap();
+    let fmt_config = config_file.to_fmt_config().unwrap().unwrap();
+    assert_eq!(
+      fmt_config.options,
+      FmtOptionsConfig {
+        use_tabs: Some(true),
+        line_width: Some(100),
+        indent_width: None,
+        single_quote: Some(true),
+        prose_wrap: Some(ProseWrap::Preserve),
+      }
+    );
+  }
+
+  #[test]
+  fn test_parse_fmt_config_unknown_option() {
+    let config_text = r#"{ "fmt": { "options": { "semiColons": false } } }"#;
+    let config_file =
+      ConfigFile::new(config_text, PathBuf::from("/deno/deno.jsonc")).unwrap();
+    assert!(config_file.to_fmt_config().is_err());
+  }
+
+  #[test]
+  fn test_parse_lint_config() {
+    let config_text = r#"{
+      "lint": {
+        "files": {
+          "include": ["src/"],
+          "exclude": ["src/testdata/"]
+        },
+        "rules": {
+          "tags": ["recommended"],
+          "include": ["ban-untagged-todo"],
+          "exclude": ["no-unused-vars"]
+        },
+        "overrides": [{
+          "files": ["src/tests/"],
+          "rules": { "exclude": ["no-explicit-any"] }
+        }],
+        "plugins": ["lint_rules/naming.ts"]
+      }
+    }"#;
+    let config_file =
+      ConfigFile::new(config_text, PathBuf::from("/deno/deno.jsonc")).unwrap();
+    assert!(config_file.to_fmt_config().unwrap().is_none());
+    let lint_config = config_file.to_lint_config().unwrap().unwrap();
+    assert_eq!(
+      lint_config,
+      LintConfig {
+        rules: LintRulesConfig {
+          tags: Some(vec!["recommended".to_string()]),
+          include: vec!["ban-untagged-todo".to_string()],
+          exclude: vec!["no-unused-vars".to_string()],
+        },
+        files: FilesConfig {
+          include: vec!["src/".to_string()],
+          exclude: vec!["src/testdata/".to_string()],
+        },
+        overrides: vec![LintOverrideConfig {
+          files: vec!["src/tests/".to_string()],
+          rules: LintRulesConfig {
+            tags: None,
+            include: vec![],
+            exclude: vec!["no-explicit-any".to_string()],
+          },
+        }],
+        plugins: vec!["lint_rules/naming.ts".to_string()],
+      }
+    );
+  }
+
+  #[test]
+  fn test_lint_rules_merge() {
+    let mut rules = LintRulesConfig {
+      tags: Some(vec!["recommended".to_string()]),
+      include: vec!["ban-untagged-todo".to_string()],
+      exclude: vec!["no-unused-vars".to_string()],
+    };
+    rules.merge(&LintRulesConfig {
+      tags: None,
+      include: vec!["no-unused-vars".to_string()],
+      exclude: vec!["ban-untagged-todo".to_string()],
+    });
+    assert_eq!(
+      rules,
+      LintRulesConfig {
+        tags: Some(vec!["recommended".to_string()]),
+        include: vec!["no-unused-vars".to_string()],
+        exclude: vec!["ban-untagged-todo".to_string()],
+      }
+    );
+  }
+
+  #[test]
+  fn test_fmt_options_merge() {
+    let mut options = FmtOptionsConfig {
+      line_width: Some(100),
+      single_quote: Some(true),
+      ..Default::default()
+    };
+    options.merge(&FmtOptionsConfig {
+      single_quote: Some(false),
+      indent_width: Some(4),
+      ..Default::default()
+    });
+    assert_eq!(
+      options,
+      FmtOptionsConfig {
+        line_width: Some(100),
+        indent_width: Some(4),
+        single_quote: Some(false),
+        ..Default::default()
+      }
+    );
+  }
+}
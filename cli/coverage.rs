@@ -1,13 +1,37 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+//! Code coverage of `deno test --coverage` and `deno run --coverage`.
+//!
+//! The V8 profiles collected during a run are either reported right away, or
+//! saved to a directory with `--coverage=<dir>`, from which `deno coverage`
+//! merges the profiles of several runs into a report.
+
+use crate::ast;
 use crate::colors;
+use crate::escape::html_escape;
+use crate::escape::xml_escape;
+use crate::global_state::GlobalState;
 use crate::inspector::DenoInspector;
 use crate::inspector::InspectorSession;
+use crate::media_type::MediaType;
+use crate::permissions::Permissions;
+use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::serde_json;
 use deno_core::serde_json::json;
 use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
 use serde::Deserialize;
+use serde::Serialize;
+use sourcemap::SourceMap;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use uuid::Uuid;
 
 pub struct CoverageCollector {
   session: Box<InspectorSession>,
@@ -68,6 +92,8 @@ impl CoverageCollector {
       coverages.push(Coverage {
         script_coverage,
         script_source: get_script_source_result.script_source,
+        source_map: None,
+        original_source: None,
       })
     }
 
@@ -92,7 +118,7 @@ impl CoverageCollector {
   }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CoverageRange {
   pub start_offset: usize,
@@ -100,7 +126,7 @@ pub struct CoverageRange {
   pub count: usize,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FunctionCoverage {
   pub function_name: String,
@@ -108,7 +134,7 @@ pub struct FunctionCoverage {
   pub is_block_coverage: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScriptCoverage {
   pub script_id: String,
@@ -116,11 +142,17 @@ pub struct ScriptCoverage {
   pub functions: Vec<FunctionCoverage>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Coverage {
   pub script_coverage: ScriptCoverage,
   pub script_source: String,
+  /// Source map of a script emitted by the compiler.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub source_map: Option<String>,
+  /// Source the script was emitted from.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub original_source: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -136,82 +168,1019 @@ pub struct GetScriptSourceResult {
   pub bytecode: Option<String>,
 }
 
-pub struct PrettyCoverageReporter {
-  quiet: bool,
+/// Hit counts of the lines, functions and branches of a script, computed from
+/// one or more coverage profiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverageReport {
+  pub url: String,
+  /// Lines of the script, with their hit count if they contain code.
+  pub lines: Vec<(String, Option<usize>)>,
+  pub functions: Vec<FunctionReport>,
+  pub branches: Vec<BranchReport>,
 }
 
-// TODO(caspervonb) add support for lcov output (see geninfo(1) for format spec).
-impl PrettyCoverageReporter {
-  pub fn new(quiet: bool) -> PrettyCoverageReporter {
-    PrettyCoverageReporter { quiet }
-  }
-
-  pub fn visit_coverage(&mut self, coverage: &Coverage) {
-    let lines = coverage.script_source.lines().collect::<Vec<_>>();
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionReport {
+  pub name: String,
+  /// Index of the line where the function starts.
+  pub line_index: usize,
+  pub hits: usize,
+}
 
-    let mut covered_lines: Vec<usize> = Vec::new();
-    let mut uncovered_lines: Vec<usize> = Vec::new();
+/// A branch of a point where control flow splits, such as the consequent of
+/// an `if` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchReport {
+  /// Index of the line where the branch starts.
+  pub line_index: usize,
+  /// Index of the branch point, shared by its branches.
+  pub block: usize,
+  pub hits: usize,
+}
 
-    let mut line_start_offset = 0;
-    for (index, line) in lines.iter().enumerate() {
-      let line_end_offset = line_start_offset + line.len();
+impl CoverageReport {
+  /// Compute the report of a script, in terms of its original source when it
+  /// was emitted by the compiler. The code the compiler generated, such as
+  /// helpers, is left out then.
+  pub fn new(coverage: &Coverage) -> Self {
+    let ranges = coverage
+      .script_coverage
+      .functions
+      .iter()
+      .flat_map(|function| function.ranges.iter())
+      .collect::<Vec<_>>();
+    let script_lines = split_lines(&coverage.script_source);
+    let maybe_source_map =
+      match (&coverage.source_map, &coverage.original_source) {
+        (Some(source_map), Some(original_source)) => {
+          SourceMap::from_slice(source_map.as_bytes())
+            .ok()
+            .map(|source_map| (source_map, original_source))
+        }
+        _ => None,
+      };
 
-      let mut count = 0;
-      for function in &coverage.script_coverage.functions {
-        for range in &function.ranges {
-          if range.start_offset <= line_start_offset
-            && range.end_offset >= line_end_offset
-          {
-            if range.count == 0 {
-              count = 0;
-              break;
+    let lines = match &maybe_source_map {
+      Some((source_map, original_source)) => {
+        original_lines(original_source, source_map, &script_lines, &ranges)
+      }
+      None => script_lines
+        .iter()
+        .map(|(line_start, text)| {
+          let hits = code_range(text).map(|(start, end)| {
+            let (start, end) = (line_start + start, line_start + end);
+            // The innermost range that contains the start of the code,
+            // unless some of the code of the line was never run.
+            let uncovered = ranges.iter().any(|range| {
+              range.count == 0
+                && range.start_offset < end
+                && range.end_offset > start
+            });
+            if uncovered {
+              0
+            } else {
+              count_at(&ranges, start)
             }
+          });
+          (text.to_string(), hits)
+        })
+        .collect(),
+    };
 
-            count += range.count;
-          }
+    // The line of the report an offset of the script is on, unless it's in
+    // code generated by the compiler.
+    let line_index_at = |offset: usize| {
+      let script_line_index = match script_lines
+        .binary_search_by_key(&offset, |(line_start, _)| *line_start)
+      {
+        Ok(index) => index,
+        Err(index) => index - 1,
+      };
+      match &maybe_source_map {
+        Some((source_map, _)) => {
+          let column = offset - script_lines[script_line_index].0;
+          source_map
+            .lookup_token(script_line_index as u32, column as u32)
+            .filter(|token| token.get_src_id() == 0)
+            .map(|token| token.get_src_line() as usize)
         }
+        None => Some(script_line_index),
+      }
+    };
+
+    // V8 only reports the blocks that didn't run as often as the enclosing
+    // code, so the branches are found in the script. A branch without code
+    // ran whenever the others didn't.
+    let branch_points =
+      ModuleSpecifier::resolve_url_or_path(&coverage.script_coverage.url)
+        .ok()
+        .and_then(|specifier| {
+          ast::parse(
+            &specifier,
+            &coverage.script_source,
+            &MediaType::JavaScript,
+          )
+          .ok()
+        })
+        .map(|parsed_module| parsed_module.get_branch_points())
+        .unwrap_or_default();
+    let utf16_offset = utf16_offset_of(&coverage.script_source);
+    let mut branches = Vec::new();
+    let mut block = 0;
+    for branch_point in branch_points {
+      let branch_point_offset = utf16_offset(branch_point.offset);
+      let line_index = match line_index_at(branch_point_offset) {
+        Some(line_index) => line_index,
+        None => continue,
+      };
+      let mut branches_hits = 0;
+      for offset in branch_point.branch_offsets {
+        let offset = utf16_offset(offset);
+        let hits = count_at(&ranges, offset);
+        branches_hits += hits;
+        branches.push(BranchReport {
+          line_index: line_index_at(offset).unwrap_or(line_index),
+          block,
+          hits,
+        });
+      }
+      if branch_point.has_implicit_branch {
+        let hits = count_at(&ranges, branch_point_offset);
+        branches.push(BranchReport {
+          line_index,
+          block,
+          hits: hits.saturating_sub(branches_hits),
+        });
+      }
+      block += 1;
+    }
 
-        line_start_offset = line_end_offset;
+    let mut functions = Vec::new();
+    for (index, function) in
+      coverage.script_coverage.functions.iter().enumerate()
+    {
+      let range = match function.ranges.first() {
+        Some(range) => range,
+        None => continue,
+      };
+      // The top level code of the script is reported as a function.
+      if index == 0
+        && range.start_offset == 0
+        && function.function_name.is_empty()
+      {
+        continue;
       }
-      if count > 0 {
-        covered_lines.push(index);
+      let name = if function.function_name.is_empty() {
+        format!("(anonymous_{})", index)
       } else {
-        uncovered_lines.push(index);
+        function.function_name.clone()
+      };
+      let line_index = match line_index_at(range.start_offset) {
+        Some(line_index) => line_index,
+        None => continue,
+      };
+      functions.push(FunctionReport {
+        name,
+        line_index,
+        hits: range.count,
+      });
+    }
+
+    Self {
+      url: coverage.script_coverage.url.clone(),
+      lines,
+      functions,
+      branches,
+    }
+  }
+
+  /// Add the hit counts of `other`, a report of the same script from another
+  /// run. If the script changed in between, only the report of the latest
+  /// run is kept.
+  pub fn merge(&mut self, other: CoverageReport) {
+    let same_source = self.lines.len() == other.lines.len()
+      && self.branches.len() == other.branches.len()
+      && self
+        .lines
+        .iter()
+        .zip(&other.lines)
+        .all(|((text, _), (other_text, _))| text == other_text);
+    if !same_source {
+      *self = other;
+      return;
+    }
+    for ((_, hits), (_, other_hits)) in self.lines.iter_mut().zip(other.lines) {
+      if let (Some(hits), Some(other_hits)) = (hits.as_mut(), other_hits) {
+        *hits += other_hits;
+      }
+    }
+    for other_function in other.functions {
+      match self.functions.iter_mut().find(|function| {
+        function.name == other_function.name
+          && function.line_index == other_function.line_index
+      }) {
+        Some(function) => function.hits += other_function.hits,
+        None => self.functions.push(other_function),
       }
     }
+    // The branches were found in the same source, in the same order.
+    for (branch, other_branch) in self.branches.iter_mut().zip(other.branches) {
+      branch.hits += other_branch.hits;
+    }
+  }
 
-    if !self.quiet {
-      print!("cover {} ... ", coverage.script_coverage.url);
+  /// The number of lines that contain code, and of those that ran.
+  pub fn line_counts(&self) -> (usize, usize) {
+    let code_lines = self.lines.iter().filter_map(|(_, hits)| *hits);
+    let total = code_lines.clone().count();
+    let covered = code_lines.filter(|hits| *hits > 0).count();
+    (total, covered)
+  }
 
-      let line_coverage_ratio = covered_lines.len() as f32 / lines.len() as f32;
-      let line_coverage = format!(
-        "{:.3}% ({}/{})",
-        line_coverage_ratio * 100.0,
-        covered_lines.len(),
-        lines.len()
-      );
+  /// The number of branches, and of those that ran.
+  pub fn branch_counts(&self) -> (usize, usize) {
+    let total = self.branches.len();
+    let covered = self.branches.iter().filter(|branch| branch.hits > 0);
+    (total, covered.count())
+  }
 
-      if line_coverage_ratio >= 0.9 {
-        println!("{}", colors::green(&line_coverage));
-      } else if line_coverage_ratio >= 0.75 {
-        println!("{}", colors::yellow(&line_coverage));
-      } else {
-        println!("{}", colors::red(&line_coverage));
+  /// The local path of the script, or its URL.
+  pub fn file_name(&self) -> String {
+    match Url::parse(&self.url) {
+      Ok(url) if url.scheme() == "file" => match url.to_file_path() {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(_) => self.url.clone(),
+      },
+      _ => self.url.clone(),
+    }
+  }
+}
+
+/// Split a source into lines, along with the offsets of their start in UTF-16
+/// code units, which V8 offsets are in.
+fn split_lines(source: &str) -> Vec<(usize, &str)> {
+  let mut lines = Vec::new();
+  let mut offset = 0;
+  for line in source.split('\n') {
+    lines.push((offset, line.strip_suffix('\r').unwrap_or(line)));
+    offset += line.encode_utf16().count() + 1;
+  }
+  if source.ends_with('\n') {
+    lines.pop();
+  }
+  lines
+}
+
+/// Convert the byte offsets of a source, which the AST has, to offsets in UTF-16
+/// code units.
+fn utf16_offset_of(source: &str) -> impl Fn(usize) -> usize {
+  // The ends of the characters that take fewer UTF-16 code units than bytes,
+  // with how many fewer units the source takes up to there.
+  let mut shifts = Vec::new();
+  let mut shift = 0;
+  for (offset, c) in source.char_indices() {
+    if c.len_utf8() != c.len_utf16() {
+      shift += c.len_utf8() - c.len_utf16();
+      shifts.push((offset + c.len_utf8(), shift));
+    }
+  }
+  move |offset| match shifts.binary_search_by_key(&offset, |(end, _)| *end) {
+    Ok(index) => offset - shifts[index].1,
+    Err(0) => offset,
+    Err(index) => offset - shifts[index - 1].1,
+  }
+}
+
+/// The hit count of the innermost range that contains an offset.
+fn count_at(ranges: &[&CoverageRange], offset: usize) -> usize {
+  ranges
+    .iter()
+    .filter(|range| range.start_offset <= offset && range.end_offset > offset)
+    .min_by_key(|range| range.end_offset - range.start_offset)
+    .map_or(0, |range| range.count)
+}
+
+/// The lines of the original source of an emitted script. A line is as
+/// covered as the least covered code that was emitted from it, and lines
+/// nothing was emitted from don't contain code.
+fn original_lines(
+  original_source: &str,
+  source_map: &SourceMap,
+  script_lines: &[(usize, &str)],
+  ranges: &[&CoverageRange],
+) -> Vec<(String, Option<usize>)> {
+  let mut lines = split_lines(original_source)
+    .into_iter()
+    .map(|(_, text)| (text.to_string(), None))
+    .collect::<Vec<(String, Option<usize>)>>();
+  for token in source_map.tokens() {
+    if token.get_src_id() != 0 {
+      continue;
+    }
+    let line_start = match script_lines.get(token.get_dst_line() as usize) {
+      Some((line_start, _)) => *line_start,
+      None => continue,
+    };
+    let line = match lines.get_mut(token.get_src_line() as usize) {
+      Some(line) => line,
+      None => continue,
+    };
+    if code_range(&line.0).is_none() {
+      continue;
+    }
+    let count = count_at(ranges, line_start + token.get_dst_col() as usize);
+    line.1 = Some(line.1.map_or(count, |hits| hits.min(count)));
+  }
+  lines
+}
+
+/// Offsets of the first and past the last non-whitespace characters of a
+/// line, in UTF-16 code units.
+fn code_range(line: &str) -> Option<(usize, usize)> {
+  let trimmed = line.trim();
+  if trimmed.is_empty() {
+    return None;
+  }
+  let start = line[..line.len() - line.trim_start().len()]
+    .encode_utf16()
+    .count();
+  Some((start, start + trimmed.encode_utf16().count()))
+}
+
+/// Sum the counts of several reports.
+fn total_counts(
+  reports: &[CoverageReport],
+  counts: impl Fn(&CoverageReport) -> (usize, usize),
+) -> (usize, usize) {
+  reports.iter().fold((0, 0), |(total, covered), report| {
+    let (report_total, report_covered) = counts(report);
+    (total + report_total, covered + report_covered)
+  })
+}
+
+fn coverage_ratio(total: usize, covered: usize) -> f64 {
+  if total == 0 {
+    1.0
+  } else {
+    covered as f64 / total as f64
+  }
+}
+
+/// Write the profiles collected during a run to a new file of `dir`, to be
+/// reported by `deno coverage`.
+pub fn save_coverages(
+  dir: &Path,
+  coverages: &[Coverage],
+) -> Result<PathBuf, AnyError> {
+  fs::create_dir_all(dir)?;
+  let path = dir.join(format!("{}.json", Uuid::new_v4()));
+  fs::write(&path, serde_json::to_string(coverages)?)?;
+  Ok(path)
+}
+
+/// Read the profiles saved in `dir` and merge those of the same script,
+/// oldest run first.
+pub fn load_coverage_reports(
+  dir: &Path,
+) -> Result<Vec<CoverageReport>, AnyError> {
+  let mut files = Vec::new();
+  for entry in fs::read_dir(dir)? {
+    let path = entry?.path();
+    if path.extension().map_or(false, |ext| ext == "json") {
+      let modified = fs::metadata(&path)?.modified()?;
+      files.push((modified, path));
+    }
+  }
+  if files.is_empty() {
+    return Err(generic_error(format!(
+      "No coverage profiles found in {}",
+      dir.to_string_lossy()
+    )));
+  }
+  files.sort();
+
+  let mut coverages = Vec::new();
+  for (_, path) in files {
+    let file_coverages: Vec<Coverage> =
+      serde_json::from_str(&fs::read_to_string(&path)?).map_err(|err| {
+        generic_error(format!(
+          "Invalid coverage profile {}: {}",
+          path.to_string_lossy(),
+          err
+        ))
+      })?;
+    coverages.extend(file_coverages);
+  }
+  Ok(create_coverage_reports(&coverages))
+}
+
+/// Create the reports of `coverages`, merging those of the same script, such
+/// as the profiles collected by different workers.
+pub fn create_coverage_reports(coverages: &[Coverage]) -> Vec<CoverageReport> {
+  let mut reports: Vec<CoverageReport> = Vec::new();
+  for coverage in coverages {
+    let report = CoverageReport::new(coverage);
+    match reports
+      .iter_mut()
+      .find(|existing| existing.url == report.url)
+    {
+      Some(existing) => existing.merge(report),
+      None => reports.push(report),
+    }
+  }
+  reports.sort_by(|a, b| a.url.cmp(&b.url));
+  reports
+}
+
+/// Minimum percentages of covered lines and branches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CoverageThreshold {
+  pub lines: Option<f64>,
+  pub branches: Option<f64>,
+  /// Whether every script must meet the thresholds, rather than all of them
+  /// together.
+  pub per_file: bool,
+}
+
+impl std::str::FromStr for CoverageThreshold {
+  type Err = String;
+
+  /// Parse thresholds like `lines:80,branches:70`.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut threshold = CoverageThreshold::default();
+    for part in s.split(',') {
+      let mut split = part.splitn(2, ':');
+      let kind = split.next().unwrap_or_default();
+      let value = match split.next().map(str::parse::<f64>) {
+        Some(Ok(value)) if (0.0..=100.0).contains(&value) => value,
+        _ => return Err(format!("Invalid coverage threshold: {}", part)),
+      };
+      match kind {
+        "lines" => threshold.lines = Some(value),
+        "branches" => threshold.branches = Some(value),
+        _ => return Err(format!("Unknown coverage threshold: {}", kind)),
       }
+    }
+    Ok(threshold)
+  }
+}
+
+/// Check the coverage of the reports against a threshold, returning a message
+/// for every coverage below it.
+pub fn check_coverage_threshold(
+  reports: &[CoverageReport],
+  threshold: &CoverageThreshold,
+) -> Vec<String> {
+  let mut failures = Vec::new();
+  let mut check = |kind: &str,
+                   maybe_url: Option<&str>,
+                   (total, covered): (usize, usize),
+                   maybe_minimum: Option<f64>| {
+    let minimum = match maybe_minimum {
+      Some(minimum) => minimum,
+      None => return,
+    };
+    let percentage = coverage_ratio(total, covered) * 100.0;
+    if percentage < minimum {
+      let subject = match maybe_url {
+        Some(url) => format!("{} coverage of {}", kind, url),
+        None => format!("{} coverage", kind),
+      };
+      failures.push(format!(
+        "{} is {:.3}% ({}/{}), below the threshold of {}%",
+        subject, percentage, covered, total, minimum
+      ));
+    }
+  };
+
+  if threshold.per_file {
+    for report in reports {
+      check(
+        "Line",
+        Some(&report.url),
+        report.line_counts(),
+        threshold.lines,
+      );
+      check(
+        "Branch",
+        Some(&report.url),
+        report.branch_counts(),
+        threshold.branches,
+      );
+    }
+  } else {
+    let line_counts = total_counts(reports, |report| report.line_counts());
+    check("Line", None, line_counts, threshold.lines);
+    let branch_counts = total_counts(reports, |report| report.branch_counts());
+    check("Branch", None, branch_counts, threshold.branches);
+  }
+  failures
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoverageReporterKind {
+  Pretty,
+  Lcov,
+  Cobertura,
+  Html,
+}
+
+impl std::str::FromStr for CoverageReporterKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "pretty" => Ok(CoverageReporterKind::Pretty),
+      "lcov" => Ok(CoverageReporterKind::Lcov),
+      "cobertura" => Ok(CoverageReporterKind::Cobertura),
+      "html" => Ok(CoverageReporterKind::Html),
+      _ => Err(format!("Unknown coverage reporter: {}", s)),
+    }
+  }
+}
+
+pub trait CoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> io::Result<()>;
+  fn done(&mut self) -> io::Result<()>;
+}
+
+/// Create a reporter writing to the file at `maybe_output`, or to stdout. The
+/// HTML reporter writes to the directory at `maybe_output`, which it requires.
+pub fn create_coverage_reporter(
+  kind: CoverageReporterKind,
+  maybe_output: Option<PathBuf>,
+  quiet: bool,
+) -> Result<Box<dyn CoverageReporter>, AnyError> {
+  if kind == CoverageReporterKind::Html {
+    let dir = maybe_output.ok_or_else(|| {
+      generic_error("The HTML coverage reporter requires an output directory")
+    })?;
+    fs::create_dir_all(&dir)?;
+    return Ok(Box::new(HtmlCoverageReporter {
+      dir,
+      pages: Vec::new(),
+    }));
+  }
+  let out: Box<dyn Write> = match maybe_output {
+    Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+    None => Box::new(io::stdout()),
+  };
+  let reporter: Box<dyn CoverageReporter> = match kind {
+    CoverageReporterKind::Pretty => {
+      Box::new(PrettyCoverageReporter { out, quiet })
+    }
+    CoverageReporterKind::Lcov => Box::new(LcovCoverageReporter { out }),
+    CoverageReporterKind::Cobertura => Box::new(CoberturaCoverageReporter {
+      out,
+      reports: Vec::new(),
+    }),
+    CoverageReporterKind::Html => unreachable!(),
+  };
+  Ok(reporter)
+}
+
+/// Prints the line and branch coverage of every script, followed by its
+/// uncovered lines.
+pub struct PrettyCoverageReporter {
+  out: Box<dyn Write>,
+  quiet: bool,
+}
 
-      for line_index in uncovered_lines {
-        println!(
+impl PrettyCoverageReporter {
+  pub fn new(quiet: bool) -> PrettyCoverageReporter {
+    PrettyCoverageReporter {
+      out: Box::new(io::stdout()),
+      quiet,
+    }
+  }
+}
+
+impl CoverageReporter for PrettyCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> io::Result<()> {
+    if self.quiet {
+      return Ok(());
+    }
+    let (total, covered) = report.line_counts();
+    let line_coverage = format_coverage(total, covered);
+    let (total, covered) = report.branch_counts();
+    if total > 0 {
+      writeln!(
+        self.out,
+        "cover {} ... {}, branches {}",
+        report.url,
+        line_coverage,
+        format_coverage(total, covered)
+      )?;
+    } else {
+      writeln!(self.out, "cover {} ... {}", report.url, line_coverage)?;
+    }
+
+    for (index, (text, hits)) in report.lines.iter().enumerate() {
+      if *hits == Some(0) {
+        writeln!(
+          self.out,
           "{:width$}{} {}",
-          line_index + 1,
+          index + 1,
           colors::gray(" |"),
-          colors::red(&lines[line_index]),
+          colors::red(text),
           width = 4
-        );
+        )?;
+      }
+    }
+    Ok(())
+  }
+
+  fn done(&mut self) -> io::Result<()> {
+    self.out.flush()
+  }
+}
+
+fn format_coverage(total: usize, covered: usize) -> String {
+  let ratio = coverage_ratio(total, covered);
+  let coverage = format!("{:.3}% ({}/{})", ratio * 100.0, covered, total);
+  if ratio >= 0.9 {
+    colors::green(&coverage).to_string()
+  } else if ratio >= 0.75 {
+    colors::yellow(&coverage).to_string()
+  } else {
+    colors::red(&coverage).to_string()
+  }
+}
+
+/// Writes a tracefile in the LCOV format described in geninfo(1).
+struct LcovCoverageReporter {
+  out: Box<dyn Write>,
+}
+
+impl CoverageReporter for LcovCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> io::Result<()> {
+    writeln!(self.out, "SF:{}", report.file_name())?;
+    for function in &report.functions {
+      writeln!(self.out, "FN:{},{}", function.line_index + 1, function.name)?;
+    }
+    for function in &report.functions {
+      writeln!(self.out, "FNDA:{},{}", function.hits, function.name)?;
+    }
+    let functions_hit =
+      report.functions.iter().filter(|function| function.hits > 0);
+    writeln!(self.out, "FNF:{}", report.functions.len())?;
+    writeln!(self.out, "FNH:{}", functions_hit.count())?;
+    // Branches are numbered within their block.
+    let mut index = 0;
+    for (i, branch) in report.branches.iter().enumerate() {
+      if i > 0 && report.branches[i - 1].block != branch.block {
+        index = 0;
       }
+      writeln!(
+        self.out,
+        "BRDA:{},{},{},{}",
+        branch.line_index + 1,
+        branch.block,
+        index,
+        branch.hits
+      )?;
+      index += 1;
+    }
+    let (total, covered) = report.branch_counts();
+    writeln!(self.out, "BRF:{}", total)?;
+    writeln!(self.out, "BRH:{}", covered)?;
+    for (index, (_, hits)) in report.lines.iter().enumerate() {
+      if let Some(hits) = hits {
+        writeln!(self.out, "DA:{},{}", index + 1, hits)?;
+      }
+    }
+    let (total, covered) = report.line_counts();
+    writeln!(self.out, "LH:{}", covered)?;
+    writeln!(self.out, "LF:{}", total)?;
+    writeln!(self.out, "end_of_record")
+  }
+
+  fn done(&mut self) -> io::Result<()> {
+    self.out.flush()
+  }
+}
+
+/// Writes a Cobertura XML document once all scripts have been visited, as
+/// it starts with the totals.
+struct CoberturaCoverageReporter {
+  out: Box<dyn Write>,
+  reports: Vec<CoverageReport>,
+}
+
+impl CoverageReporter for CoberturaCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> io::Result<()> {
+    self.reports.push(report.clone());
+    Ok(())
+  }
+
+  fn done(&mut self) -> io::Result<()> {
+    let (total, covered) =
+      total_counts(&self.reports, |report| report.line_counts());
+    let (branches_total, branches_covered) =
+      total_counts(&self.reports, |report| report.branch_counts());
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |duration| duration.as_secs());
+    let line_rate = coverage_ratio(total, covered);
+    let branch_rate = coverage_ratio(branches_total, branches_covered);
+
+    writeln!(self.out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+      self.out,
+      r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    )?;
+    writeln!(
+      self.out,
+      r#"<coverage line-rate="{:.4}" branch-rate="{:.4}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="{}" timestamp="{}">"#,
+      line_rate,
+      branch_rate,
+      covered,
+      total,
+      branches_covered,
+      branches_total,
+      crate::version::DENO,
+      timestamp,
+    )?;
+    writeln!(self.out, "  <sources/>")?;
+    writeln!(self.out, "  <packages>")?;
+    writeln!(
+      self.out,
+      r#"    <package name="deno" line-rate="{:.4}" branch-rate="{:.4}" complexity="0">"#,
+      line_rate, branch_rate
+    )?;
+    writeln!(self.out, "      <classes>")?;
+    for report in &self.reports {
+      let (total, covered) = report.line_counts();
+      let (branches_total, branches_covered) = report.branch_counts();
+      let file_name = xml_escape(&report.file_name());
+      writeln!(
+        self.out,
+        r#"        <class name="{}" filename="{}" line-rate="{:.4}" branch-rate="{:.4}" complexity="0">"#,
+        file_name,
+        file_name,
+        coverage_ratio(total, covered),
+        coverage_ratio(branches_total, branches_covered),
+      )?;
+      writeln!(self.out, "          <methods>")?;
+      for function in &report.functions {
+        writeln!(
+          self.out,
+          r#"            <method name="{}" signature="" line-rate="{}" branch-rate="0">"#,
+          xml_escape(&function.name),
+          if function.hits > 0 { 1 } else { 0 },
+        )?;
+        writeln!(
+          self.out,
+          r#"              <lines><line number="{}" hits="{}"/></lines>"#,
+          function.line_index + 1,
+          function.hits
+        )?;
+        writeln!(self.out, "            </method>")?;
+      }
+      writeln!(self.out, "          </methods>")?;
+      writeln!(self.out, "          <lines>")?;
+      for (index, (_, hits)) in report.lines.iter().enumerate() {
+        let hits = match hits {
+          Some(hits) => hits,
+          None => continue,
+        };
+        let branches = report
+          .branches
+          .iter()
+          .filter(|branch| branch.line_index == index);
+        let branches_total = branches.clone().count();
+        if branches_total == 0 {
+          writeln!(
+            self.out,
+            r#"            <line number="{}" hits="{}" branch="false"/>"#,
+            index + 1,
+            hits
+          )?;
+        } else {
+          let branches_covered =
+            branches.filter(|branch| branch.hits > 0).count();
+          writeln!(
+            self.out,
+            r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+            index + 1,
+            hits,
+            branches_covered * 100 / branches_total,
+            branches_covered,
+            branches_total
+          )?;
+        }
+      }
+      writeln!(self.out, "          </lines>")?;
+      writeln!(self.out, "        </class>")?;
+    }
+    writeln!(self.out, "      </classes>")?;
+    writeln!(self.out, "    </package>")?;
+    writeln!(self.out, "  </packages>")?;
+    writeln!(self.out, "</coverage>")?;
+    self.out.flush()
+  }
+}
+
+static HTML_STYLES: &str = r#"body {
+  margin: 0;
+  padding: 1rem 2rem;
+  font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial,
+    sans-serif;
+  color: #24292e;
+}
+table {
+  border-collapse: collapse;
+}
+th,
+td {
+  padding: 0.25rem 0.75rem;
+  text-align: left;
+}
+pre {
+  margin: 0;
+  font-family: SFMono-Regular, Consolas, Menlo, monospace;
+  font-size: 0.9em;
+}
+.source td {
+  padding: 0 0.75rem;
+}
+.line-number,
+.hits {
+  color: #6a737d;
+  text-align: right;
+}
+.covered {
+  background: #e6ffed;
+}
+.uncovered {
+  background: #ffeef0;
+}
+"#;
+
+/// Writes a static site to a directory, with an index of the scripts and a
+/// page per script showing the hit count of its lines.
+struct HtmlCoverageReporter {
+  dir: PathBuf,
+  pages: Vec<HtmlPage>,
+}
+
+struct HtmlPage {
+  file_name: String,
+  url: String,
+  line_counts: (usize, usize),
+  branch_counts: (usize, usize),
+}
+
+fn render_html_page(title: &str, body: &str) -> String {
+  format!(
+    r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{}</title>
+<style>
+{}</style>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+    html_escape(title),
+    HTML_STYLES,
+    body
+  )
+}
+
+impl CoverageReporter for HtmlCoverageReporter {
+  fn visit_report(&mut self, report: &CoverageReport) -> io::Result<()> {
+    let page = HtmlPage {
+      file_name: format!("{}.html", self.pages.len()),
+      url: report.url.clone(),
+      line_counts: report.line_counts(),
+      branch_counts: report.branch_counts(),
+    };
+    let (total, covered) = page.line_counts;
+    let (branches_total, branches_covered) = page.branch_counts;
+    let mut body = format!(
+      "<p><a href=\"index.html\">All files</a></p>\n<h1>{}</h1>\n<p>{:.3}% ({}/{}) of the lines and {:.3}% ({}/{}) of the branches covered</p>\n<table class=\"source\">\n",
+      html_escape(&report.url),
+      coverage_ratio(total, covered) * 100.0,
+      covered,
+      total,
+      coverage_ratio(branches_total, branches_covered) * 100.0,
+      branches_covered,
+      branches_total
+    );
+    for (index, (text, hits)) in report.lines.iter().enumerate() {
+      let (class, hits) = match hits {
+        Some(0) => ("uncovered", "0".to_string()),
+        Some(hits) => ("covered", format!("{}x", hits)),
+        None => ("", String::new()),
+      };
+      let branches = report
+        .branches
+        .iter()
+        .filter(|branch| branch.line_index == index);
+      let branches_total = branches.clone().count();
+      let branches = if branches_total > 0 {
+        let covered = branches.filter(|branch| branch.hits > 0).count();
+        format!("{}/{}", covered, branches_total)
+      } else {
+        String::new()
+      };
+      body.push_str(&format!(
+        "<tr class=\"{}\"><td class=\"line-number\">{}</td><td class=\"hits\">{}</td><td class=\"hits\">{}</td><td><pre>{}</pre></td></tr>\n",
+        class,
+        index + 1,
+        hits,
+        branches,
+        html_escape(text)
+      ));
+    }
+    body.push_str("</table>\n");
+    fs::write(
+      self.dir.join(&page.file_name),
+      render_html_page(&report.url, &body),
+    )?;
+    self.pages.push(page);
+    Ok(())
+  }
+
+  fn done(&mut self) -> io::Result<()> {
+    let mut body = String::from(
+      "<h1>Coverage</h1>\n<table>\n<tr><th>File</th><th>Lines</th><th>Covered</th><th>Branches</th><th>Covered</th></tr>\n",
+    );
+    for page in &self.pages {
+      let (total, covered) = page.line_counts;
+      let (branches_total, branches_covered) = page.branch_counts;
+      body.push_str(&format!(
+        "<tr><td><a href=\"{}\">{}</a></td><td>{:.3}%</td><td>{}/{}</td><td>{:.3}%</td><td>{}/{}</td></tr>\n",
+        page.file_name,
+        html_escape(&page.url),
+        coverage_ratio(total, covered) * 100.0,
+        covered,
+        total,
+        coverage_ratio(branches_total, branches_covered) * 100.0,
+        branches_covered,
+        branches_total
+      ));
+    }
+    body.push_str("</table>\n");
+    fs::write(
+      self.dir.join("index.html"),
+      render_html_page("Coverage", &body),
+    )
+  }
+}
+
+/// Attach the source maps and original sources of the scripts emitted by the
+/// compiler, for their reports to refer to the lines of the original sources.
+pub fn attach_source_maps(
+  global_state: &GlobalState,
+  coverages: &mut [Coverage],
+) {
+  for coverage in coverages {
+    let specifier =
+      match ModuleSpecifier::resolve_url(&coverage.script_coverage.url) {
+        Ok(specifier) => specifier,
+        Err(_) => continue,
+      };
+    let source_map_file =
+      match global_state.ts_compiler.get_source_map_file(&specifier) {
+        Ok(source_map_file) => source_map_file,
+        Err(_) => continue,
+      };
+    let original_file = match global_state
+      .file_fetcher
+      .fetch_cached_source_file(&specifier, Permissions::allow_all())
+    {
+      Some(original_file) => original_file,
+      None => continue,
+    };
+    if let (Ok(source_map), Ok(original_source)) = (
+      source_map_file.source_code.to_string(),
+      original_file.source_code.to_string(),
+    ) {
+      coverage.source_map = Some(source_map);
+      coverage.original_source = Some(original_source);
     }
   }
 }
 
+/// Keep the coverage of the local modules of `deno run`, leaving out the
+/// scripts it evaluates itself.
+pub fn filter_run_coverages(coverages: Vec<Coverage>) -> Vec<Coverage> {
+  coverages
+    .into_iter()
+    .filter(|e| match Url::parse(&e.script_coverage.url) {
+      Ok(url) => {
+        url.scheme() == "file" && !url.path().ends_with("__anonymous__")
+      }
+      Err(_) => false,
+    })
+    .collect()
+}
+
 pub fn filter_script_coverages(
   coverages: Vec<Coverage>,
   test_file_url: Url,
@@ -250,3 +1219,320 @@ pub fn filter_script_coverages(
     })
     .collect::<Vec<Coverage>>()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str =
+    "function a() {\n  return 1;\n}\nfunction b() {\n  return 2;\n}\na();\n";
+
+  fn coverage(b_count: usize) -> Coverage {
+    serde_json::from_value(json!({
+      "scriptCoverage": {
+        "scriptId": "1",
+        "url": "file:///mod.js",
+        "functions": [
+          {
+            "functionName": "",
+            "ranges": [{ "startOffset": 0, "endOffset": 63, "count": 1 }],
+            "isBlockCoverage": true,
+          },
+          {
+            "functionName": "a",
+            "ranges": [{ "startOffset": 0, "endOffset": 28, "count": 1 }],
+            "isBlockCoverage": true,
+          },
+          {
+            "functionName": "b",
+            "ranges": [{ "startOffset": 29, "endOffset": 57, "count": b_count }],
+            "isBlockCoverage": true,
+          },
+        ],
+      },
+      "scriptSource": SOURCE,
+    }))
+    .unwrap()
+  }
+
+  fn hits(report: &CoverageReport) -> Vec<Option<usize>> {
+    report.lines.iter().map(|(_, hits)| *hits).collect()
+  }
+
+  #[test]
+  fn test_coverage_report() {
+    let report = CoverageReport::new(&coverage(0));
+    assert_eq!(report.lines.len(), 7);
+    assert_eq!(
+      hits(&report),
+      vec![
+        Some(1),
+        Some(1),
+        Some(1),
+        Some(0),
+        Some(0),
+        Some(0),
+        Some(1)
+      ]
+    );
+    assert_eq!(report.line_counts(), (7, 4));
+    assert_eq!(
+      report.functions,
+      vec![
+        FunctionReport {
+          name: "a".to_string(),
+          line_index: 0,
+          hits: 1,
+        },
+        FunctionReport {
+          name: "b".to_string(),
+          line_index: 3,
+          hits: 0,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_coverage_report_merge() {
+    let mut report = CoverageReport::new(&coverage(0));
+    report.merge(CoverageReport::new(&coverage(1)));
+    assert_eq!(
+      hits(&report),
+      vec![
+        Some(2),
+        Some(2),
+        Some(2),
+        Some(1),
+        Some(1),
+        Some(1),
+        Some(2)
+      ]
+    );
+    assert_eq!(report.functions[0].hits, 2);
+    assert_eq!(report.functions[1].hits, 1);
+
+    let mut changed = coverage(1);
+    changed.script_source = SOURCE.replace("return 2", "return 3");
+    report.merge(CoverageReport::new(&changed));
+    assert_eq!(report, CoverageReport::new(&changed));
+  }
+
+  #[test]
+  fn test_coverage_report_source_map() {
+    let mut builder = sourcemap::SourceMapBuilder::new(None);
+    for (dst_line, dst_col, src_line, src_col) in &[
+      (1, 0, 0, 0),
+      (1, 9, 0, 9),
+      (2, 2, 1, 2),
+      (3, 0, 2, 0),
+      (4, 0, 4, 0),
+    ] {
+      builder.add(
+        *dst_line,
+        *dst_col,
+        *src_line,
+        *src_col,
+        Some("file:///mod.ts"),
+        None,
+      );
+    }
+    let mut source_map = Vec::new();
+    builder.into_sourcemap().to_writer(&mut source_map).unwrap();
+
+    // The helper on the first line of the emitted script is left out.
+    let coverage: Coverage = serde_json::from_value(json!({
+      "scriptCoverage": {
+        "scriptId": "1",
+        "url": "file:///mod.ts",
+        "functions": [
+          {
+            "functionName": "",
+            "ranges": [{ "startOffset": 0, "endOffset": 52, "count": 1 }],
+            "isBlockCoverage": true,
+          },
+          {
+            "functionName": "a",
+            "ranges": [{ "startOffset": 18, "endOffset": 46, "count": 2 }],
+            "isBlockCoverage": true,
+          },
+        ],
+      },
+      "scriptSource": "var __helper = 1;\nfunction a() {\n  return 1;\n}\na();\n",
+      "sourceMap": String::from_utf8(source_map).unwrap(),
+      "originalSource": "function a(): number {\n  return 1;\n}\ntype T = number;\na();\n",
+    }))
+    .unwrap();
+    let report = CoverageReport::new(&coverage);
+    assert_eq!(report.lines[0].0, "function a(): number {");
+    assert_eq!(
+      hits(&report),
+      vec![Some(2), Some(2), Some(2), None, Some(1)]
+    );
+    assert_eq!(
+      report.functions,
+      vec![FunctionReport {
+        name: "a".to_string(),
+        line_index: 0,
+        hits: 2,
+      }]
+    );
+  }
+
+  #[test]
+  fn test_coverage_report_branches() {
+    // The consequent of the `if` statement ran as often as the function the
+    // first time, so V8 only reports the code that follows it.
+    let source =
+      "function a(x) {\n  if (x) {\n    return 1;\n  }\n  return 2;\n}\na(true);\n";
+    let branch_coverage = |block: (usize, usize)| -> Coverage {
+      serde_json::from_value(json!({
+        "scriptCoverage": {
+          "scriptId": "1",
+          "url": "file:///mod.js",
+          "functions": [
+            {
+              "functionName": "",
+              "ranges": [{ "startOffset": 0, "endOffset": 68, "count": 1 }],
+              "isBlockCoverage": true,
+            },
+            {
+              "functionName": "a",
+              "ranges": [
+                { "startOffset": 0, "endOffset": 58, "count": 1 },
+                { "startOffset": block.0, "endOffset": block.1, "count": 0 },
+              ],
+              "isBlockCoverage": true,
+            },
+          ],
+        },
+        "scriptSource": source,
+      }))
+      .unwrap()
+    };
+    let mut report = CoverageReport::new(&branch_coverage((44, 57)));
+    assert_eq!(
+      report.branches,
+      vec![
+        BranchReport {
+          line_index: 1,
+          block: 0,
+          hits: 1,
+        },
+        BranchReport {
+          line_index: 1,
+          block: 0,
+          hits: 0,
+        },
+      ]
+    );
+    assert_eq!(report.branch_counts(), (2, 1));
+
+    // The consequent didn't run the second time.
+    report.merge(CoverageReport::new(&branch_coverage((25, 44))));
+    assert_eq!(report.branches[0].hits, 1);
+    assert_eq!(report.branches[1].hits, 1);
+    assert_eq!(report.branch_counts(), (2, 2));
+  }
+
+  #[test]
+  fn test_coverage_report_branches_utf16() {
+    // V8 offsets are in UTF-16 code units, each dinosaur taking two of them
+    // and four bytes. Neither the consequent of the `if` statement nor that
+    // of the conditional expression ran.
+    let source = format!(
+      "// {}\nfunction a(x) {{\n  if (x) {{\n    return 1;\n  }}\n  return x ? 2 : 3;\n}}\na(false);\n",
+      "🦕".repeat(20)
+    );
+    let coverage: Coverage = serde_json::from_value(json!({
+      "scriptCoverage": {
+        "scriptId": "1",
+        "url": "file:///mod.js",
+        "functions": [
+          {
+            "functionName": "",
+            "ranges": [{ "startOffset": 0, "endOffset": 121, "count": 1 }],
+            "isBlockCoverage": true,
+          },
+          {
+            "functionName": "a",
+            "ranges": [
+              { "startOffset": 44, "endOffset": 110, "count": 1 },
+              { "startOffset": 69, "endOffset": 88, "count": 0 },
+              { "startOffset": 102, "endOffset": 103, "count": 0 },
+            ],
+            "isBlockCoverage": true,
+          },
+        ],
+      },
+      "scriptSource": source,
+    }))
+    .unwrap();
+    let report = CoverageReport::new(&coverage);
+    let branch = |line_index, block, hits| BranchReport {
+      line_index,
+      block,
+      hits,
+    };
+    assert_eq!(
+      report.branches,
+      vec![
+        branch(2, 0, 0),
+        branch(2, 0, 1),
+        branch(5, 1, 0),
+        branch(5, 1, 1),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_coverage_threshold() {
+    assert_eq!(
+      "lines:80,branches:70.5".parse::<CoverageThreshold>(),
+      Ok(CoverageThreshold {
+        lines: Some(80.0),
+        branches: Some(70.5),
+        per_file: false,
+      })
+    );
+    assert!("lines".parse::<CoverageThreshold>().is_err());
+    assert!("lines:101".parse::<CoverageThreshold>().is_err());
+    assert!("functions:80".parse::<CoverageThreshold>().is_err());
+
+    let covered = CoverageReport::new(&coverage(1));
+    let mut uncovered = CoverageReport::new(&coverage(0));
+    uncovered.url = "file:///uncovered.js".to_string();
+    let reports = vec![covered, uncovered];
+    let mut threshold = CoverageThreshold {
+      lines: Some(75.0),
+      branches: Some(100.0),
+      per_file: false,
+    };
+    assert!(check_coverage_threshold(&reports, &threshold).is_empty());
+    threshold.per_file = true;
+    assert_eq!(
+      check_coverage_threshold(&reports, &threshold),
+      vec![
+        "Line coverage of file:///uncovered.js is 57.143% (4/7), below the threshold of 75%"
+      ]
+    );
+    threshold.lines = Some(80.0);
+    threshold.per_file = false;
+    assert_eq!(
+      check_coverage_threshold(&reports, &threshold),
+      vec!["Line coverage is 78.571% (11/14), below the threshold of 80%"]
+    );
+  }
+
+  #[test]
+  fn test_save_and_load_coverages() {
+    let dir = tempfile::TempDir::new().unwrap();
+    assert!(load_coverage_reports(dir.path()).is_err());
+    save_coverages(dir.path(), &[coverage(0)]).unwrap();
+    save_coverages(dir.path(), &[coverage(1)]).unwrap();
+    let reports = load_coverage_reports(dir.path()).unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].url, "file:///mod.js");
+    assert_eq!(reports[0].line_counts(), (7, 7));
+  }
+}
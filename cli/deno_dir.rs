@@ -43,6 +43,16 @@ impl DenoDir {
 
     Ok(deno_dir)
   }
+
+  /// Directory of the incremental caches of `deno fmt` results.
+  pub fn fmt_incremental_cache_dir(&self) -> PathBuf {
+    self.root.join("fmt_incremental_cache")
+  }
+
+  /// Directory of the incremental caches of `deno lint` results.
+  pub fn lint_incremental_cache_dir(&self) -> PathBuf {
+    self.root.join("lint_incremental_cache")
+  }
 }
 
 /// To avoid the poorly managed dirs crate
@@ -0,0 +1,942 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Static HTML documentation generated by `deno doc --html`.
+//!
+//! The site consists of an index page, one page per entry point, a search
+//! index and a stylesheet. It is rendered from the JSON representation of
+//! the doc nodes, the same data that `deno doc --json` prints. References to
+//! types are linked to the page documenting them, across entry points.
+
+use crate::escape::html_escape;
+use deno_core::error::AnyError;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::url::Url;
+use deno_doc as doc;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+static STYLES_CSS: &str = r#"body {
+  margin: 0;
+  font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial,
+    sans-serif;
+  color: #24292e;
+  line-height: 1.5;
+}
+header {
+  display: flex;
+  align-items: center;
+  justify-content: space-between;
+  padding: 0.75rem 2rem;
+  background: #24292e;
+}
+header a {
+  color: #fff;
+  font-weight: bold;
+  text-decoration: none;
+}
+#search-results {
+  margin: 0;
+  padding: 0 2rem;
+  list-style: none;
+}
+main {
+  max-width: 60rem;
+  padding: 1rem 2rem;
+}
+pre,
+code {
+  font-family: SFMono-Regular, Consolas, Menlo, monospace;
+  font-size: 0.9em;
+}
+pre {
+  padding: 0.75rem;
+  overflow-x: auto;
+  background: #f6f8fa;
+  border-radius: 3px;
+}
+.symbol {
+  margin-top: 2rem;
+  border-top: 1px solid #e1e4e8;
+}
+.member {
+  margin-left: 1.5rem;
+}
+.kind {
+  color: #6a737d;
+  font-weight: normal;
+}
+.location {
+  color: #6a737d;
+  font-size: 0.85em;
+}
+"#;
+
+static SEARCH_JS: &str = r#"(function () {
+  const input = document.getElementById("search");
+  const results = document.getElementById("search-results");
+  input.addEventListener("input", () => {
+    const query = input.value.trim().toLowerCase();
+    results.innerHTML = "";
+    if (query === "") {
+      return;
+    }
+    const matches = window.DENO_DOC_SEARCH_INDEX.filter((item) =>
+      item.name.toLowerCase().includes(query)
+    ).slice(0, 50);
+    for (const item of matches) {
+      const li = document.createElement("li");
+      const a = document.createElement("a");
+      a.href = item.href;
+      a.textContent = item.name;
+      const kind = document.createElement("span");
+      kind.className = "kind";
+      kind.textContent = " " + item.kind + " in " + item.module;
+      li.append(a, kind);
+      results.append(li);
+    }
+  });
+})();
+"#;
+
+/// The doc nodes of an entry point.
+pub struct DocModule {
+  pub specifier: String,
+  pub doc_nodes: Vec<doc::DocNode>,
+}
+
+struct Page {
+  title: String,
+  file_name: String,
+  nodes: Vec<Value>,
+}
+
+struct SearchEntry {
+  name: String,
+  kind: String,
+  module: String,
+  href: String,
+}
+
+/// Write the documentation site for `modules` to the `output` directory.
+pub fn generate(
+  modules: Vec<DocModule>,
+  output: &Path,
+) -> Result<(), AnyError> {
+  let cwd = std::env::current_dir()?;
+  let mut pages = Vec::new();
+  for module in modules {
+    let title = get_display_name(&module.specifier, &cwd);
+    let file_name = get_unique_file_name(&title, &pages);
+    let mut nodes = match serde_json::to_value(&module.doc_nodes)? {
+      Value::Array(nodes) => nodes,
+      _ => unreachable!(),
+    };
+    nodes.retain(|node| get_str(node, "kind") != "import");
+    sort_nodes(&mut nodes);
+    pages.push(Page {
+      title,
+      file_name,
+      nodes,
+    });
+  }
+
+  // Every symbol is addressed by its qualified name, which is also the id of
+  // its section. The first entry point defining a name wins.
+  let mut links = HashMap::new();
+  let mut search_index = Vec::new();
+  for page in &pages {
+    collect_symbols(page, &page.nodes, "", &mut links, &mut search_index);
+  }
+
+  fs::create_dir_all(output)?;
+  fs::write(output.join("styles.css"), STYLES_CSS)?;
+  fs::write(output.join("search.js"), SEARCH_JS)?;
+  let search_index = search_index
+    .iter()
+    .map(|entry| {
+      json!({
+        "name": entry.name,
+        "kind": entry.kind,
+        "module": entry.module,
+        "href": entry.href,
+      })
+    })
+    .collect::<Vec<_>>();
+  fs::write(
+    output.join("search_index.js"),
+    format!(
+      "window.DENO_DOC_SEARCH_INDEX = {};\n",
+      serde_json::to_string(&search_index)?
+    ),
+  )?;
+  fs::write(output.join("index.html"), render_index(&pages))?;
+  for page in &pages {
+    let renderer = Renderer {
+      links: &links,
+      file_name: &page.file_name,
+    };
+    fs::write(output.join(&page.file_name), renderer.render_page(page))?;
+  }
+  Ok(())
+}
+
+/// Paths of local modules are shown relative to the current directory.
+pub fn get_display_name(specifier: &str, cwd: &Path) -> String {
+  let url = match Url::parse(specifier) {
+    Ok(url) => url,
+    Err(_) => return specifier.to_string(),
+  };
+  if url.scheme() == "file" {
+    if let Ok(path) = url.to_file_path() {
+      if let Ok(relative) = path.strip_prefix(cwd) {
+        return relative.to_string_lossy().replace('\\', "/");
+      }
+      return path.to_string_lossy().replace('\\', "/");
+    }
+  }
+  specifier.to_string()
+}
+
+fn get_unique_file_name(title: &str, pages: &[Page]) -> String {
+  let slug = title
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect::<String>();
+  let mut file_name = format!("{}.html", slug);
+  let mut i = 1;
+  while file_name == "index.html"
+    || pages.iter().any(|page| page.file_name == file_name)
+  {
+    i += 1;
+    file_name = format!("{}_{}.html", slug, i);
+  }
+  file_name
+}
+
+fn collect_symbols(
+  page: &Page,
+  nodes: &[Value],
+  prefix: &str,
+  links: &mut HashMap<String, String>,
+  search_index: &mut Vec<SearchEntry>,
+) {
+  for node in nodes {
+    let name = format!("{}{}", prefix, get_str(node, "name"));
+    let href = format!("{}#{}", page.file_name, name);
+    links.entry(name.clone()).or_insert_with(|| href.clone());
+    search_index.push(SearchEntry {
+      name: name.clone(),
+      kind: get_kind_label(get_str(node, "kind")).to_string(),
+      module: page.title.clone(),
+      href,
+    });
+    if let Some(elements) = node["namespaceDef"]["elements"].as_array() {
+      let prefix = format!("{}.", name);
+      collect_symbols(page, elements, &prefix, links, search_index);
+    }
+  }
+}
+
+fn get_str<'a>(value: &'a Value, key: &str) -> &'a str {
+  value[key].as_str().unwrap_or("")
+}
+
+fn get_kind_label(kind: &str) -> &str {
+  match kind {
+    "typeAlias" => "type",
+    kind => kind,
+  }
+}
+
+fn get_kind_order(kind: &str) -> usize {
+  match kind {
+    "function" => 0,
+    "variable" => 1,
+    "class" => 2,
+    "enum" => 3,
+    "interface" => 4,
+    "typeAlias" => 5,
+    "namespace" => 6,
+    _ => 7,
+  }
+}
+
+fn get_kind_heading(kind: &str) -> &str {
+  match kind {
+    "function" => "Functions",
+    "variable" => "Variables",
+    "class" => "Classes",
+    "enum" => "Enums",
+    "interface" => "Interfaces",
+    "typeAlias" => "Type Aliases",
+    "namespace" => "Namespaces",
+    _ => "Other",
+  }
+}
+
+fn sort_nodes(nodes: &mut Vec<Value>) {
+  nodes.sort_by(|a, b| {
+    get_kind_order(get_str(a, "kind"))
+      .cmp(&get_kind_order(get_str(b, "kind")))
+      .then_with(|| get_str(a, "name").cmp(get_str(b, "name")))
+  });
+  for node in nodes.iter_mut() {
+    if let Some(elements) = node["namespaceDef"]["elements"].as_array_mut() {
+      sort_nodes(elements);
+    }
+  }
+}
+
+/// Render the markdown of a JSDoc comment. Raw HTML, both blocks and inline
+/// tags, is escaped and shown as text, as doc comments of third party
+/// modules can't be trusted to be embedded in the page.
+fn render_markdown(markdown: &str) -> String {
+  let parser = pulldown_cmark::Parser::new(markdown).map(|event| match event {
+    pulldown_cmark::Event::Html(html) => pulldown_cmark::Event::Text(html),
+    event => event,
+  });
+  let mut html = String::new();
+  pulldown_cmark::html::push_html(&mut html, parser);
+  html
+}
+
+fn render_layout(title: &str, content: &str) -> String {
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<link rel="stylesheet" href="styles.css">
+</head>
+<body>
+<header>
+<a href="index.html">Documentation</a>
+<input id="search" type="search" placeholder="Search" autocomplete="off">
+</header>
+<ul id="search-results"></ul>
+<main>
+{content}</main>
+<script src="search_index.js"></script>
+<script src="search.js"></script>
+</body>
+</html>
+"#,
+    title = html_escape(title),
+    content = content
+  )
+}
+
+fn render_index(pages: &[Page]) -> String {
+  let mut content = String::new();
+  content.push_str("<h1>Modules</h1>\n<ul>\n");
+  for page in pages {
+    writeln!(
+      content,
+      "<li><a href=\"{}\">{}</a></li>",
+      page.file_name,
+      html_escape(&page.title)
+    )
+    .unwrap();
+  }
+  content.push_str("</ul>\n");
+  render_layout("Documentation", &content)
+}
+
+struct Renderer<'a> {
+  links: &'a HashMap<String, String>,
+  file_name: &'a str,
+}
+
+impl<'a> Renderer<'a> {
+  fn render_page(&self, page: &Page) -> String {
+    let mut content = String::new();
+    writeln!(content, "<h1>{}</h1>", html_escape(&page.title)).unwrap();
+    content.push_str(&self.render_toc(&page.nodes, ""));
+    for node in &page.nodes {
+      content.push_str(&self.render_node(node, ""));
+    }
+    render_layout(&page.title, &content)
+  }
+
+  fn render_toc(&self, nodes: &[Value], prefix: &str) -> String {
+    let mut toc = String::new();
+    let mut current_kind = None;
+    for node in nodes {
+      let kind = get_str(node, "kind");
+      if current_kind != Some(kind) {
+        if current_kind.is_some() {
+          toc.push_str("</ul>\n");
+        }
+        writeln!(toc, "<h2>{}</h2>\n<ul>", get_kind_heading(kind)).unwrap();
+        current_kind = Some(kind);
+      }
+      let name = format!("{}{}", prefix, get_str(node, "name"));
+      writeln!(
+        toc,
+        "<li><a href=\"#{}\">{}</a></li>",
+        html_escape(&name),
+        html_escape(&name)
+      )
+      .unwrap();
+    }
+    if current_kind.is_some() {
+      toc.push_str("</ul>\n");
+    }
+    toc
+  }
+
+  /// Link `name` to the section documenting it, preferring the current page.
+  fn render_type_name(&self, name: &str) -> String {
+    match self.links.get(name) {
+      Some(href) => {
+        let href = match href.strip_prefix(self.file_name) {
+          Some(anchor) if anchor.starts_with('#') => anchor,
+          _ => href,
+        };
+        format!(
+          "<a href=\"{}\">{}</a>",
+          html_escape(href),
+          html_escape(name)
+        )
+      }
+      None => html_escape(name),
+    }
+  }
+
+  fn render_node(&self, node: &Value, prefix: &str) -> String {
+    let kind = get_str(node, "kind");
+    let name = format!("{}{}", prefix, get_str(node, "name"));
+    let mut html = String::new();
+    writeln!(
+      html,
+      "<section class=\"symbol\" id=\"{}\">",
+      html_escape(&name)
+    )
+    .unwrap();
+    writeln!(
+      html,
+      "<h3><span class=\"kind\">{}</span> {}</h3>",
+      get_kind_label(kind),
+      html_escape(&name)
+    )
+    .unwrap();
+    writeln!(
+      html,
+      "<pre class=\"signature\">{}</pre>",
+      self.render_signature(node)
+    )
+    .unwrap();
+    html.push_str(&render_js_doc(node));
+    html.push_str(&render_location(node));
+
+    match kind {
+      "class" => html.push_str(&self.render_class_members(&node["classDef"])),
+      "interface" => {
+        html.push_str(&self.render_interface_members(&node["interfaceDef"]))
+      }
+      "enum" => html.push_str(&render_enum_members(&node["enumDef"])),
+      "namespace" => {
+        if let Some(elements) = node["namespaceDef"]["elements"].as_array() {
+          let prefix = format!("{}.", name);
+          html.push_str(&self.render_toc(elements, &prefix));
+          for element in elements {
+            html.push_str(&self.render_node(element, &prefix));
+          }
+        }
+      }
+      _ => {}
+    }
+    html.push_str("</section>\n");
+    html
+  }
+
+  fn render_signature(&self, node: &Value) -> String {
+    let name = html_escape(get_str(node, "name"));
+    match get_str(node, "kind") {
+      "function" => {
+        let def = &node["functionDef"];
+        let keyword = if def["isAsync"].as_bool() == Some(true) {
+          "async function"
+        } else {
+          "function"
+        };
+        let generator = if def["isGenerator"].as_bool() == Some(true) {
+          "*"
+        } else {
+          ""
+        };
+        format!(
+          "{}{} {}{}",
+          keyword,
+          generator,
+          name,
+          self.render_function(def)
+        )
+      }
+      "variable" => {
+        let def = &node["variableDef"];
+        let mut signature = format!("{} {}", get_str(def, "kind"), name);
+        if !def["tsType"].is_null() {
+          write!(signature, ": {}", self.render_type(&def["tsType"])).unwrap();
+        }
+        signature
+      }
+      "class" => {
+        let def = &node["classDef"];
+        let mut signature = String::new();
+        if def["isAbstract"].as_bool() == Some(true) {
+          signature.push_str("abstract ");
+        }
+        write!(
+          signature,
+          "class {}{}",
+          name,
+          self.render_type_params(&def["typeParams"])
+        )
+        .unwrap();
+        if let Some(extends) = def["extends"].as_str() {
+          write!(
+            signature,
+            " extends {}{}",
+            self.render_type_name(extends),
+            self.render_type_args(&def["superTypeParams"])
+          )
+          .unwrap();
+        }
+        let implements = self.render_type_list(&def["implements"], ", ");
+        if !implements.is_empty() {
+          write!(signature, " implements {}", implements).unwrap();
+        }
+        signature
+      }
+      "interface" => {
+        let def = &node["interfaceDef"];
+        let mut signature = format!(
+          "interface {}{}",
+          name,
+          self.render_type_params(&def["typeParams"])
+        );
+        let extends = self.render_type_list(&def["extends"], ", ");
+        if !extends.is_empty() {
+          write!(signature, " extends {}", extends).unwrap();
+        }
+        signature
+      }
+      "typeAlias" => {
+        let def = &node["typeAliasDef"];
+        format!(
+          "type {}{} = {}",
+          name,
+          self.render_type_params(&def["typeParams"]),
+          self.render_type(&def["tsType"])
+        )
+      }
+      "enum" => format!("enum {}", name),
+      "namespace" => format!("namespace {}", name),
+      _ => name,
+    }
+  }
+
+  /// Render the type parameters, parameters and return type of a function.
+  fn render_function(&self, def: &Value) -> String {
+    let mut signature = format!(
+      "{}({})",
+      self.render_type_params(&def["typeParams"]),
+      self.render_params(&def["params"])
+    );
+    if !def["returnType"].is_null() {
+      write!(signature, ": {}", self.render_type(&def["returnType"])).unwrap();
+    }
+    signature
+  }
+
+  fn render_params(&self, params: &Value) -> String {
+    let params = match params.as_array() {
+      Some(params) => params,
+      None => return String::new(),
+    };
+    params
+      .iter()
+      .map(|param| self.render_param(param))
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  fn render_param(&self, param: &Value) -> String {
+    let mut html = match get_str(param, "kind") {
+      "identifier" => html_escape(get_str(param, "name")),
+      "rest" => format!("...{}", self.render_param(&param["arg"])),
+      "assign" => self.render_param(&param["left"]),
+      "array" => "[…]".to_string(),
+      "object" => "{…}".to_string(),
+      _ => "…".to_string(),
+    };
+    let is_optional = param["optional"].as_bool() == Some(true)
+      || get_str(param, "kind") == "assign";
+    if is_optional {
+      html.push('?');
+    }
+    if !param["tsType"].is_null() {
+      write!(html, ": {}", self.render_type(&param["tsType"])).unwrap();
+    }
+    html
+  }
+
+  fn render_type_params(&self, type_params: &Value) -> String {
+    let type_params = match type_params.as_array() {
+      Some(type_params) if !type_params.is_empty() => type_params,
+      _ => return String::new(),
+    };
+    let type_params = type_params
+      .iter()
+      .map(|param| {
+        let mut html = html_escape(get_str(param, "name"));
+        if !param["constraint"].is_null() {
+          write!(html, " extends {}", self.render_type(&param["constraint"]))
+            .unwrap();
+        }
+        if !param["default"].is_null() {
+          write!(html, " = {}", self.render_type(&param["default"])).unwrap();
+        }
+        html
+      })
+      .collect::<Vec<_>>();
+    format!("&lt;{}&gt;", type_params.join(", "))
+  }
+
+  fn render_type_args(&self, type_args: &Value) -> String {
+    let type_args = self.render_type_list(type_args, ", ");
+    if type_args.is_empty() {
+      type_args
+    } else {
+      format!("&lt;{}&gt;", type_args)
+    }
+  }
+
+  fn render_type_list(&self, types: &Value, separator: &str) -> String {
+    match types.as_array() {
+      Some(types) => types
+        .iter()
+        .map(|ts_type| self.render_type(ts_type))
+        .collect::<Vec<_>>()
+        .join(separator),
+      None => String::new(),
+    }
+  }
+
+  fn render_type(&self, ts_type: &Value) -> String {
+    if let Some(name) = ts_type.as_str() {
+      return self.render_type_name(name);
+    }
+    match get_str(ts_type, "kind") {
+      "keyword" => html_escape(get_str(ts_type, "keyword")),
+      "typeRef" => {
+        let type_ref = &ts_type["typeRef"];
+        format!(
+          "{}{}",
+          self.render_type_name(get_str(type_ref, "typeName")),
+          self.render_type_args(&type_ref["typeParams"])
+        )
+      }
+      "union" => self.render_type_list(&ts_type["union"], " | "),
+      "intersection" => {
+        self.render_type_list(&ts_type["intersection"], " &amp; ")
+      }
+      "array" => format!("{}[]", self.render_type(&ts_type["array"])),
+      "tuple" => {
+        format!("[{}]", self.render_type_list(&ts_type["tuple"], ", "))
+      }
+      "parenthesized" => {
+        format!("({})", self.render_type(&ts_type["parenthesized"]))
+      }
+      "rest" => format!("...{}", self.render_type(&ts_type["rest"])),
+      "optional" => format!("{}?", self.render_type(&ts_type["optional"])),
+      "typeOperator" => {
+        let type_operator = &ts_type["typeOperator"];
+        format!(
+          "{} {}",
+          html_escape(get_str(type_operator, "operator")),
+          self.render_type(&type_operator["tsType"])
+        )
+      }
+      "typeQuery" => {
+        format!("typeof {}", html_escape(get_str(ts_type, "typeQuery")))
+      }
+      "indexedAccess" => {
+        let indexed_access = &ts_type["indexedAccess"];
+        format!(
+          "{}[{}]",
+          self.render_type(&indexed_access["objType"]),
+          self.render_type(&indexed_access["indexType"])
+        )
+      }
+      "fnOrConstructor" => {
+        let def = &ts_type["fnOrConstructor"];
+        let keyword = if def["constructor"].as_bool() == Some(true) {
+          "new "
+        } else {
+          ""
+        };
+        format!(
+          "{}{}({}) =&gt; {}",
+          keyword,
+          self.render_type_params(&def["typeParams"]),
+          self.render_params(&def["params"]),
+          self.render_type(&def["tsType"])
+        )
+      }
+      _ => match get_str(ts_type, "repr") {
+        "" => "…".to_string(),
+        repr => html_escape(repr),
+      },
+    }
+  }
+
+  fn render_class_members(&self, def: &Value) -> String {
+    let mut html = String::new();
+    for constructor in def["constructors"].as_array().into_iter().flatten() {
+      let signature = format!(
+        "constructor({})",
+        self.render_params(&constructor["params"])
+      );
+      html.push_str(&render_member(&signature, constructor));
+    }
+    for property in def["properties"].as_array().into_iter().flatten() {
+      let mut signature = render_modifiers(property);
+      signature.push_str(&html_escape(get_str(property, "name")));
+      if property["optional"].as_bool() == Some(true) {
+        signature.push('?');
+      }
+      if !property["tsType"].is_null() {
+        write!(signature, ": {}", self.render_type(&property["tsType"]))
+          .unwrap();
+      }
+      html.push_str(&render_member(&signature, property));
+    }
+    for method in def["methods"].as_array().into_iter().flatten() {
+      let mut signature = render_modifiers(method);
+      match get_str(method, "kind") {
+        "getter" => signature.push_str("get "),
+        "setter" => signature.push_str("set "),
+        _ => {}
+      }
+      write!(
+        signature,
+        "{}{}",
+        html_escape(get_str(method, "name")),
+        self.render_function(&method["functionDef"])
+      )
+      .unwrap();
+      html.push_str(&render_member(&signature, method));
+    }
+    html
+  }
+
+  fn render_interface_members(&self, def: &Value) -> String {
+    let mut html = String::new();
+    for property in def["properties"].as_array().into_iter().flatten() {
+      let mut signature = String::new();
+      if property["readonly"].as_bool() == Some(true) {
+        signature.push_str("readonly ");
+      }
+      signature.push_str(&html_escape(get_str(property, "name")));
+      if property["optional"].as_bool() == Some(true) {
+        signature.push('?');
+      }
+      if !property["tsType"].is_null() {
+        write!(signature, ": {}", self.render_type(&property["tsType"]))
+          .unwrap();
+      }
+      html.push_str(&render_member(&signature, property));
+    }
+    for method in def["methods"].as_array().into_iter().flatten() {
+      let signature = format!(
+        "{}{}",
+        html_escape(get_str(method, "name")),
+        self.render_function(method)
+      );
+      html.push_str(&render_member(&signature, method));
+    }
+    html
+  }
+}
+
+fn render_modifiers(member: &Value) -> String {
+  let mut modifiers = String::new();
+  if let Some(accessibility) = member["accessibility"].as_str() {
+    write!(modifiers, "{} ", accessibility).unwrap();
+  }
+  if member["isStatic"].as_bool() == Some(true) {
+    modifiers.push_str("static ");
+  }
+  if member["isAbstract"].as_bool() == Some(true) {
+    modifiers.push_str("abstract ");
+  }
+  if member["readonly"].as_bool() == Some(true) {
+    modifiers.push_str("readonly ");
+  }
+  modifiers
+}
+
+fn render_member(signature: &str, member: &Value) -> String {
+  format!(
+    "<div class=\"member\">\n<pre class=\"signature\">{}</pre>\n{}</div>\n",
+    signature,
+    render_js_doc(member)
+  )
+}
+
+fn render_enum_members(def: &Value) -> String {
+  let mut html = String::new();
+  for member in def["members"].as_array().into_iter().flatten() {
+    let signature = html_escape(get_str(member, "name"));
+    html.push_str(&render_member(&signature, member));
+  }
+  html
+}
+
+fn render_js_doc(node: &Value) -> String {
+  match node["jsDoc"].as_str() {
+    Some(js_doc) => {
+      format!("<div class=\"doc\">\n{}</div>\n", render_markdown(js_doc))
+    }
+    None => String::new(),
+  }
+}
+
+fn render_location(node: &Value) -> String {
+  let location = &node["location"];
+  if location.is_null() {
+    return String::new();
+  }
+  let cwd = std::env::current_dir().unwrap_or_default();
+  format!(
+    "<p class=\"location\">Defined in {}:{}</p>\n",
+    html_escape(&get_display_name(get_str(location, "filename"), &cwd)),
+    location["line"]
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn renderer_test<F: FnOnce(&Renderer)>(f: F) {
+    let mut links = HashMap::new();
+    links.insert("Foo".to_string(), "mod_ts.html#Foo".to_string());
+    links.insert("Bar".to_string(), "other_ts.html#Bar".to_string());
+    let renderer = Renderer {
+      links: &links,
+      file_name: "mod_ts.html",
+    };
+    f(&renderer);
+  }
+
+  #[test]
+  fn test_render_type_links() {
+    renderer_test(|renderer| {
+      let ts_type = json!({
+        "repr": "",
+        "kind": "union",
+        "union": [
+          {
+            "repr": "Foo",
+            "kind": "typeRef",
+            "typeRef": { "typeName": "Foo", "typeParams": null },
+          },
+          {
+            "repr": "Bar",
+            "kind": "typeRef",
+            "typeRef": { "typeName": "Bar", "typeParams": null },
+          },
+          { "repr": "string", "kind": "keyword", "keyword": "string" },
+        ],
+      });
+      assert_eq!(
+        renderer.render_type(&ts_type),
+        concat!(
+          "<a href=\"#Foo\">Foo</a> | ",
+          "<a href=\"other_ts.html#Bar\">Bar</a> | string"
+        )
+      );
+    });
+  }
+
+  #[test]
+  fn test_render_function_signature() {
+    renderer_test(|renderer| {
+      let number_type =
+        json!({ "repr": "number", "kind": "keyword", "keyword": "number" });
+      let node = json!({
+        "kind": "function",
+        "name": "add",
+        "functionDef": {
+          "params": [
+            {
+              "kind": "identifier",
+              "name": "a",
+              "optional": false,
+              "tsType": number_type,
+            },
+            {
+              "kind": "identifier",
+              "name": "b",
+              "optional": true,
+              "tsType": {
+                "repr": "Foo",
+                "kind": "typeRef",
+                "typeRef": { "typeName": "Foo", "typeParams": null },
+              },
+            },
+          ],
+          "returnType": number_type,
+          "isAsync": false,
+          "isGenerator": false,
+          "typeParams": [],
+        },
+      });
+      assert_eq!(
+        renderer.render_signature(&node),
+        "function add(a: number, b?: <a href=\"#Foo\">Foo</a>): number"
+      );
+    });
+  }
+
+  #[test]
+  fn test_get_unique_file_name() {
+    let pages = vec![Page {
+      title: "mod.ts".to_string(),
+      file_name: "mod_ts.html".to_string(),
+      nodes: vec![],
+    }];
+    assert_eq!(get_unique_file_name("mod.ts", &pages), "mod_ts_2.html");
+    assert_eq!(get_unique_file_name("lib/a.ts", &pages), "lib_a_ts.html");
+    assert_eq!(get_unique_file_name("index", &[]), "index_2.html");
+  }
+
+  #[test]
+  fn test_render_markdown() {
+    assert_eq!(
+      render_markdown("Adds `a` and *b*."),
+      "<p>Adds <code>a</code> and <em>b</em>.</p>\n"
+    );
+  }
+
+  #[test]
+  fn test_render_markdown_escapes_html() {
+    assert_eq!(
+      render_markdown("Adds <img src=x onerror=alert(1)> and <b>b</b>."),
+      "<p>Adds &lt;img src=x onerror=alert(1)&gt; and &lt;b&gt;b&lt;/b&gt;.</p>\n"
+    );
+    assert_eq!(
+      render_markdown("<script>alert(1)</script>"),
+      "&lt;script&gt;alert(1)&lt;/script&gt;"
+    );
+  }
+}
@@ -0,0 +1,469 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Testing of the examples of documentation with `deno test --doc`.
+//!
+//! Fenced TypeScript and JavaScript code blocks are extracted from the JSDoc
+//! comments of modules and from markdown files. Every code block becomes a
+//! module of its own, named after the file and lines it comes from, such as
+//! `mod.ts$12-18.ts`. An example of a module imports the names that the
+//! module exports, unless it has imports of its own. All examples are type
+//! checked, and those whose fence has the `run` attribute are also evaluated
+//! as tests. Code blocks with the `ignore` attribute are skipped.
+
+use crate::ast;
+use crate::doc_html::get_display_name;
+use crate::file_fetcher::SourceFile;
+use crate::file_fetcher::TextDocument;
+use crate::global_state::GlobalState;
+use crate::media_type::MediaType;
+use crate::permissions::Permissions;
+use crate::test_runner::prepare_urls;
+use crate::worker::MainWorker;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::serde_json::json;
+use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
+use deno_doc as doc;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use swc_common::comments::CommentKind;
+
+/// A fenced code block of markdown.
+#[derive(Debug, PartialEq)]
+struct CodeBlock {
+  language: String,
+  attributes: Vec<String>,
+  /// Lines of the source file of the first and last line of code.
+  start: usize,
+  end: usize,
+  code: String,
+}
+
+struct OpenCodeBlock {
+  fence: String,
+  indent: usize,
+  info: String,
+  start: usize,
+  lines: Vec<String>,
+}
+
+/// A fence is at least three backticks or tildes.
+fn get_fence(line: &str) -> Option<String> {
+  let c = line.chars().next()?;
+  if c != '`' && c != '~' {
+    return None;
+  }
+  let fence = line.chars().take_while(|&d| d == c).collect::<String>();
+  if fence.len() < 3 {
+    return None;
+  }
+  Some(fence)
+}
+
+/// Extract the fenced code blocks of markdown `lines`, where `first_line` is
+/// the line of the source file of the first one.
+fn extract_code_blocks(lines: &[String], first_line: usize) -> Vec<CodeBlock> {
+  let mut blocks = Vec::new();
+  let mut current: Option<OpenCodeBlock> = None;
+  for (index, line) in lines.iter().enumerate() {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    match current.take() {
+      None => {
+        if let Some(fence) = get_fence(trimmed) {
+          current = Some(OpenCodeBlock {
+            info: trimmed[fence.len()..].trim().to_string(),
+            fence,
+            indent,
+            start: first_line + index + 1,
+            lines: Vec::new(),
+          });
+        }
+      }
+      Some(mut block) => {
+        let fence_char = block.fence.chars().next().unwrap();
+        let is_closing = trimmed.starts_with(&block.fence)
+          && trimmed.trim_end().chars().all(|c| c == fence_char);
+        if !is_closing {
+          // Lines are indented like the fence in a list item.
+          let line_indent = indent.min(block.indent);
+          block.lines.push(line[line_indent..].to_string());
+          current = Some(block);
+          continue;
+        }
+        let mut info = block
+          .info
+          .split(|c: char| c.is_whitespace() || c == ',')
+          .filter(|s| !s.is_empty())
+          .map(String::from);
+        blocks.push(CodeBlock {
+          language: info.next().unwrap_or_default().to_lowercase(),
+          attributes: info.collect(),
+          start: block.start,
+          end: block.start + block.lines.len().max(1) - 1,
+          code: block.lines.join("\n"),
+        });
+      }
+    }
+  }
+  blocks
+}
+
+/// Lines of the text of a JSDoc comment, without their leading `*`.
+fn get_js_doc_lines(text: &str) -> Vec<String> {
+  text
+    .lines()
+    .map(|line| {
+      let line = line.trim_start();
+      let line = line.strip_prefix('*').unwrap_or(line);
+      line.strip_prefix(' ').unwrap_or(line).to_string()
+    })
+    .collect()
+}
+
+fn get_code_blocks(
+  source_file: &SourceFile,
+) -> Result<Vec<CodeBlock>, AnyError> {
+  let source = source_file.source_code.to_string()?;
+  if source_file
+    .filename
+    .extension()
+    .map_or(false, |ext| ext == "md")
+  {
+    let lines = source.lines().map(String::from).collect::<Vec<_>>();
+    return Ok(extract_code_blocks(&lines, 1));
+  }
+  let specifier = ModuleSpecifier::from(source_file.url.clone());
+  let parsed_module = ast::parse(&specifier, &source, &source_file.media_type)?;
+  let mut blocks = Vec::new();
+  for comment in parsed_module.get_comments() {
+    let text = comment.text.to_string();
+    if comment.kind != CommentKind::Block || !text.starts_with('*') {
+      continue;
+    }
+    let line = parsed_module.get_location(&comment.span).line;
+    blocks.extend(extract_code_blocks(&get_js_doc_lines(&text), line));
+  }
+  Ok(blocks)
+}
+
+/// Names that the module exports, which are imported by its examples.
+fn get_exported_names(
+  global_state: &Arc<GlobalState>,
+  source_file: &SourceFile,
+) -> Result<Vec<String>, AnyError> {
+  let loader = Box::new(global_state.file_fetcher.clone());
+  let doc_parser = doc::DocParser::new(loader, false);
+  let syntax = ast::get_syntax(&source_file.media_type);
+  let doc_nodes = doc_parser
+    .parse_source(
+      &source_file.url.to_string(),
+      syntax,
+      &source_file.source_code.to_string()?,
+    )
+    .map_err(|err| generic_error(err.to_string()))?;
+  let mut names = Vec::new();
+  for doc_node in doc_nodes {
+    let is_identifier = doc_node
+      .name
+      .chars()
+      .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+      && !doc_node.name.starts_with(char::is_numeric);
+    if doc_node.kind == doc::DocNodeKind::Import
+      || doc_node.name.is_empty()
+      || doc_node.name == "default"
+      || !is_identifier
+      || names.contains(&doc_node.name)
+    {
+      continue;
+    }
+    names.push(doc_node.name);
+  }
+  Ok(names)
+}
+
+fn get_media_type(language: &str) -> Option<(MediaType, &'static str)> {
+  match language {
+    "ts" | "typescript" => Some((MediaType::TypeScript, "ts")),
+    "tsx" => Some((MediaType::TSX, "tsx")),
+    "js" | "javascript" | "mjs" => Some((MediaType::JavaScript, "js")),
+    "jsx" => Some((MediaType::JSX, "jsx")),
+    _ => None,
+  }
+}
+
+/// A code block turned into a module.
+pub struct DocExample {
+  pub specifier: ModuleSpecifier,
+  /// The file and lines of the code block.
+  pub name: String,
+  pub source_file: SourceFile,
+  /// Whether the example is evaluated as a test, besides being type checked.
+  pub run: bool,
+}
+
+/// Create the examples of the code blocks of `source_file`.
+pub fn get_examples(
+  global_state: &Arc<GlobalState>,
+  source_file: &SourceFile,
+  cwd: &Path,
+) -> Result<Vec<DocExample>, AnyError> {
+  let is_markdown = source_file
+    .filename
+    .extension()
+    .map_or(false, |ext| ext == "md");
+  let mut maybe_exported_names = None;
+  let mut examples = Vec::new();
+  for block in get_code_blocks(source_file)? {
+    let (media_type, extension) = match get_media_type(&block.language) {
+      Some(media_type) => media_type,
+      None => continue,
+    };
+    if block.attributes.iter().any(|a| a == "ignore") {
+      continue;
+    }
+    let suffix = format!("${}-{}.{}", block.start, block.end, extension);
+    let url = Url::parse(&format!("{}{}", source_file.url, suffix))?;
+
+    // Imports are hoisted, so appending them keeps the lines of the example.
+    // An example is always a module, so that the declarations of different
+    // examples don't conflict.
+    let mut code = block.code.clone();
+    let has_imports = code
+      .lines()
+      .any(|line| line.trim_start().starts_with("import "));
+    if !is_markdown && !has_imports {
+      if maybe_exported_names.is_none() {
+        maybe_exported_names =
+          Some(get_exported_names(global_state, source_file)?);
+      }
+      let names = maybe_exported_names.as_ref().unwrap();
+      if !names.is_empty() {
+        code.push_str(&format!(
+          "\nimport {{ {} }} from \"{}\";",
+          names.join(", "),
+          source_file.url
+        ));
+      }
+    }
+    code.push_str("\nexport {};\n");
+
+    let name = format!(
+      "{} (lines {}-{})",
+      get_display_name(source_file.url.as_str(), cwd),
+      block.start,
+      block.end
+    );
+    examples.push(DocExample {
+      specifier: ModuleSpecifier::from(url.clone()),
+      name,
+      source_file: SourceFile {
+        filename: PathBuf::from(format!(
+          "{}{}",
+          source_file.filename.to_string_lossy(),
+          suffix
+        )),
+        url,
+        types_header: None,
+        media_type,
+        source_code: TextDocument::new(code.into_bytes(), Some("utf-8")),
+      },
+      run: block.attributes.iter().any(|a| a == "run"),
+    });
+  }
+  Ok(examples)
+}
+
+/// Modules and markdown files whose examples are tested.
+fn is_supported_doc(path: &Path) -> bool {
+  let name = path.file_name().unwrap_or_default().to_string_lossy();
+  if name.ends_with(".d.ts") {
+    return false;
+  }
+  matches!(
+    path.extension().and_then(|ext| ext.to_str()),
+    Some("ts")
+      | Some("tsx")
+      | Some("js")
+      | Some("jsx")
+      | Some("mjs")
+      | Some("md")
+  )
+}
+
+/// Render the module registering a test for each of the `examples`, which
+/// imports the example.
+fn render_doc_test_file(examples: &[&DocExample]) -> String {
+  let mut test_file = "".to_string();
+  for example in examples {
+    test_file.push_str(&format!(
+      "Deno.test({}, async () => {{\n  await import({});\n}});\n",
+      json!(example.name),
+      json!(example.specifier.to_string())
+    ));
+  }
+  test_file
+}
+
+fn save_source_file(
+  global_state: &Arc<GlobalState>,
+  path: PathBuf,
+  source: String,
+) -> ModuleSpecifier {
+  let url = Url::from_file_path(&path).expect("Should be valid file url");
+  let specifier = ModuleSpecifier::from(url.clone());
+  let source_file = SourceFile {
+    filename: path,
+    url,
+    types_header: None,
+    media_type: MediaType::TypeScript,
+    source_code: TextDocument::new(source.into_bytes(), Some("utf-8")),
+  };
+  global_state
+    .file_fetcher
+    .save_source_file_in_cache(&specifier, source_file);
+  specifier
+}
+
+/// The examples of the documentation that were found.
+pub struct DocTests {
+  /// Number of examples that were type checked.
+  pub checked: usize,
+  /// Generated test module evaluating the examples that run.
+  pub maybe_test_module: Option<Url>,
+  /// The files the examples were found in.
+  pub sources: Vec<Url>,
+}
+
+/// Type check the examples of the files of `include`, and prepare a test
+/// module for those that run.
+pub async fn prepare_doc_tests(
+  global_state: &Arc<GlobalState>,
+  include: Vec<String>,
+  cwd: &PathBuf,
+) -> Result<DocTests, AnyError> {
+  // Directories are walked in no particular order.
+  let mut urls = prepare_urls(include, cwd, is_supported_doc)?;
+  urls.sort();
+  urls.dedup();
+  let mut examples = Vec::new();
+  let mut sources = Vec::new();
+  for url in urls {
+    let specifier = ModuleSpecifier::from(url.clone());
+    let source_file = global_state
+      .file_fetcher
+      .fetch_source_file(&specifier, None, Permissions::allow_all())
+      .await?;
+    let file_examples = get_examples(global_state, &source_file, cwd)?;
+    if !file_examples.is_empty() {
+      sources.push(url);
+      examples.extend(file_examples);
+    }
+  }
+  if examples.is_empty() {
+    return Ok(DocTests {
+      checked: 0,
+      maybe_test_module: None,
+      sources,
+    });
+  }
+
+  // Examples are saved in the file fetcher cache so that the compiler can
+  // access them, and type checked at once by loading a module importing all
+  // of them without evaluating it.
+  let mut check_file = "".to_string();
+  for example in &examples {
+    check_file.push_str(&format!("import \"{}\";\n", example.specifier));
+    global_state.file_fetcher.save_source_file_in_cache(
+      &example.specifier,
+      example.source_file.clone(),
+    );
+  }
+  let check_module =
+    save_source_file(global_state, cwd.join("$deno$doc.ts"), check_file);
+  let mut worker = MainWorker::new(global_state, check_module.clone());
+  worker.preload_module(&check_module).await?;
+
+  let runnable = examples.iter().filter(|e| e.run).collect::<Vec<_>>();
+  let maybe_test_module = if runnable.is_empty() {
+    None
+  } else {
+    let test_file = render_doc_test_file(&runnable);
+    let specifier =
+      save_source_file(global_state, cwd.join("$deno$doc_test.ts"), test_file);
+    Some(specifier.as_url().clone())
+  };
+  Ok(DocTests {
+    checked: examples.len(),
+    maybe_test_module,
+    sources,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+  }
+
+  #[test]
+  fn test_extract_code_blocks() {
+    let markdown = lines(
+      "# Title\n\n```ts ignore\nconst a = 1;\n```\n\n- item\n\n  ~~~js, run\n  console.log(a);\n    indented();\n  ~~~\n\n```\n```\n",
+    );
+    assert_eq!(
+      extract_code_blocks(&markdown, 1),
+      vec![
+        CodeBlock {
+          language: "ts".to_string(),
+          attributes: vec!["ignore".to_string()],
+          start: 4,
+          end: 4,
+          code: "const a = 1;".to_string(),
+        },
+        CodeBlock {
+          language: "js".to_string(),
+          attributes: vec!["run".to_string()],
+          start: 10,
+          end: 11,
+          code: "console.log(a);\n  indented();".to_string(),
+        },
+        CodeBlock {
+          language: "".to_string(),
+          attributes: vec![],
+          start: 15,
+          end: 15,
+          code: "".to_string(),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_unterminated_code_block() {
+    assert_eq!(
+      extract_code_blocks(&lines("```ts\nconst a = 1;"), 1),
+      vec![]
+    );
+  }
+
+  #[test]
+  fn test_get_js_doc_lines() {
+    assert_eq!(
+      get_js_doc_lines("*\n * Add numbers.\n *\n *     indented\n "),
+      vec!["", "Add numbers.", "", "    indented", ""]
+    );
+  }
+
+  #[test]
+  fn test_is_supported_doc() {
+    assert!(is_supported_doc(Path::new("mod.ts")));
+    assert!(is_supported_doc(Path::new("docs/README.md")));
+    assert!(!is_supported_doc(Path::new("lib.d.ts")));
+    assert!(!is_supported_doc(Path::new("data.json")));
+  }
+}
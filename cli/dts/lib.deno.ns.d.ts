@@ -94,8 +94,62 @@ declare namespace Deno {
    * See: https://no-color.org/ */
   export const noColor: boolean;
 
+  /** The argument of the function of a test or step. */
+  export interface TestContext {
+    /** The name of the test. The name of a step is prefixed with the names of
+     * the test and steps running it, separated by `" > "`. */
+    name: string;
+    /** The URL of the module that registered the test. */
+    origin?: string;
+    /** Compare `actual`, serialized with `Deno.inspect()`, to the snapshot
+     * stored in the `__snapshots__` directory next to the test module. Missing
+     * and changed snapshots are written when running
+     * `deno test --update-snapshots`.
+     *
+     * ```ts
+     * Deno.test("parses the config", (t) => {
+     *   t.assertSnapshot(parseConfig("debug = true"));
+     * });
+     * ```
+     */
+    assertSnapshot(actual: unknown, message?: string): void;
+    /** Run a step of the test, which is reported with its own status. The
+     * test fails if any of its steps fail. Resolves to whether the step
+     * passed. Steps must be awaited, and can't run concurrently.
+     *
+     * ```ts
+     * Deno.test("database", async (t) => {
+     *   const db = await connect();
+     *   await t.step("insert", async () => {
+     *     await db.insert({ id: 1 });
+     *   });
+     *   await t.step("query", async () => {
+     *     assertEquals(await db.query({ id: 1 }), [{ id: 1 }]);
+     *   });
+     *   db.close();
+     * });
+     * ```
+     */
+    step(t: TestStepDefinition): Promise<boolean>;
+    step(
+      name: string,
+      fn: (t: TestContext) => void | Promise<void>,
+    ): Promise<boolean>;
+  }
+
+  export interface TestStepDefinition {
+    fn: (t: TestContext) => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** Check that the number of async completed ops after the step is the same
+     * as number of dispatched ops. Defaults to true. */
+    sanitizeOps?: boolean;
+    /** Ensure the step does not "leak" resources. Defaults to true. */
+    sanitizeResources?: boolean;
+  }
+
   export interface TestDefinition {
-    fn: () => void | Promise<void>;
+    fn: (t: TestContext) => void | Promise<void>;
     name: string;
     ignore?: boolean;
     /** If at lease one test has `only` set to true, only run tests that have
@@ -108,6 +162,38 @@ declare namespace Deno {
      * after the test has exactly the same contents as before the test. Defaults
      * to true. */
     sanitizeResources?: boolean;
+    /** Fail the test if it hasn't finished after this many milliseconds,
+     * reporting the async ops and resources it is still waiting on. Overrides
+     * the `--test-timeout` of `deno test`. */
+    timeout?: number;
+    /** Run the test with a subset of the permissions of `deno test`, which are
+     * restored once the test has finished. Permissions that aren't set are
+     * kept, and the test fails if it asks for a permission that isn't
+     * granted to `deno test`.
+     *
+     * ```ts
+     * Deno.test({
+     *   name: "reads fixtures only",
+     *   permissions: { read: ["./fixtures"], net: false },
+     *   fn() {
+     *     Deno.readTextFileSync("./fixtures/config.json");
+     *   },
+     * });
+     * ```
+     */
+    permissions?: TestPermissions;
+  }
+
+  /** The permissions of a test. `true` grants a permission and `false` denies
+   * it, while a list of paths or hosts grants these only. */
+  export interface TestPermissions {
+    read?: boolean | string[];
+    write?: boolean | string[];
+    net?: boolean | string[];
+    env?: boolean;
+    run?: boolean;
+    plugin?: boolean;
+    hrtime?: boolean;
   }
 
   /** Register a test which will be run when `deno test` is used on the command
@@ -161,7 +247,10 @@ declare namespace Deno {
    * });
    * ```
    * */
-  export function test(name: string, fn: () => void | Promise<void>): void;
+  export function test(
+    name: string,
+    fn: (t: TestContext) => void | Promise<void>,
+  ): void;
 
   /** Exit the Deno process with optional exit code. If no exit code is supplied
    * then Deno will exit with return code of 0.
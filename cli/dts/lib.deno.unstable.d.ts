@@ -1312,6 +1312,59 @@ declare namespace Deno {
     atime: number | Date,
     mtime: number | Date,
   ): Promise<void>;
+  /** **UNSTABLE**: New API, yet to be vetted.
+   * A benchmark registered with `Deno.bench()`. */
+  export interface BenchDefinition {
+    fn: () => void | Promise<void>;
+    name: string;
+    ignore?: boolean;
+    /** If at least one benchmark has `only` set to true, only run benchmarks
+     * that have `only` set to true and fail the bench run. */
+    only?: boolean;
+    /** Measure exactly this many iterations, one by one, instead of as many
+     * batches of iterations as fit in the measuring time. */
+    n?: number;
+    /** Run this many iterations before measuring the benchmark. */
+    warmup?: number;
+  }
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   * Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a bench module.
+   * `fn` can be async if required.
+   *
+   * ```ts
+   * Deno.bench({
+   *   name: "parse a URL",
+   *   fn(): void {
+   *     new URL("https://deno.land/std/testing/asserts.ts");
+   *   },
+   * });
+   *
+   * Deno.bench({
+   *   name: "read a small file",
+   *   n: 100,
+   *   warmup: 10,
+   *   async fn() {
+   *     await Deno.readFile("hello_world.txt");
+   *   },
+   * });
+   * ```
+   */
+  export function bench(b: BenchDefinition): void;
+
+  /** **UNSTABLE**: New API, yet to be vetted.
+   * Register a benchmark which will be run when `deno bench` is used on the
+   * command line and the containing module looks like a bench module.
+   * `fn` can be async if required.
+   *
+   * ```ts
+   * Deno.bench("encode a string", (): void => {
+   *   new TextEncoder().encode("hello world");
+   * });
+   * ```
+   */
+  export function bench(name: string, fn: () => void | Promise<void>): void;
 }
 
 declare function fetch(
@@ -0,0 +1,49 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Escaping of text embedded in the HTML and XML reports.
+
+/// Escapes `text` for use in HTML text and attribute values.
+pub fn html_escape(text: &str) -> String {
+  escape(text, "&#39;")
+}
+
+/// Escapes `text` for use in XML text and attribute values.
+pub fn xml_escape(text: &str) -> String {
+  escape(text, "&apos;")
+}
+
+fn escape(text: &str, apostrophe: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str(apostrophe),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_html_escape() {
+    assert_eq!(
+      html_escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+      "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+    );
+  }
+
+  #[test]
+  fn test_xml_escape() {
+    assert_eq!(
+      xml_escape(r#"<a b="c">'d' & e</a>"#),
+      "&lt;a b=&quot;c&quot;&gt;&apos;d&apos; &amp; e&lt;/a&gt;"
+    );
+  }
+}
@@ -116,6 +116,11 @@ impl SourceFileCache {
       None => None,
     }
   }
+
+  pub fn remove(&self, key: &str) {
+    let mut c = self.0.lock().unwrap();
+    c.remove(key);
+  }
 }
 
 const SUPPORTED_URL_SCHEMES: [&str; 3] = ["http", "https", "file"];
@@ -207,6 +212,11 @@ impl SourceFileFetcher {
     self.source_file_cache.set(specifier.to_string(), file);
   }
 
+  /// Remove a file injected with `save_source_file_in_cache()`.
+  pub fn remove_source_file_from_cache(&self, specifier: &ModuleSpecifier) {
+    self.source_file_cache.remove(&specifier.to_string());
+  }
+
   pub async fn fetch_source_file(
     &self,
     specifier: &ModuleSpecifier,
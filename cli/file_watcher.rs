@@ -61,7 +61,9 @@ where
   }
 }
 
-pub async fn file_watcher(paths: &[PathBuf]) -> Result<(), AnyError> {
+/// Wait for any of `paths` to be created, modified or removed. Returns the
+/// paths affected by the first such change.
+pub async fn file_watcher(paths: &[PathBuf]) -> Result<Vec<PathBuf>, AnyError> {
   let (sender, mut receiver) =
     mpsc::channel::<Result<NotifyEvent, AnyError>>(16);
   let sender = std::sync::Mutex::new(sender);
@@ -84,11 +86,11 @@ pub async fn file_watcher(paths: &[PathBuf]) -> Result<(), AnyError> {
   while let Some(result) = receiver.next().await {
     let event = result?;
     match event.kind {
-      EventKind::Create(_) => break,
-      EventKind::Modify(_) => break,
-      EventKind::Remove(_) => break,
+      EventKind::Create(_) => return Ok(event.paths),
+      EventKind::Modify(_) => return Ok(event.paths),
+      EventKind::Remove(_) => return Ok(event.paths),
       _ => continue,
     }
   }
-  Ok(())
+  Ok(vec![])
 }
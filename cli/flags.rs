@@ -1,5 +1,11 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::config_file::FmtOptionsConfig;
+use crate::config_file::ProseWrap;
+use crate::coverage::CoverageReporterKind;
+use crate::coverage::CoverageThreshold;
+use crate::lint::LintReporterKind;
+use crate::test_runner::TestReporterKind;
 use clap::App;
 use clap::AppSettings;
 use clap::Arg;
@@ -17,6 +23,11 @@ macro_rules! svec {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum DenoSubcommand {
+  Bench {
+    include: Option<Vec<String>>,
+    filter: Option<String>,
+    json: bool,
+  },
   Bundle {
     source_file: String,
     out_file: Option<PathBuf>,
@@ -24,11 +35,21 @@ pub enum DenoSubcommand {
   Completions {
     buf: Box<[u8]>,
   },
+  Coverage {
+    /// Directory of the profiles saved by `deno test --coverage=<dir>`.
+    dir: PathBuf,
+    reporter: CoverageReporterKind,
+    output: Option<PathBuf>,
+  },
   Doc {
     private: bool,
     json: bool,
     source_file: Option<String>,
     filter: Option<String>,
+    /// Output directory of the static site generated with `--html`.
+    html_output: Option<PathBuf>,
+    /// Modules documented by `--html`.
+    entry_points: Vec<String>,
   },
   Eval {
     print: bool,
@@ -42,6 +63,7 @@ pub enum DenoSubcommand {
     check: bool,
     files: Vec<String>,
     ignore: Vec<String>,
+    options: FmtOptionsConfig,
   },
   Help,
   Info {
@@ -59,7 +81,8 @@ pub enum DenoSubcommand {
     files: Vec<String>,
     ignore: Vec<String>,
     rules: bool,
-    json: bool,
+    reporter: LintReporterKind,
+    fix: bool,
   },
   Repl,
   Run {
@@ -71,6 +94,14 @@ pub enum DenoSubcommand {
     allow_none: bool,
     include: Option<Vec<String>>,
     filter: Option<String>,
+    jobs: usize,
+    reporter: TestReporterKind,
+    reporter_output: Option<PathBuf>,
+    timeout: Option<u64>,
+    update_snapshots: bool,
+    doc: bool,
+    shuffle: Option<u64>,
+    trace_ops: bool,
   },
   Types,
   Upgrade {
@@ -107,6 +138,9 @@ pub struct Flags {
   pub cached_only: bool,
   pub config_path: Option<String>,
   pub coverage: bool,
+  /// Directory to save the coverage profiles to instead of reporting them.
+  pub coverage_dir: Option<PathBuf>,
+  pub coverage_threshold: Option<CoverageThreshold>,
   pub ignore: Vec<String>,
   pub import_map_path: Option<String>,
   pub inspect: Option<SocketAddr>,
@@ -272,12 +306,16 @@ pub fn flags_from_vec_safe(args: Vec<String>) -> clap::Result<Flags> {
     eval_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("repl") {
     repl_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("bench") {
+    bench_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("bundle") {
     bundle_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("install") {
     install_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("completions") {
     completions_parse(&mut flags, m);
+  } else if let Some(m) = matches.subcommand_matches("coverage") {
+    coverage_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("test") {
     test_parse(&mut flags, m);
   } else if let Some(m) = matches.subcommand_matches("upgrade") {
@@ -333,9 +371,11 @@ If the flag is set, restrict these messages to errors.",
         )
         .global(true),
     )
+    .subcommand(bench_subcommand())
     .subcommand(bundle_subcommand())
     .subcommand(cache_subcommand())
     .subcommand(completions_subcommand())
+    .subcommand(coverage_subcommand())
     .subcommand(doc_subcommand())
     .subcommand(eval_subcommand())
     .subcommand(fmt_subcommand())
@@ -356,6 +396,7 @@ fn types_parse(flags: &mut Flags, _matches: &clap::ArgMatches) {
 }
 
 fn fmt_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  config_arg_parse(flags, matches);
   let files = match matches.values_of("files") {
     Some(f) => f.map(String::from).collect(),
     None => vec![],
@@ -364,10 +405,32 @@ fn fmt_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     Some(f) => f.map(String::from).collect(),
     None => vec![],
   };
+  let options = FmtOptionsConfig {
+    use_tabs: if matches.is_present("options-use-tabs") {
+      Some(true)
+    } else {
+      None
+    },
+    line_width: matches
+      .value_of("options-line-width")
+      .map(|w| w.parse().unwrap()),
+    indent_width: matches
+      .value_of("options-indent-width")
+      .map(|w| w.parse().unwrap()),
+    single_quote: if matches.is_present("options-single-quote") {
+      Some(true)
+    } else {
+      None
+    },
+    prose_wrap: matches
+      .value_of("options-prose-wrap")
+      .map(|w| w.parse::<ProseWrap>().unwrap()),
+  };
   flags.subcommand = DenoSubcommand::Fmt {
     check: matches.is_present("check"),
     files,
     ignore,
+    options,
   }
 }
 
@@ -405,6 +468,24 @@ fn install_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 }
 
+fn bench_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  runtime_args_parse(flags, matches, true);
+  // Without it, the timer is too coarse to measure short iterations.
+  flags.allow_hrtime = true;
+
+  let filter = matches.value_of("filter").map(String::from);
+  let json = matches.is_present("json");
+  let include = matches
+    .values_of("files")
+    .map(|files| files.map(String::from).collect());
+
+  flags.subcommand = DenoSubcommand::Bench {
+    include,
+    filter,
+    json,
+  };
+}
+
 fn bundle_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   // TODO(nayeemrmn): Replace the next couple lines with `compile_args_parse()`
   // once `deno bundle --no-check` is supported.
@@ -445,6 +526,21 @@ fn completions_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   };
 }
 
+fn coverage_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  coverage_threshold_arg_parse(flags, matches);
+  let dir = PathBuf::from(matches.value_of("dir").unwrap());
+  let reporter = matches
+    .value_of("reporter")
+    .map(|s| s.parse().unwrap())
+    .unwrap_or(CoverageReporterKind::Pretty);
+  let output = matches.value_of("output").map(PathBuf::from);
+  flags.subcommand = DenoSubcommand::Coverage {
+    dir,
+    reporter,
+    output,
+  };
+}
+
 fn repl_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   runtime_args_parse(flags, matches, false);
   flags.subcommand = DenoSubcommand::Repl;
@@ -573,6 +669,10 @@ fn run_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   }
 
   flags.watch = matches.is_present("watch");
+  if let Some(coverage_dir) = matches.value_of("coverage") {
+    flags.coverage = true;
+    flags.coverage_dir = Some(PathBuf::from(coverage_dir));
+  }
   flags.subcommand = DenoSubcommand::Run { script };
 }
 
@@ -587,7 +687,40 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 
   if coverage {
     flags.coverage = true;
+    flags.coverage_dir = matches.value_of("coverage").map(PathBuf::from);
   }
+  coverage_threshold_arg_parse(flags, matches);
+
+  flags.watch = matches.is_present("watch");
+
+  let jobs = if matches.is_present("jobs") {
+    match matches.value_of("jobs") {
+      Some(value) => value.parse().unwrap(),
+      None => num_cpus::get(),
+    }
+  } else {
+    1
+  };
+  let reporter = matches
+    .value_of("reporter")
+    .map(|s| s.parse().unwrap())
+    .unwrap_or(TestReporterKind::Pretty);
+  let reporter_output = matches.value_of("reporter-output").map(PathBuf::from);
+  let timeout = matches
+    .value_of("test-timeout")
+    .map(|value| value.parse().unwrap());
+  let update_snapshots = matches.is_present("update-snapshots");
+  let doc = matches.is_present("doc");
+  // Without a value, the seed of `--seed` is used if there is one.
+  let shuffle = if matches.is_present("shuffle") {
+    match matches.value_of("shuffle") {
+      Some(value) => Some(value.parse().unwrap()),
+      None => Some(flags.seed.unwrap_or_else(rand::random)),
+    }
+  } else {
+    None
+  };
+  let trace_ops = matches.is_present("trace-ops");
 
   let include = if matches.is_present("files") {
     let files: Vec<String> = matches
@@ -606,6 +739,14 @@ fn test_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     include,
     filter,
     allow_none,
+    jobs,
+    reporter,
+    reporter_output,
+    timeout,
+    update_snapshots,
+    doc,
+    shuffle,
+    trace_ops,
   };
 }
 
@@ -634,19 +775,43 @@ fn upgrade_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
 fn doc_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
   reload_arg_parse(flags, matches);
 
-  let source_file = matches.value_of("source_file").map(String::from);
   let private = matches.is_present("private");
   let json = matches.is_present("json");
+  if matches.is_present("html") {
+    // All positional arguments are entry points.
+    let entry_points = ["source_file", "filter", "entry_points"]
+      .iter()
+      .filter_map(|name| matches.values_of(name))
+      .flatten()
+      .map(String::from)
+      .collect();
+    let html_output =
+      PathBuf::from(matches.value_of("output").unwrap_or("docs"));
+    flags.subcommand = DenoSubcommand::Doc {
+      source_file: None,
+      json,
+      filter: None,
+      private,
+      html_output: Some(html_output),
+      entry_points,
+    };
+    return;
+  }
+
+  let source_file = matches.value_of("source_file").map(String::from);
   let filter = matches.value_of("filter").map(String::from);
   flags.subcommand = DenoSubcommand::Doc {
     source_file,
     json,
     filter,
     private,
+    html_output: None,
+    entry_points: vec![],
   };
 }
 
 fn lint_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
+  config_arg_parse(flags, matches);
   let files = match matches.values_of("files") {
     Some(f) => f.map(String::from).collect(),
     None => vec![],
@@ -656,12 +821,21 @@ fn lint_parse(flags: &mut Flags, matches: &clap::ArgMatches) {
     None => vec![],
   };
   let rules = matches.is_present("rules");
-  let json = matches.is_present("json");
+  let reporter = if matches.is_present("json") {
+    LintReporterKind::Json
+  } else {
+    matches
+      .value_of("reporter")
+      .map(|s| s.parse().unwrap())
+      .unwrap_or(LintReporterKind::Pretty)
+  };
+  let fix = matches.is_present("fix");
   flags.subcommand = DenoSubcommand::Lint {
     files,
     rules,
     ignore,
-    json,
+    reporter,
+    fix,
   };
 }
 
@@ -680,7 +854,7 @@ fn fmt_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("fmt")
     .about("Format source files")
     .long_about(
-      "Auto-format JavaScript/TypeScript source code.
+      "Auto-format JavaScript, TypeScript, Markdown and JSON files.
   deno fmt
   deno fmt myfile1.ts myfile2.ts
   deno fmt --check
@@ -692,7 +866,24 @@ Ignore formatting code by preceding it with an ignore comment:
   // deno-fmt-ignore
 
 Ignore formatting a file by adding an ignore comment at the top of the file:
-  // deno-fmt-ignore-file",
+  // deno-fmt-ignore-file
+
+Formatting options can be set in the \"fmt\" section of a configuration file.
+Options passed on the command line take precedence:
+  deno fmt --config deno.jsonc
+  deno fmt --options-line-width=100 --options-single-quote
+
+  {
+    \"fmt\": {
+      \"options\": {
+        \"useTabs\": false,
+        \"lineWidth\": 100,
+        \"indentWidth\": 4,
+        \"singleQuote\": true,
+        \"proseWrap\": \"preserve\"
+      }
+    }
+  }",
     )
     .arg(
       Arg::with_name("check")
@@ -700,6 +891,46 @@ Ignore formatting a file by adding an ignore comment at the top of the file:
         .help("Check if the source files are formatted")
         .takes_value(false),
     )
+    .arg(config_arg().help("Load formatting options from configuration file"))
+    .arg(
+      Arg::with_name("options-use-tabs")
+        .long("options-use-tabs")
+        .help("Use tabs instead of spaces for indentation"),
+    )
+    .arg(
+      Arg::with_name("options-line-width")
+        .long("options-line-width")
+        .value_name("NUMBER")
+        .help("Define maximum line width. Defaults to 80")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<u32>() {
+          Ok(w) if w > 0 => Ok(()),
+          _ => Err("Line width should be a positive number".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("options-indent-width")
+        .long("options-indent-width")
+        .value_name("NUMBER")
+        .help("Define indentation width. Defaults to 2")
+        .takes_value(true)
+        .validator(|val: String| match val.parse::<u8>() {
+          Ok(w) if w > 0 => Ok(()),
+          _ => Err("Indent width should be a positive number".to_string()),
+        }),
+    )
+    .arg(
+      Arg::with_name("options-single-quote")
+        .long("options-single-quote")
+        .help("Use single quotes instead of double quotes"),
+    )
+    .arg(
+      Arg::with_name("options-prose-wrap")
+        .long("options-prose-wrap")
+        .takes_value(true)
+        .possible_values(&["always", "never", "preserve"])
+        .help("Define how prose should be wrapped. Defaults to always"),
+    )
     .arg(
       Arg::with_name("ignore")
         .long("ignore")
@@ -781,6 +1012,51 @@ The installation root is determined, in order of precedence:
 These must be added to the path manually if required.")
 }
 
+fn bench_subcommand<'a, 'b>() -> App<'a, 'b> {
+  runtime_args(SubCommand::with_name("bench"), true)
+    .arg(
+      Arg::with_name("filter")
+        .set(ArgSettings::AllowLeadingHyphen)
+        .long("filter")
+        .takes_value(true)
+        .help("Run benchmarks with this string or pattern in the bench name"),
+    )
+    .arg(
+      Arg::with_name("json")
+        .long("json")
+        .help("Output the results in JSON format")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("files")
+        .help("List of file names to run")
+        .takes_value(true)
+        .multiple(true),
+    )
+    .about("Run benchmarks")
+    .long_about(
+      "Run benchmarks using Deno's built-in bench runner.
+
+Evaluate the given modules, run all benchmarks declared with 'Deno.bench()'
+and report results to standard output:
+  deno bench --unstable src/fetch_bench.ts src/signal_bench.ts
+
+Directory arguments are expanded to all contained files matching the glob
+{*_,*.,}bench.{js,mjs,ts,jsx,tsx}:
+  deno bench --unstable src/
+
+Each benchmark is warmed up, then run for as many iterations as fit in half a
+second, and reported with the mean, min, max, 75th and 99th percentile time of
+an iteration. Benchmarks are allowed high resolution time. Iterations are timed
+in batches that take at least 100 times the resolution of the clock, which is
+2ms if the 'hrtime' permission is revoked.
+
+Output the results and the version of Deno as a JSON document, to compare them
+with those of another run:
+  deno bench --unstable --json > bench.json",
+    )
+}
+
 fn bundle_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("bundle")
     // TODO(nayeemrmn): Replace the next couple lines with `compile_args()` once
@@ -913,6 +1189,55 @@ Future runs of this module will trigger no downloads or compilation unless
     )
 }
 
+fn coverage_subcommand<'a, 'b>() -> App<'a, 'b> {
+  SubCommand::with_name("coverage")
+    .about("Report coverage profiles")
+    .long_about(
+      "Report the coverage profiles saved by 'deno test --coverage=<dir>'.
+
+Profiles of the same module from several runs are merged:
+  deno test --unstable --coverage=cov_profile
+  deno coverage --unstable cov_profile
+
+Write a tracefile in the LCOV format:
+  deno coverage --unstable --reporter=lcov --output=cov.lcov cov_profile
+
+Write a report in the Cobertura XML format:
+  deno coverage --unstable --reporter=cobertura --output=coverage.xml cov_profile
+
+Generate an HTML report, in cov_profile/html unless --output is given:
+  deno coverage --unstable --reporter=html cov_profile
+
+Exit with an error when less than 80% of the lines or 70% of the branches of
+all files together are covered:
+  deno coverage --unstable --coverage-threshold=lines:80,branches:70 cov_profile",
+    )
+    .arg(
+      Arg::with_name("dir")
+        .takes_value(true)
+        .required(true)
+        .help("Directory of the coverage profiles"),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["pretty", "lcov", "cobertura", "html"])
+        .help("Select the format of the coverage report"),
+    )
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .takes_value(true)
+        .require_equals(true)
+        .value_name("PATH")
+        .help("Write the report to a file, or to a directory with --reporter=html"),
+    )
+    .arg(coverage_threshold_arg())
+    .arg(coverage_threshold_per_file_arg())
+}
+
 fn upgrade_subcommand<'a, 'b>() -> App<'a, 'b> {
   SubCommand::with_name("upgrade")
     .about("Upgrade deno executable to given version")
@@ -974,7 +1299,11 @@ Target a specific symbol:
 
 Show documentation for runtime built-ins:
     deno doc
-    deno doc --builtin Deno.Listener",
+    deno doc --builtin Deno.Listener
+
+Generate a static HTML documentation site for one or more modules, with a
+page per module and a search index. Defaults to the \"docs\" directory:
+    deno doc --html --output=./docs ./mod.ts ./other.ts",
     )
     .arg(reload_arg())
     .arg(
@@ -989,6 +1318,20 @@ Show documentation for runtime built-ins:
         .help("Output private documentation")
         .takes_value(false),
     )
+    .arg(
+      Arg::with_name("html")
+        .long("html")
+        .help("Generate a static HTML documentation site")
+        .conflicts_with("json")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("output")
+        .long("output")
+        .help("Directory to write the HTML documentation to")
+        .requires("html")
+        .takes_value(true),
+    )
     // TODO(nayeemrmn): Make `--builtin` a proper option. Blocked by
     // https://github.com/clap-rs/clap/issues/1794. Currently `--builtin` is
     // just a possible value of `source_file` so leading hyphens must be
@@ -1003,6 +1346,13 @@ Show documentation for runtime built-ins:
         .conflicts_with("json")
         .conflicts_with("pretty"),
     )
+    .arg(
+      Arg::with_name("entry_points")
+        .help("Additional modules to document with --html")
+        .takes_value(true)
+        .multiple(true)
+        .requires("html"),
+    )
 }
 
 fn lint_subcommand<'a, 'b>() -> App<'a, 'b> {
@@ -1016,6 +1366,12 @@ fn lint_subcommand<'a, 'b>() -> App<'a, 'b> {
 Print result as JSON:
   deno lint --unstable --json
 
+Print result in SARIF, checkstyle or JUnit format, with paths relative to the
+current directory:
+  deno lint --unstable --reporter=sarif > lint.sarif
+  deno lint --unstable --reporter=checkstyle > checkstyle.xml
+  deno lint --unstable --reporter=junit > junit.xml
+
 Read from stdin:
   cat file.ts | deno lint --unstable -
   cat file.ts | deno lint --unstable --json -
@@ -1023,6 +1379,10 @@ Read from stdin:
 List available rules:
   deno lint --unstable --rules
 
+Fix problems of rules that support it (no-extra-semi, prefer-const and
+ban-untagged-ignore) in place:
+  deno lint --unstable --fix
+
 Ignore diagnostics on the next line by preceding it with an ignore comment and
 rule name:
   // deno-lint-ignore no-explicit-any
@@ -1036,6 +1396,25 @@ ESLint ignore comments are also supported:
 
 Ignore linting a file by adding an ignore comment at the top of the file:
   // deno-lint-ignore-file
+
+Rules and files to lint can be set in the \"lint\" section of a configuration
+file. Rules are selected by tag or name, and overrides apply to the listed
+files and directories only:
+  deno lint --unstable --config deno.jsonc
+
+  {
+    \"lint\": {
+      \"files\": { \"include\": [\"src/\"], \"exclude\": [\"src/testdata/\"] },
+      \"rules\": {
+        \"tags\": [\"recommended\"],
+        \"include\": [\"ban-untagged-todo\"],
+        \"exclude\": [\"no-unused-vars\"]
+      },
+      \"overrides\": [
+        { \"files\": [\"src/tests/\"], \"rules\": { \"exclude\": [\"no-explicit-any\"] } }
+      ]
+    }
+  }
 ",
     )
     .arg(
@@ -1043,6 +1422,7 @@ Ignore linting a file by adding an ignore comment at the top of the file:
         .long("rules")
         .help("List available rules"),
     )
+    .arg(config_arg().help("Load lint configuration from configuration file"))
     .arg(
       Arg::with_name("ignore")
         .long("ignore")
@@ -1056,6 +1436,21 @@ Ignore linting a file by adding an ignore comment at the top of the file:
       Arg::with_name("json")
         .long("json")
         .help("Output lint result in JSON format")
+        .conflicts_with("reporter")
+        .takes_value(false),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["pretty", "json", "sarif", "checkstyle", "junit"])
+        .help("Select the format of the lint result"),
+    )
+    .arg(
+      Arg::with_name("fix")
+        .long("fix")
+        .help("Fix any linting errors for rules that support it")
         .takes_value(false),
     )
     .arg(
@@ -1127,6 +1522,23 @@ fn permission_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
 fn run_subcommand<'a, 'b>() -> App<'a, 'b> {
   runtime_args(SubCommand::with_name("run"), true)
     .arg(watch_arg())
+    .arg(
+      Arg::with_name("coverage")
+        .long("coverage")
+        .takes_value(true)
+        .require_equals(true)
+        .value_name("DIR")
+        .requires("unstable")
+        .conflicts_with("inspect")
+        .conflicts_with("inspect-brk")
+        .conflicts_with("watch")
+        .help("Save coverage profiles to a directory")
+        .long_help(
+          "Save the coverage profiles of the program to a directory, to be reported
+by 'deno coverage' along with those of other runs and of 'deno test'. They are
+saved when the program exits, including with Deno.exit(), SIGINT or SIGTERM.",
+        ),
+    )
     .setting(AppSettings::TrailingVarArg)
     .arg(script_arg())
     .about("Run a program given a filename or url to the module. Use '-' as a filename to read from stdin.")
@@ -1147,12 +1559,16 @@ Grant permission to read allow-listed files from disk:
   deno run --allow-read=/etc https://deno.land/std/http/file_server.ts
 
 Deno allows specifying the filename '-' to read the file from stdin.
-  curl https://deno.land/std/examples/welcome.ts | target/debug/deno run -",
+  curl https://deno.land/std/examples/welcome.ts | target/debug/deno run -
+
+Save coverage profiles of the program, to be reported by 'deno coverage':
+  deno run --unstable --coverage=cov_profile main.ts",
     )
 }
 
 fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
   runtime_args(SubCommand::with_name("test"), true)
+    .arg(watch_arg())
     .arg(
       Arg::with_name("failfast")
         .long("failfast")
@@ -1175,11 +1591,95 @@ fn test_subcommand<'a, 'b>() -> App<'a, 'b> {
     .arg(
       Arg::with_name("coverage")
         .long("coverage")
-        .takes_value(false)
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .value_name("DIR")
         .requires("unstable")
         .conflicts_with("inspect")
         .conflicts_with("inspect-brk")
-        .help("Collect coverage information"),
+        .conflicts_with("watch")
+        .help("Collect coverage information")
+        .long_help(
+          "Collect coverage information
+Without a directory, print the line coverage of the tested modules. With a
+directory, save the coverage profiles to it, to be reported by
+'deno coverage'.",
+        ),
+    )
+    .arg(coverage_threshold_arg().requires("coverage"))
+    .arg(coverage_threshold_per_file_arg())
+    .arg(
+      Arg::with_name("jobs")
+        .long("jobs")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .value_name("N")
+        .validator(|val: String| match val.parse::<usize>() {
+          Ok(jobs) if jobs > 0 => Ok(()),
+          _ => Err("jobs should be a positive number".to_string()),
+        })
+        .help("Number of test modules to run in parallel"),
+    )
+    .arg(
+      Arg::with_name("reporter")
+        .long("reporter")
+        .takes_value(true)
+        .require_equals(true)
+        .possible_values(&["pretty", "junit", "tap", "json"])
+        .help("Select the format of the test results"),
+    )
+    .arg(
+      Arg::with_name("reporter-output")
+        .long("reporter-output")
+        .takes_value(true)
+        .value_name("FILE")
+        .requires("reporter")
+        .help("Write the test results to a file instead of stdout"),
+    )
+    .arg(
+      Arg::with_name("test-timeout")
+        .long("test-timeout")
+        .takes_value(true)
+        .require_equals(true)
+        .value_name("MS")
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(timeout) if timeout > 0 => Ok(()),
+          _ => Err("test timeout should be a positive number".to_string()),
+        })
+        .help("Fail tests that take longer than this many milliseconds"),
+    )
+    .arg(
+      Arg::with_name("update-snapshots")
+        .long("update-snapshots")
+        .help("Write new and changed snapshots and remove obsolete ones"),
+    )
+    .arg(
+      Arg::with_name("doc").long("doc").help(
+        "Type check the code blocks of JSDoc comments and markdown files",
+      ),
+    )
+    .arg(
+      Arg::with_name("shuffle")
+        .long("shuffle")
+        .min_values(0)
+        .max_values(1)
+        .require_equals(true)
+        .takes_value(true)
+        .value_name("SEED")
+        .validator(|val: String| match val.parse::<u64>() {
+          Ok(_) => Ok(()),
+          Err(_) => Err("Seed should be a number".to_string()),
+        })
+        .help("Run test modules and their tests in a random order"),
+    )
+    .arg(
+      Arg::with_name("trace-ops")
+        .long("trace-ops")
+        .help("Show where the ops and resources leaked by tests were created"),
     )
     .arg(
       Arg::with_name("files")
@@ -1197,7 +1697,47 @@ report results to standard output:
 
 Directory arguments are expanded to all contained files matching the glob
 {*_,*.,}test.{js,mjs,ts,jsx,tsx}:
-  deno test src/",
+  deno test src/
+
+Run test modules in parallel, each one in its own isolate, using 4 workers or
+as many as there are CPUs:
+  deno test --jobs=4
+  deno test --jobs
+
+Report results as JUnit XML, TAP or newline-delimited JSON events, to stdout or
+a file:
+  deno test --reporter=tap
+  deno test --reporter=junit --reporter-output=junit.xml
+
+Re-run the test modules affected by a change of a local file they import:
+  deno test --unstable --watch
+
+Fail tests that haven't finished after 5 seconds, unless they set a 'timeout'
+of their own:
+  deno test --test-timeout=5000
+
+Write the snapshots asserted with 't.assertSnapshot()' to the '__snapshots__'
+directory next to each test module, replacing those that changed:
+  deno test --update-snapshots
+
+Type check the TypeScript and JavaScript code blocks of JSDoc comments and
+markdown files, and run those marked with 'run':
+  deno test --doc
+
+Run test modules and the tests of each module in a random order, printing the
+seed that reproduces it. Without a value, the seed of '--seed' is used if
+given, otherwise a random one:
+  deno test --shuffle
+  deno test --shuffle=1234
+
+Capture the stack trace of every op dispatched by the tests, so that the ops
+and resources they leak are reported with the code that created them:
+  deno test --trace-ops
+
+Collect coverage, and exit with an error when less than 80% of the lines or
+70% of the branches of any tested file are covered:
+  deno test --unstable --coverage --coverage-threshold=lines:80,branches:70 \
+    --coverage-threshold-per-file",
     )
 }
 
@@ -1237,6 +1777,37 @@ fn config_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
   flags.config_path = matches.value_of("config").map(ToOwned::to_owned);
 }
 
+fn coverage_threshold_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("coverage-threshold")
+    .long("coverage-threshold")
+    .takes_value(true)
+    .require_equals(true)
+    .value_name("THRESHOLDS")
+    .validator(|val: String| val.parse::<CoverageThreshold>().map(|_| ()))
+    .help("Fail if the coverage is below these percentages")
+    .long_help(
+      "Fail if the coverage is below these percentages, given for lines and
+branches like lines:80,branches:70. They apply to the coverage of all files
+together, unless --coverage-threshold-per-file is set.",
+    )
+}
+
+fn coverage_threshold_per_file_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("coverage-threshold-per-file")
+    .long("coverage-threshold-per-file")
+    .requires("coverage-threshold")
+    .help("Apply the coverage thresholds to every file")
+}
+
+fn coverage_threshold_arg_parse(flags: &mut Flags, matches: &ArgMatches) {
+  flags.coverage_threshold =
+    matches.value_of("coverage-threshold").map(|value| {
+      let mut threshold: CoverageThreshold = value.parse().unwrap();
+      threshold.per_file = matches.is_present("coverage-threshold-per-file");
+      threshold
+    });
+}
+
 fn ca_file_arg<'a, 'b>() -> Arg<'a, 'b> {
   Arg::with_name("cert")
     .long("cert")
@@ -1628,6 +2199,37 @@ mod tests {
     );
   }
 
+  #[test]
+  fn run_coverage() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--coverage=cov_profile",
+      "script.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Run {
+          script: "script.ts".to_string(),
+        },
+        coverage: true,
+        coverage_dir: Some(PathBuf::from("cov_profile")),
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--coverage=cov_profile",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn run_reload_allow_write() {
     let r = flags_from_vec_safe(svec![
@@ -1801,6 +2403,7 @@ mod tests {
           ignore: vec![],
           check: false,
           files: vec!["script_1.ts".to_string(), "script_2.ts".to_string()],
+          options: FmtOptionsConfig::default(),
         },
         ..Flags::default()
       }
@@ -1814,6 +2417,7 @@ mod tests {
           ignore: vec![],
           check: true,
           files: vec![],
+          options: FmtOptionsConfig::default(),
         },
         ..Flags::default()
       }
@@ -1827,10 +2431,48 @@ mod tests {
           ignore: vec![],
           check: false,
           files: vec![],
+          options: FmtOptionsConfig::default(),
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn fmt_with_options() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "fmt",
+      "--config",
+      "deno.jsonc",
+      "--options-use-tabs",
+      "--options-line-width=60",
+      "--options-indent-width=4",
+      "--options-single-quote",
+      "--options-prose-wrap=never"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Fmt {
+          ignore: vec![],
+          check: false,
+          files: vec![],
+          options: FmtOptionsConfig {
+            use_tabs: Some(true),
+            line_width: Some(60),
+            indent_width: Some(4),
+            single_quote: Some(true),
+            prose_wrap: Some(ProseWrap::Never),
+          },
         },
+        config_path: Some("deno.jsonc".to_string()),
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec!["deno", "fmt", "--options-line-width=0"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -1848,8 +2490,9 @@ mod tests {
         subcommand: DenoSubcommand::Lint {
           files: vec!["script_1.ts".to_string(), "script_2.ts".to_string()],
           rules: false,
-          json: false,
+          reporter: LintReporterKind::Pretty,
           ignore: vec![],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -1868,8 +2511,9 @@ mod tests {
         subcommand: DenoSubcommand::Lint {
           files: vec![],
           rules: false,
-          json: false,
+          reporter: LintReporterKind::Pretty,
           ignore: svec!["script_1.ts", "script_2.ts"],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -1883,8 +2527,9 @@ mod tests {
         subcommand: DenoSubcommand::Lint {
           files: vec![],
           rules: true,
-          json: false,
+          reporter: LintReporterKind::Pretty,
           ignore: vec![],
+          fix: false,
         },
         unstable: true,
         ..Flags::default()
@@ -1895,27 +2540,106 @@ mod tests {
       "deno",
       "lint",
       "--unstable",
-      "--json",
-      "script_1.ts"
+      "--config",
+      "deno.jsonc",
+      "--rules"
     ]);
     assert_eq!(
       r.unwrap(),
       Flags {
         subcommand: DenoSubcommand::Lint {
-          files: vec!["script_1.ts".to_string()],
-          rules: false,
-          json: true,
+          files: vec![],
+          rules: true,
+          reporter: LintReporterKind::Pretty,
           ignore: vec![],
+          fix: false,
         },
+        config_path: Some("deno.jsonc".to_string()),
         unstable: true,
         ..Flags::default()
       }
     );
-  }
 
-  #[test]
-  fn types() {
-    let r = flags_from_vec_safe(svec!["deno", "types"]);
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "lint",
+      "--unstable",
+      "--json",
+      "script_1.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Lint {
+          files: vec!["script_1.ts".to_string()],
+          rules: false,
+          reporter: LintReporterKind::Json,
+          ignore: vec![],
+          fix: false,
+        },
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "lint",
+      "--unstable",
+      "--fix",
+      "script_1.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Lint {
+          files: vec!["script_1.ts".to_string()],
+          rules: false,
+          reporter: LintReporterKind::Pretty,
+          ignore: vec![],
+          fix: true,
+        },
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "lint",
+      "--unstable",
+      "--reporter=sarif",
+      "script_1.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Lint {
+          files: vec!["script_1.ts".to_string()],
+          rules: false,
+          reporter: LintReporterKind::Sarif,
+          ignore: vec![],
+          fix: false,
+        },
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "lint",
+      "--unstable",
+      "--reporter=json",
+      "--json",
+      "script_1.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn types() {
+    let r = flags_from_vec_safe(svec!["deno", "types"]);
     assert_eq!(
       r.unwrap(),
       Flags {
@@ -2257,6 +2981,52 @@ mod tests {
     );
   }
 
+  #[test]
+  fn bench() {
+    let r = flags_from_vec_safe(svec!["deno", "bench", "--unstable"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bench {
+          include: None,
+          filter: None,
+          json: false,
+        },
+        allow_hrtime: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn bench_with_flags() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "bench",
+      "--unstable",
+      "--allow-read",
+      "--filter=parse",
+      "--json",
+      "dir1/",
+      "foo_bench.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Bench {
+          include: Some(svec!["dir1/", "foo_bench.ts"]),
+          filter: Some("parse".to_string()),
+          json: true,
+        },
+        allow_hrtime: true,
+        allow_read: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
   #[test]
   fn bundle() {
     let r = flags_from_vec_safe(svec!["deno", "bundle", "source.ts"]);
@@ -2855,6 +3625,14 @@ mod tests {
           allow_none: true,
           quiet: false,
           include: Some(svec!["dir1/", "dir2/"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
         },
         allow_net: true,
         ..Flags::default()
@@ -2874,6 +3652,14 @@ mod tests {
           quiet: false,
           filter: Some("foo".to_string()),
           include: Some(svec!["dir1"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
         },
         ..Flags::default()
       }
@@ -2893,6 +3679,14 @@ mod tests {
           quiet: false,
           filter: Some("- foo".to_string()),
           include: Some(svec!["dir1"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
         },
         ..Flags::default()
       }
@@ -2917,14 +3711,438 @@ mod tests {
           quiet: false,
           filter: None,
           include: Some(svec!["dir1"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        coverage: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_coverage_dir() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--coverage=cov_profile",
+      "dir1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
         },
         coverage: true,
+        coverage_dir: Some(PathBuf::from("cov_profile")),
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_coverage_threshold() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--unstable",
+      "--coverage",
+      "--coverage-threshold=lines:80,branches:70",
+      "--coverage-threshold-per-file"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        coverage: true,
+        coverage_threshold: Some(CoverageThreshold {
+          lines: Some(80.0),
+          branches: Some(70.0),
+          per_file: true,
+        }),
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--coverage-threshold=lines:80"
+    ]);
+    assert!(r.is_err());
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "coverage",
+      "--coverage-threshold=lines:80,functions:70",
+      "cov_profile"
+    ]);
+    assert!(r.is_err());
+    // The coverage saved by `deno run` is checked by `deno coverage`.
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "run",
+      "--unstable",
+      "--coverage=cov_profile",
+      "--coverage-threshold=lines:80",
+      "script.ts"
+    ]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn coverage() {
+    let r = flags_from_vec_safe(svec!["deno", "coverage", "cov_profile"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Coverage {
+          dir: PathBuf::from("cov_profile"),
+          reporter: CoverageReporterKind::Pretty,
+          output: None,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "coverage",
+      "--unstable",
+      "--reporter=lcov",
+      "--output=cov.lcov",
+      "cov_profile"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Coverage {
+          dir: PathBuf::from("cov_profile"),
+          reporter: CoverageReporterKind::Lcov,
+          output: Some(PathBuf::from("cov.lcov")),
+        },
         unstable: true,
         ..Flags::default()
       }
     );
   }
 
+  #[test]
+  fn test_jobs() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs=4", "dir1"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          jobs: 4,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs", "dir1"]);
+    match r.unwrap().subcommand {
+      DenoSubcommand::Test { jobs, include, .. } => {
+        assert_eq!(jobs, num_cpus::get());
+        assert_eq!(include, Some(svec!["dir1"]));
+      }
+      _ => unreachable!(),
+    }
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--jobs=0"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_watch() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--unstable", "--watch"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        watch: true,
+        unstable: true,
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_timeout() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--test-timeout=5000"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: Some(5000),
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--test-timeout=0"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_doc() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--doc", "mod.ts"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["mod.ts"]),
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: true,
+          shuffle: None,
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--doc", "--watch"]);
+    assert!(r.unwrap().watch);
+  }
+
+  #[test]
+  fn test_trace_ops() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--trace-ops"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: true,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_shuffle() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=1234"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: Some(1234),
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r =
+      flags_from_vec_safe(svec!["deno", "test", "--seed", "250", "--shuffle"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: Some(250),
+          trace_ops: false,
+        },
+        seed: Some(250),
+        v8_flags: Some(svec!["--random-seed=250"]),
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle"]);
+    assert!(matches!(
+      r.unwrap().subcommand,
+      DenoSubcommand::Test {
+        shuffle: Some(_),
+        ..
+      }
+    ));
+
+    let r = flags_from_vec_safe(svec!["deno", "test", "--shuffle=abc"]);
+    assert!(r.is_err());
+  }
+
+  #[test]
+  fn test_update_snapshots() {
+    let r = flags_from_vec_safe(svec!["deno", "test", "--update-snapshots"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: None,
+          jobs: 1,
+          reporter: TestReporterKind::Pretty,
+          reporter_output: None,
+          timeout: None,
+          update_snapshots: true,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+  }
+
+  #[test]
+  fn test_reporter() {
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--reporter=junit",
+      "--reporter-output",
+      "junit.xml",
+      "dir1"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Test {
+          fail_fast: false,
+          allow_none: false,
+          quiet: false,
+          filter: None,
+          include: Some(svec!["dir1"]),
+          jobs: 1,
+          reporter: TestReporterKind::Junit,
+          reporter_output: Some(PathBuf::from("junit.xml")),
+          timeout: None,
+          update_snapshots: false,
+          doc: false,
+          shuffle: None,
+          trace_ops: false,
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "test",
+      "--reporter-output=results.txt"
+    ]);
+    assert!(r.is_err());
+  }
+
   #[test]
   fn run_with_cafile() {
     let r = flags_from_vec_safe(svec![
@@ -3071,6 +4289,8 @@ mod tests {
           json: true,
           source_file: Some("path/to/module.ts".to_string()),
           filter: None,
+          html_output: None,
+          entry_points: vec![],
         },
         ..Flags::default()
       }
@@ -3090,6 +4310,8 @@ mod tests {
           json: false,
           source_file: Some("path/to/module.ts".to_string()),
           filter: Some("SomeClass.someField".to_string()),
+          html_output: None,
+          entry_points: vec![],
         },
         ..Flags::default()
       }
@@ -3104,6 +4326,8 @@ mod tests {
           json: false,
           source_file: None,
           filter: None,
+          html_output: None,
+          entry_points: vec![],
         },
         ..Flags::default()
       }
@@ -3119,6 +4343,8 @@ mod tests {
           json: false,
           source_file: Some("--builtin".to_string()),
           filter: Some("Deno.Listener".to_string()),
+          html_output: None,
+          entry_points: vec![],
         },
         ..Flags::default()
       }
@@ -3138,10 +4364,55 @@ mod tests {
           json: false,
           source_file: Some("path/to/module.js".to_string()),
           filter: None,
+          html_output: None,
+          entry_points: vec![],
         },
         ..Flags::default()
       }
     );
+
+    let r = flags_from_vec_safe(svec![
+      "deno",
+      "doc",
+      "--html",
+      "--output=site",
+      "mod.ts",
+      "a.ts",
+      "b.ts"
+    ]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Doc {
+          private: false,
+          json: false,
+          source_file: None,
+          filter: None,
+          html_output: Some(PathBuf::from("site")),
+          entry_points: svec!["mod.ts", "a.ts", "b.ts"],
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "doc", "--html"]);
+    assert_eq!(
+      r.unwrap(),
+      Flags {
+        subcommand: DenoSubcommand::Doc {
+          private: false,
+          json: false,
+          source_file: None,
+          filter: None,
+          html_output: Some(PathBuf::from("docs")),
+          entry_points: vec![],
+        },
+        ..Flags::default()
+      }
+    );
+
+    let r = flags_from_vec_safe(svec!["deno", "doc", "--html", "--json"]);
+    assert!(r.is_err());
   }
 
   #[test]
@@ -1,19 +1,29 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
 //! This module provides file formatting utilities using
-//! [`dprint-plugin-typescript`](https://github.com/dsherret/dprint-plugin-typescript).
+//! [`dprint-plugin-typescript`](https://github.com/dsherret/dprint-plugin-typescript),
+//! [`dprint-plugin-markdown`](https://github.com/dprint/dprint-plugin-markdown) and
+//! [`dprint-plugin-json`](https://github.com/dprint/dprint-plugin-json).
 //!
 //! At the moment it is only consumed using CLI but in
 //! the future it can be easily extended to provide
 //! the same functions as ops available in JS runtime.
 
 use crate::colors;
+use crate::config_file::ConfigFile;
+use crate::config_file::FmtOptionsConfig;
+use crate::config_file::ProseWrap;
 use crate::diff::diff;
 use crate::fs::files_in_subtree;
+use crate::fs::get_lowercase_extension;
+use crate::fs::is_supported_ext;
+use crate::incremental_cache;
+use crate::incremental_cache::IncrementalCache;
 use crate::text_encoding;
 use deno_core::error::generic_error;
 use deno_core::error::AnyError;
 use deno_core::futures;
+use deno_core::serde_json;
 use dprint_plugin_typescript as dprint;
 use std::fs;
 use std::io::stdin;
@@ -27,37 +37,58 @@ use std::sync::{Arc, Mutex};
 
 const BOM_CHAR: char = '\u{FEFF}';
 
-/// Format JavaScript/TypeScript files.
+/// Format JavaScript/TypeScript, Markdown and JSON files.
 ///
 /// First argument and ignore supports globs, and if it is `None`
 /// then the current directory is recursively walked.
+///
+/// Formatting options are read from the `fmt` section of the configuration
+/// file, if one is provided, and then overridden by `options`.
+///
+/// Files that are known to be formatted from a previous run are skipped,
+/// using the cache of the project stored in `incremental_cache_dir`.
 pub async fn format(
   args: Vec<String>,
   check: bool,
   exclude: Vec<String>,
+  maybe_config_path: Option<String>,
+  options: FmtOptionsConfig,
+  incremental_cache_dir: PathBuf,
 ) -> Result<(), AnyError> {
+  let root = incremental_cache::project_root(maybe_config_path.as_deref())?;
+  let options = resolve_fmt_options(maybe_config_path, &options)?;
   if args.len() == 1 && args[0] == "-" {
-    return format_stdin(check);
+    return format_stdin(check, &options);
   }
   // collect all files provided.
-  let mut target_files = collect_files(args)?;
+  let mut target_files = collect_files(args, is_supported)?;
   if !exclude.is_empty() {
     // collect all files to be ignored
     // and retain only files that should be formatted.
-    let ignore_files = collect_files(exclude)?;
+    let ignore_files = collect_files(exclude, is_supported)?;
     target_files.retain(|f| !ignore_files.contains(&f));
   }
-  let config = get_config();
-  if check {
-    check_source_files(config, target_files).await
+  let config = FormatConfig::new(&options);
+  let incremental_cache = Arc::new(IncrementalCache::new(
+    &incremental_cache_dir,
+    &root,
+    &[&serde_json::to_vec(&options)?],
+  ));
+  let result = if check {
+    check_source_files(config, target_files, incremental_cache.clone()).await
   } else {
-    format_source_files(config, target_files).await
+    format_source_files(config, target_files, incremental_cache.clone()).await
+  };
+  if let Err(err) = incremental_cache.save() {
+    debug!("Failed to save fmt cache: {}", err);
   }
+  result
 }
 
 async fn check_source_files(
-  config: dprint::configuration::Configuration,
+  config: FormatConfig,
   paths: Vec<PathBuf>,
+  incremental_cache: Arc<IncrementalCache>,
 ) -> Result<(), AnyError> {
   let not_formatted_files_count = Arc::new(AtomicUsize::new(0));
   let checked_files_count = Arc::new(AtomicUsize::new(0));
@@ -71,10 +102,15 @@ async fn check_source_files(
     move |file_path| {
       checked_files_count.fetch_add(1, Ordering::Relaxed);
       let file_text = read_file_contents(&file_path)?.text;
-      let r = dprint::format_text(&file_path, &file_text, &config);
+      if incremental_cache.is_file_same(&file_path, &file_text) {
+        return Ok(());
+      }
+      let r = format_text(&file_path, &file_text, &config);
       match r {
         Ok(formatted_text) => {
-          if formatted_text != file_text {
+          if formatted_text == file_text {
+            incremental_cache.update_file(&file_path, &file_text);
+          } else {
             not_formatted_files_count.fetch_add(1, Ordering::Relaxed);
             let _g = output_lock.lock().unwrap();
             match diff(&file_text, &formatted_text) {
@@ -126,8 +162,9 @@ async fn check_source_files(
 }
 
 async fn format_source_files(
-  config: dprint::configuration::Configuration,
+  config: FormatConfig,
   paths: Vec<PathBuf>,
+  incremental_cache: Arc<IncrementalCache>,
 ) -> Result<(), AnyError> {
   let formatted_files_count = Arc::new(AtomicUsize::new(0));
   let checked_files_count = Arc::new(AtomicUsize::new(0));
@@ -139,7 +176,10 @@ async fn format_source_files(
     move |file_path| {
       checked_files_count.fetch_add(1, Ordering::Relaxed);
       let file_contents = read_file_contents(&file_path)?;
-      let r = dprint::format_text(&file_path, &file_contents.text, &config);
+      if incremental_cache.is_file_same(&file_path, &file_contents.text) {
+        return Ok(());
+      }
+      let r = format_text(&file_path, &file_contents.text, &config);
       match r {
         Ok(formatted_text) => {
           if formatted_text != file_contents.text {
@@ -147,13 +187,14 @@ async fn format_source_files(
               &file_path,
               FileContents {
                 had_bom: file_contents.had_bom,
-                text: formatted_text,
+                text: formatted_text.clone(),
               },
             )?;
             formatted_files_count.fetch_add(1, Ordering::Relaxed);
             let _g = output_lock.lock().unwrap();
             info!("{}", file_path.to_string_lossy());
           }
+          incremental_cache.update_file(&file_path, &formatted_text);
         }
         Err(e) => {
           let _g = output_lock.lock().unwrap();
@@ -186,15 +227,18 @@ async fn format_source_files(
 /// Format stdin and write result to stdout.
 /// Treats input as TypeScript.
 /// Compatible with `--check` flag.
-fn format_stdin(check: bool) -> Result<(), AnyError> {
+fn format_stdin(
+  check: bool,
+  options: &FmtOptionsConfig,
+) -> Result<(), AnyError> {
   let mut source = String::new();
   if stdin().read_to_string(&mut source).is_err() {
     return Err(generic_error("Failed to read from stdin"));
   }
-  let config = get_config();
+  let config = FormatConfig::new(options);
 
   // dprint will fallback to jsx parsing if parsing this as a .ts file doesn't work
-  match dprint::format_text(&PathBuf::from("_stdin.ts"), &source, &config) {
+  match format_text(&PathBuf::from("_stdin.ts"), &source, &config) {
     Ok(formatted_text) => {
       if check {
         if formatted_text != source {
@@ -220,32 +264,35 @@ fn files_str(len: usize) -> &'static str {
 }
 
 fn is_supported(path: &Path) -> bool {
-  let lowercase_ext = path
-    .extension()
-    .and_then(|e| e.to_str())
-    .map(|e| e.to_lowercase());
-  if let Some(ext) = lowercase_ext {
-    ext == "ts" || ext == "tsx" || ext == "js" || ext == "jsx" || ext == "mjs"
-  } else {
-    false
-  }
+  is_supported_ext(path)
+    || matches!(
+      get_lowercase_extension(path).as_deref(),
+      Some("md") | Some("markdown") | Some("json") | Some("jsonc")
+    )
 }
 
-pub fn collect_files(
+/// Collect the files to process from the provided paths. Directories are
+/// walked recursively and only files matching `predicate` are kept; if no
+/// paths are provided the current directory is walked.
+pub fn collect_files<F>(
   files: Vec<String>,
-) -> Result<Vec<PathBuf>, std::io::Error> {
+  predicate: F,
+) -> Result<Vec<PathBuf>, std::io::Error>
+where
+  F: Fn(&Path) -> bool,
+{
   let mut target_files: Vec<PathBuf> = vec![];
 
   if files.is_empty() {
     target_files.extend(files_in_subtree(
       std::env::current_dir()?.canonicalize()?,
-      is_supported,
+      &predicate,
     ));
   } else {
     for arg in files {
       let p = PathBuf::from(arg);
       if p.is_dir() {
-        target_files.extend(files_in_subtree(p.canonicalize()?, is_supported));
+        target_files.extend(files_in_subtree(p.canonicalize()?, &predicate));
       } else {
         target_files.push(p.canonicalize()?);
       };
@@ -255,9 +302,163 @@ pub fn collect_files(
   Ok(target_files)
 }
 
-fn get_config() -> dprint::configuration::Configuration {
+/// Merge the options from the configuration file with the ones passed on
+/// the command line, the latter taking precedence.
+fn resolve_fmt_options(
+  maybe_config_path: Option<String>,
+  cli_options: &FmtOptionsConfig,
+) -> Result<FmtOptionsConfig, AnyError> {
+  let mut options = if let Some(config_path) = maybe_config_path {
+    let config_file = ConfigFile::read(&config_path)?;
+    config_file
+      .to_fmt_config()?
+      .map(|config| config.options)
+      .unwrap_or_default()
+  } else {
+    FmtOptionsConfig::default()
+  };
+  options.merge(cli_options);
+  Ok(options)
+}
+
+/// Formatter configurations for every supported file type.
+#[derive(Clone)]
+struct FormatConfig {
+  typescript: dprint::configuration::Configuration,
+  markdown: dprint_plugin_markdown::configuration::Configuration,
+  json: dprint_plugin_json::configuration::Configuration,
+}
+
+impl FormatConfig {
+  fn new(options: &FmtOptionsConfig) -> Self {
+    FormatConfig {
+      typescript: get_typescript_config(options),
+      markdown: get_markdown_config(options),
+      json: get_json_config(options),
+    }
+  }
+}
+
+/// Format `file_text` with the formatter matching the extension of
+/// `file_path`. Files with an unknown extension are treated as TypeScript.
+fn format_text(
+  file_path: &Path,
+  file_text: &str,
+  config: &FormatConfig,
+) -> Result<String, String> {
+  match get_lowercase_extension(file_path).as_deref() {
+    Some("md") | Some("markdown") => format_markdown(file_text, config),
+    Some("json") | Some("jsonc") => {
+      dprint_plugin_json::format_text(file_text, &config.json)
+        .map_err(|e| e.to_string())
+    }
+    _ => dprint::format_text(file_path, file_text, &config.typescript),
+  }
+}
+
+/// Format markdown text, including the fenced code blocks tagged as
+/// JavaScript, TypeScript or JSON.
+fn format_markdown(
+  file_text: &str,
+  config: &FormatConfig,
+) -> Result<String, String> {
+  let typescript_config = config.typescript.clone();
+  let json_config = config.json.clone();
+  dprint_plugin_markdown::format_text(
+    file_text,
+    &config.markdown,
+    Box::new(move |tag, text, line_width| {
+      let tag = tag.to_lowercase();
+      // It's important to give dprint the proper file extension, otherwise
+      // it might parse the code block twice.
+      let extension = match tag.as_str() {
+        "javascript" => "js",
+        "typescript" => "ts",
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "json" | "jsonc" => &tag,
+        _ => return Ok(text.to_string()),
+      };
+      if extension == "json" || extension == "jsonc" {
+        let mut json_config = json_config.clone();
+        json_config.line_width = line_width;
+        dprint_plugin_json::format_text(text, &json_config)
+      } else {
+        let fake_filename = PathBuf::from(format!("_codeblock.{}", extension));
+        let mut codeblock_config = typescript_config.clone();
+        codeblock_config.line_width = line_width;
+        dprint::format_text(&fake_filename, text, &codeblock_config)
+          .map_err(|e| e.into())
+      }
+    }),
+  )
+  .map_err(|e| e.to_string())
+}
+
+fn get_typescript_config(
+  options: &FmtOptionsConfig,
+) -> dprint::configuration::Configuration {
   use dprint::configuration::*;
-  ConfigurationBuilder::new().deno().build()
+  let mut builder = ConfigurationBuilder::new();
+  builder.deno();
+  if let Some(use_tabs) = options.use_tabs {
+    builder.use_tabs(use_tabs);
+  }
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(indent_width) = options.indent_width {
+    builder.indent_width(indent_width);
+  }
+  if let Some(single_quote) = options.single_quote {
+    if single_quote {
+      builder.quote_style(QuoteStyle::PreferSingle);
+    } else {
+      builder.quote_style(QuoteStyle::PreferDouble);
+    }
+  }
+  builder.build()
+}
+
+fn get_markdown_config(
+  options: &FmtOptionsConfig,
+) -> dprint_plugin_markdown::configuration::Configuration {
+  use dprint_plugin_markdown::configuration::*;
+  let mut builder = ConfigurationBuilder::new();
+  // Matches `.dprintrc.json` in the repository
+  builder
+    .line_width(80)
+    .text_wrap(TextWrap::Always)
+    .ignore_directive("deno-fmt-ignore")
+    .ignore_start_directive("deno-fmt-ignore-start")
+    .ignore_end_directive("deno-fmt-ignore-end");
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(prose_wrap) = options.prose_wrap {
+    builder.text_wrap(match prose_wrap {
+      ProseWrap::Always => TextWrap::Always,
+      ProseWrap::Never => TextWrap::Never,
+      ProseWrap::Preserve => TextWrap::Maintain,
+    });
+  }
+  builder.build()
+}
+
+fn get_json_config(
+  options: &FmtOptionsConfig,
+) -> dprint_plugin_json::configuration::Configuration {
+  use dprint_plugin_json::configuration::*;
+  let mut builder = ConfigurationBuilder::new();
+  builder.line_width(80).indent_width(2);
+  if let Some(use_tabs) = options.use_tabs {
+    builder.use_tabs(use_tabs);
+  }
+  if let Some(line_width) = options.line_width {
+    builder.line_width(line_width);
+  }
+  if let Some(indent_width) = options.indent_width {
+    builder.indent_width(indent_width);
+  }
+  builder.build()
 }
 
 struct FileContents {
@@ -341,7 +542,11 @@ where
 #[test]
 fn test_is_supported() {
   assert!(!is_supported(Path::new("tests/subdir/redirects")));
-  assert!(!is_supported(Path::new("README.md")));
+  assert!(is_supported(Path::new("README.md")));
+  assert!(is_supported(Path::new("README.MARKDOWN")));
+  assert!(is_supported(Path::new("tsconfig.json")));
+  assert!(is_supported(Path::new("deno.jsonc")));
+  assert!(!is_supported(Path::new("Cargo.toml")));
   assert!(is_supported(Path::new("lib/typescript.d.ts")));
   assert!(is_supported(Path::new("cli/tests/001_hello.js")));
   assert!(is_supported(Path::new("cli/tests/002_hello.ts")));
@@ -354,3 +559,49 @@ fn test_is_supported() {
   assert!(is_supported(Path::new("foo.mjs")));
   assert!(!is_supported(Path::new("foo.mjsx")));
 }
+
+#[test]
+fn test_get_typescript_config() {
+  let source = "const a = \"a\";\n";
+  let config = get_typescript_config(&FmtOptionsConfig::default());
+  let formatted =
+    dprint::format_text(&PathBuf::from("test.ts"), source, &config).unwrap();
+  assert_eq!(formatted, source);
+
+  let config = get_typescript_config(&FmtOptionsConfig {
+    single_quote: Some(true),
+    indent_width: Some(4),
+    ..Default::default()
+  });
+  let formatted = dprint::format_text(
+    &PathBuf::from("test.ts"),
+    "function f() {\n  return \"a\";\n}\n",
+    &config,
+  )
+  .unwrap();
+  assert_eq!(formatted, "function f() {\n    return 'a';\n}\n");
+}
+
+#[test]
+fn test_format_markdown() {
+  let config = FormatConfig::new(&FmtOptionsConfig::default());
+  let source = "# Title\n\n```ts\nconst a = 1\n```\n\n```sh\nls  -l\n```\n";
+  let formatted =
+    format_text(&PathBuf::from("README.md"), source, &config).unwrap();
+  assert_eq!(
+    formatted,
+    "# Title\n\n```ts\nconst a = 1;\n```\n\n```sh\nls  -l\n```\n"
+  );
+}
+
+#[test]
+fn test_format_json() {
+  let config = FormatConfig::new(&FmtOptionsConfig::default());
+  let source = "{\"a\":[1,2],\n// comment\n\"b\":true}";
+  let formatted =
+    format_text(&PathBuf::from("deno.jsonc"), source, &config).unwrap();
+  assert_eq!(
+    formatted,
+    "{\n  \"a\": [1, 2],\n  // comment\n  \"b\": true\n}\n"
+  );
+}
@@ -104,6 +104,30 @@ mod tests {
     let expected = Path::new("/a");
     assert_eq!(resolve_from_cwd(expected).unwrap(), expected);
   }
+
+  #[test]
+  fn test_is_supported_ext() {
+    assert!(!is_supported_ext(Path::new("tests/subdir/redirects")));
+    assert!(!is_supported_ext(Path::new("README.md")));
+    assert!(is_supported_ext(Path::new("lib/typescript.d.ts")));
+    assert!(is_supported_ext(Path::new("cli/tests/001_hello.js")));
+    assert!(is_supported_ext(Path::new("foo.TSX")));
+    assert!(is_supported_ext(Path::new("foo.mjs")));
+    assert!(!is_supported_ext(Path::new("foo.mjsx")));
+  }
+
+  #[test]
+  fn test_is_supported_test_path() {
+    let is_test = |path: &str| is_supported_test_path(Path::new(path), "test");
+    assert!(is_test("foo/bar_test.ts"));
+    assert!(is_test("foo/bar.test.mjs"));
+    assert!(is_test("foo/test.jsx"));
+    assert!(!is_test("foo/latest.ts"));
+    assert!(!is_test("foo/test.md"));
+    assert!(!is_test("foo/bar_test.TS"));
+    assert!(!is_test("foo/bar.bench.ts"));
+    assert!(!is_test("test"));
+  }
 }
 
 pub fn files_in_subtree<F>(root: PathBuf, filter: F) -> Vec<PathBuf>
@@ -119,3 +143,36 @@ where
     .filter(|p| if p.is_dir() { false } else { filter(&p) })
     .collect()
 }
+
+pub fn get_lowercase_extension(path: &Path) -> Option<String> {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_lowercase())
+}
+
+/// Checks if the path has a JavaScript or TypeScript extension.
+pub fn is_supported_ext(path: &Path) -> bool {
+  if let Some(ext) = get_lowercase_extension(path) {
+    matches!(ext.as_str(), "ts" | "tsx" | "js" | "jsx" | "mjs")
+  } else {
+    false
+  }
+}
+
+/// Checks if the path is of a module named after `suffix`, like the
+/// `foo_test.ts`, `foo.test.ts` and `test.ts` test modules.
+pub fn is_supported_test_path(path: &Path, suffix: &str) -> bool {
+  let file_name = match path.file_name() {
+    Some(file_name) => file_name.to_string_lossy(),
+    None => return false,
+  };
+  let (stem, ext) = match file_name.rfind('.') {
+    Some(index) => (&file_name[..index], &file_name[index + 1..]),
+    None => return false,
+  };
+  matches!(ext, "ts" | "tsx" | "js" | "jsx" | "mjs")
+    && (stem == suffix
+      || stem.ends_with(&format!("_{}", suffix))
+      || stem.ends_with(&format!(".{}", suffix)))
+}
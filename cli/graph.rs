@@ -537,6 +537,7 @@ impl Graph {
       jsx_factory: compiler_options.jsx_factory,
       jsx_fragment_factory: compiler_options.jsx_fragment_factory,
       transform_jsx,
+      inject_helpers: false,
     };
 
     let mut emit_count: u128 = 0;
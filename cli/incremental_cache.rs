@@ -0,0 +1,191 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! A cache of files that were already processed by `deno fmt` or `deno lint`
+//! without any findings, so unchanged files can be skipped on the next run.
+//!
+//! Each project root gets its own cache file, so running a tool in one
+//! project does not invalidate the cache of another. Entries are keyed by
+//! the hash of the file contents. The cache of a root is invalidated when the
+//! "state" changes, which is the Deno version together with the tool
+//! configuration.
+
+use crate::checksum;
+use deno_core::error::AnyError;
+use deno_core::serde_json;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheData {
+  state_hash: String,
+  files: HashMap<PathBuf, String>,
+}
+
+pub struct IncrementalCache {
+  path: PathBuf,
+  state_hash: String,
+  previous_files: HashMap<PathBuf, String>,
+  files: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl IncrementalCache {
+  /// Load the cache of the project at `root` from `cache_dir`. `state` must
+  /// contain everything that affects the result of the tool besides the file
+  /// contents.
+  pub fn new(cache_dir: &Path, root: &Path, state: &[&[u8]]) -> Self {
+    let root_hash = checksum::gen(&[root.to_string_lossy().as_bytes()]);
+    let path = cache_dir.join(format!("{}.json", root_hash));
+    let mut state_parts: Vec<&[u8]> = vec![crate::version::DENO.as_bytes()];
+    state_parts.extend_from_slice(state);
+    let state_hash = checksum::gen(&state_parts);
+    let previous_files = match Self::load(&path) {
+      Some(data) if data.state_hash == state_hash => data.files,
+      _ => HashMap::new(),
+    };
+    let files = Mutex::new(previous_files.clone());
+    IncrementalCache {
+      path,
+      state_hash,
+      previous_files,
+      files,
+    }
+  }
+
+  fn load(path: &Path) -> Option<CacheData> {
+    let text = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&text) {
+      Ok(data) => Some(data),
+      Err(err) => {
+        debug!("Ignoring corrupt cache {}: {}", path.display(), err);
+        None
+      }
+    }
+  }
+
+  /// Returns true if `file_text` is identical to the text that was recorded
+  /// for `file_path` in a previous run.
+  pub fn is_file_same(&self, file_path: &Path, file_text: &str) -> bool {
+    match self.previous_files.get(file_path) {
+      Some(hash) => *hash == checksum::gen(&[file_text.as_bytes()]),
+      None => false,
+    }
+  }
+
+  /// Record that `file_text` of `file_path` has no findings.
+  pub fn update_file(&self, file_path: &Path, file_text: &str) {
+    let hash = checksum::gen(&[file_text.as_bytes()]);
+    let mut files = self.files.lock().unwrap();
+    files.insert(file_path.to_path_buf(), hash);
+  }
+
+  /// Persist the cache. Entries of files that no longer exist are dropped.
+  /// The file is replaced atomically, so concurrent runs never observe a
+  /// partially written cache.
+  pub fn save(&self) -> Result<(), AnyError> {
+    let mut files = self.files.lock().unwrap().clone();
+    files.retain(|path, _| path.exists());
+    let data = CacheData {
+      state_hash: self.state_hash.clone(),
+      files,
+    };
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let temp_path = self.path.with_extension(format!("{}.tmp", process::id()));
+    fs::write(&temp_path, serde_json::to_string(&data)?)?;
+    if let Err(err) = fs::rename(&temp_path, &self.path) {
+      let _ = fs::remove_file(&temp_path);
+      return Err(err.into());
+    }
+    Ok(())
+  }
+}
+
+/// The root of the project the tool runs in: the directory of the
+/// configuration file if one is provided, otherwise the current directory.
+pub fn project_root(
+  maybe_config_path: Option<&str>,
+) -> Result<PathBuf, AnyError> {
+  let cwd = std::env::current_dir()?;
+  let root = match maybe_config_path {
+    Some(config_path) => {
+      let config_path = cwd.join(config_path).canonicalize()?;
+      config_path.parent().map(Path::to_path_buf).unwrap_or(cwd)
+    }
+    None => cwd,
+  };
+  Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_incremental_cache() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let cache_dir = temp_dir.path().join("cache");
+    let root = temp_dir.path();
+    let file_path = temp_dir.path().join("mod.ts");
+    fs::write(&file_path, "const a = 1;\n").unwrap();
+
+    let cache = IncrementalCache::new(&cache_dir, root, &[b"state"]);
+    assert!(!cache.is_file_same(&file_path, "const a = 1;\n"));
+    cache.update_file(&file_path, "const a = 1;\n");
+    // Updates are only visible in the next run.
+    assert!(!cache.is_file_same(&file_path, "const a = 1;\n"));
+    cache.save().unwrap();
+
+    let cache = IncrementalCache::new(&cache_dir, root, &[b"state"]);
+    assert!(cache.is_file_same(&file_path, "const a = 1;\n"));
+    assert!(!cache.is_file_same(&file_path, "const a = 2;\n"));
+
+    // A different state invalidates every entry.
+    let cache = IncrementalCache::new(&cache_dir, root, &[b"other state"]);
+    assert!(!cache.is_file_same(&file_path, "const a = 1;\n"));
+  }
+
+  #[test]
+  fn test_incremental_cache_per_root() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let cache_dir = temp_dir.path().join("cache");
+    let root_a = temp_dir.path().join("a");
+    let root_b = temp_dir.path().join("b");
+    let file_path = root_a.join("mod.ts");
+    fs::create_dir_all(&root_a).unwrap();
+    fs::write(&file_path, "const a = 1;\n").unwrap();
+
+    let cache = IncrementalCache::new(&cache_dir, &root_a, &[b"state"]);
+    cache.update_file(&file_path, "const a = 1;\n");
+    cache.save().unwrap();
+
+    // Saving the cache of another root with a different state must not
+    // touch the cache of the first root.
+    let cache = IncrementalCache::new(&cache_dir, &root_b, &[b"other state"]);
+    cache.save().unwrap();
+
+    let cache = IncrementalCache::new(&cache_dir, &root_a, &[b"state"]);
+    assert!(cache.is_file_same(&file_path, "const a = 1;\n"));
+    let files: Vec<_> = fs::read_dir(&cache_dir).unwrap().collect();
+    assert_eq!(files.len(), 2);
+  }
+
+  #[test]
+  fn test_incremental_cache_corrupt() {
+    let temp_dir = TempDir::new().expect("tempdir fail");
+    let cache_dir = temp_dir.path();
+    let root = Path::new("/");
+    let cache = IncrementalCache::new(cache_dir, root, &[b"state"]);
+    fs::write(&cache.path, "{ not json").unwrap();
+    let cache = IncrementalCache::new(cache_dir, root, &[b"state"]);
+    assert!(!cache.is_file_same(Path::new("/mod.ts"), ""));
+  }
+}
@@ -26,6 +26,7 @@ use deno_core::v8;
 use std::cell::BorrowMutError;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::ffi::c_void;
 use std::mem::replace;
 use std::mem::take;
@@ -826,6 +827,8 @@ pub struct InspectorSession {
   v8_session: v8::UniqueRef<v8::inspector::V8InspectorSession>,
   response_tx_map: HashMap<i32, oneshot::Sender<serde_json::Value>>,
   next_message_id: i32,
+  subscriptions: HashSet<String>,
+  notification_queue: Vec<serde_json::Value>,
 }
 
 impl Deref for InspectorSession {
@@ -867,8 +870,25 @@ impl v8::inspector::ChannelImpl for InspectorSession {
 
   fn send_notification(
     &mut self,
-    _message: v8::UniquePtr<v8::inspector::StringBuffer>,
+    message: v8::UniquePtr<v8::inspector::StringBuffer>,
   ) {
+    // Notifications nobody subscribed to, such as the scripts parsed while
+    // the debugger is enabled, are dropped.
+    if self.subscriptions.is_empty() {
+      return;
+    }
+    let raw_message = message.unwrap().string().to_string();
+    let notification: serde_json::Value =
+      match serde_json::from_str(&raw_message) {
+        Ok(notification) => notification,
+        Err(_) => return,
+      };
+    let subscribed = notification["method"]
+      .as_str()
+      .map_or(false, |method| self.subscriptions.contains(method));
+    if subscribed {
+      self.notification_queue.push(notification);
+    }
   }
 
   fn flush_protocol_notifications(&mut self) {}
@@ -888,16 +908,32 @@ impl InspectorSession {
 
       let response_tx_map = HashMap::new();
       let next_message_id = 0;
+      let subscriptions = HashSet::new();
+      let notification_queue = Vec::new();
 
       Self {
         v8_channel,
         v8_session,
         response_tx_map,
         next_message_id,
+        subscriptions,
+        notification_queue,
       }
     })
   }
 
+  /// Queue the notifications of `method`, such as
+  /// `Runtime.executionContextCreated`, until they are taken.
+  pub fn subscribe(&mut self, method: &str) {
+    self.subscriptions.insert(method.to_string());
+  }
+
+  /// Take the notifications of the subscribed methods received since the
+  /// last call.
+  pub fn notifications(&mut self) -> Vec<serde_json::Value> {
+    take(&mut self.notification_queue)
+  }
+
   pub async fn post_message(
     &mut self,
     method: String,
@@ -8,50 +8,279 @@
 //! the same functions as ops available in JS runtime.
 use crate::ast;
 use crate::colors;
+use crate::config_file::ConfigFile;
+use crate::config_file::LintConfig;
+use crate::config_file::LintRulesConfig;
+use crate::escape::xml_escape;
 use crate::fmt::collect_files;
 use crate::fmt::run_parallelized;
 use crate::fmt_errors;
+use crate::fs::is_supported_ext;
+use crate::incremental_cache;
+use crate::incremental_cache::IncrementalCache;
+use crate::lint_fix;
+use crate::lint_fix::LintProblem;
+use crate::lint_plugin::LintPlugins;
 use crate::media_type::MediaType;
 use deno_core::error::{generic_error, AnyError, JsStackFrame};
 use deno_core::serde_json;
+use deno_core::serde_json::json;
 use deno_lint::diagnostic::LintDiagnostic;
 use deno_lint::linter::Linter;
 use deno_lint::linter::LinterBuilder;
 use deno_lint::rules;
 use deno_lint::rules::LintRule;
 use serde::Serialize;
+use std::collections::BTreeSet;
 use std::fs;
 use std::io::{stdin, Read};
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use swc_ecmascript::parser::Syntax;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LintReporterKind {
   Pretty,
   Json,
+  Sarif,
+  Checkstyle,
+  Junit,
+}
+
+impl std::str::FromStr for LintReporterKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "pretty" => Ok(LintReporterKind::Pretty),
+      "json" => Ok(LintReporterKind::Json),
+      "sarif" => Ok(LintReporterKind::Sarif),
+      "checkstyle" => Ok(LintReporterKind::Checkstyle),
+      "junit" => Ok(LintReporterKind::Junit),
+      _ => Err(format!("Unknown lint reporter: {}", s)),
+    }
+  }
 }
 
 fn create_reporter(kind: LintReporterKind) -> Box<dyn LintReporter + Send> {
   match kind {
     LintReporterKind::Pretty => Box::new(PrettyLintReporter::new()),
-    LintReporterKind::Json => Box::new(JsonLintReporter::new()),
+    LintReporterKind::Json => Box::new(CollectingLintReporter::new(print_json)),
+    LintReporterKind::Sarif => {
+      Box::new(CollectingLintReporter::new(print_sarif))
+    }
+    LintReporterKind::Checkstyle => {
+      Box::new(CollectingLintReporter::new(print_checkstyle))
+    }
+    LintReporterKind::Junit => {
+      Box::new(CollectingLintReporter::new(print_junit))
+    }
   }
 }
 
+/// Lint configuration with the paths of the configuration file resolved.
+struct LintSettings {
+  config: LintConfig,
+  include: Vec<String>,
+  exclude: Vec<String>,
+  overrides: Vec<(Vec<PathBuf>, LintRulesConfig)>,
+  plugins: Option<LintPlugins>,
+}
+
+impl LintSettings {
+  /// Read the `lint` section of the configuration file, if provided. Paths
+  /// in the file are relative to its directory.
+  fn new(maybe_config_path: Option<String>) -> Result<Self, AnyError> {
+    let config_file = match maybe_config_path {
+      Some(config_path) => ConfigFile::read(&config_path)?,
+      None => {
+        return Ok(LintSettings {
+          config: LintConfig::default(),
+          include: vec![],
+          exclude: vec![],
+          overrides: vec![],
+          plugins: None,
+        })
+      }
+    };
+    let config = config_file.to_lint_config()?.unwrap_or_default();
+    let config_dir = config_file.path.parent().unwrap().to_path_buf();
+    let resolve_path = |path: &String| {
+      let path = config_dir.join(path);
+      path.canonicalize().unwrap_or(path)
+    };
+
+    let plugins = if config.plugins.is_empty() {
+      None
+    } else {
+      let paths = config.plugins.iter().map(resolve_path).collect::<Vec<_>>();
+      let plugins = LintPlugins::load(&paths)?;
+      let all_rules = rules::get_all_rules();
+      for code in &plugins.rule_codes {
+        if all_rules.iter().any(|r| r.code() == code.as_str()) {
+          return Err(generic_error(format!(
+            "Lint plugin rule \"{}\" conflicts with a built-in rule",
+            code
+          )));
+        }
+      }
+      Some(plugins)
+    };
+    let plugin_codes = match &plugins {
+      Some(plugins) => plugins.rule_codes.clone(),
+      None => vec![],
+    };
+
+    validate_rules_config(&config.rules, &plugin_codes, &config_file)?;
+    for lint_override in &config.overrides {
+      validate_rules_config(&lint_override.rules, &plugin_codes, &config_file)?;
+    }
+
+    let include = config
+      .files
+      .include
+      .iter()
+      .map(|p| resolve_path(p).to_string_lossy().to_string())
+      .collect();
+    let exclude = config
+      .files
+      .exclude
+      .iter()
+      .map(|p| resolve_path(p).to_string_lossy().to_string())
+      .collect();
+    let overrides = config
+      .overrides
+      .iter()
+      .map(|lint_override| {
+        let paths = lint_override.files.iter().map(resolve_path).collect();
+        (paths, lint_override.rules.clone())
+      })
+      .collect();
+
+    Ok(LintSettings {
+      config,
+      include,
+      exclude,
+      overrides,
+      plugins,
+    })
+  }
+
+  /// Returns the rule selection for `file_path`, which is the base selection
+  /// with every matching override applied in order.
+  fn get_rules_config(&self, file_path: &Path) -> LintRulesConfig {
+    let mut rules_config = self.config.rules.clone();
+    for (paths, override_rules_config) in &self.overrides {
+      if paths.iter().any(|p| file_path.starts_with(p)) {
+        rules_config.merge(override_rules_config);
+      }
+    }
+    rules_config
+  }
+}
+
+fn validate_rules_config(
+  rules_config: &LintRulesConfig,
+  plugin_codes: &[String],
+  config_file: &ConfigFile,
+) -> Result<(), AnyError> {
+  let all_rules = rules::get_all_rules();
+  let is_known = |code: &String| {
+    all_rules.iter().any(|r| r.code() == code.as_str())
+      || plugin_codes.contains(code)
+  };
+  for code in rules_config.include.iter().chain(&rules_config.exclude) {
+    if !is_known(code) {
+      return Err(generic_error(format!(
+        "Unknown lint rule \"{}\" in {}",
+        code,
+        config_file.path.to_string_lossy()
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Returns the rules enabled by `rules_config`.
+fn get_configured_rules(
+  rules_config: &LintRulesConfig,
+) -> Vec<Box<dyn LintRule>> {
+  let tags = match &rules_config.tags {
+    Some(tags) => tags.clone(),
+    None => vec!["recommended".to_string()],
+  };
+  rules::get_all_rules()
+    .into_iter()
+    .filter(|rule| {
+      let code = rule.code().to_string();
+      if rules_config.exclude.contains(&code) {
+        false
+      } else {
+        rules_config.include.contains(&code)
+          || rule
+            .tags()
+            .iter()
+            .any(|tag| tags.iter().any(|t| t.as_str() == *tag))
+      }
+    })
+    .collect()
+}
+
+/// Returns the codes of the plugin rules enabled by `rules_config`. Plugin
+/// rules are not tagged, so they are enabled unless excluded.
+fn get_configured_plugin_rules(
+  rules_config: &LintRulesConfig,
+  plugins: &LintPlugins,
+) -> Vec<String> {
+  plugins
+    .rule_codes
+    .iter()
+    .filter(|code| !rules_config.exclude.contains(code))
+    .cloned()
+    .collect()
+}
+
+/// Lint the given files. Files that had no problems in a previous run and
+/// did not change since are skipped, using the cache of the project stored
+/// in `incremental_cache_dir`.
+///
+/// If no files are given, the files included by the configuration file are
+/// linted. With `fix`, the problems that can be fixed automatically are
+/// written back to the files and only the remaining ones are reported.
 pub async fn lint_files(
   args: Vec<String>,
   ignore: Vec<String>,
-  json: bool,
+  reporter_kind: LintReporterKind,
+  fix: bool,
+  maybe_config_path: Option<String>,
+  incremental_cache_dir: PathBuf,
 ) -> Result<(), AnyError> {
+  let root = incremental_cache::project_root(maybe_config_path.as_deref())?;
+  let settings = Arc::new(LintSettings::new(maybe_config_path)?);
   if args.len() == 1 && args[0] == "-" {
-    return lint_stdin(json);
+    if fix {
+      return Err(generic_error("--fix cannot be used when linting stdin"));
+    }
+    return lint_stdin(
+      reporter_kind,
+      &settings.config.rules,
+      settings.plugins.as_ref(),
+    );
   }
-  let mut target_files = collect_files(args)?;
+  let args = if args.is_empty() {
+    settings.include.clone()
+  } else {
+    args
+  };
+  let mut ignore = ignore;
+  ignore.extend(settings.exclude.iter().cloned());
+  let mut target_files = collect_files(args, is_supported_ext)?;
   if !ignore.is_empty() {
     // collect all files to be ignored
     // and retain only files that should be linted.
-    let ignore_files = collect_files(ignore)?;
+    let ignore_files = collect_files(ignore, is_supported_ext)?;
     target_files.retain(|f| !ignore_files.contains(&f));
   }
   debug!("Found {} files", target_files.len());
@@ -59,22 +288,57 @@ pub async fn lint_files(
 
   let has_error = Arc::new(AtomicBool::new(false));
 
-  let reporter_kind = if json {
-    LintReporterKind::Json
-  } else {
-    LintReporterKind::Pretty
-  };
   let reporter_lock = Arc::new(Mutex::new(create_reporter(reporter_kind)));
+  let config_state = serde_json::to_vec(&settings.config)?;
+  let overrides_state = format!("{:?}", settings.overrides);
+  let mut state: Vec<&[u8]> = vec![&config_state, overrides_state.as_bytes()];
+  if let Some(plugins) = &settings.plugins {
+    state.extend(plugins.sources());
+  }
+  let incremental_cache =
+    Arc::new(IncrementalCache::new(&incremental_cache_dir, &root, &state));
 
   run_parallelized(target_files, {
     let reporter_lock = reporter_lock.clone();
     let has_error = has_error.clone();
+    let incremental_cache = incremental_cache.clone();
     move |file_path| {
-      let r = lint_file(file_path.clone());
+      let rules_config = settings.get_rules_config(&file_path);
+      let r = fs::read_to_string(&file_path)
+        .map_err(AnyError::from)
+        .and_then(|source| {
+          if incremental_cache.is_file_same(&file_path, &source) {
+            return Ok(None);
+          }
+          if fix {
+            let r = lint_and_fix_file(
+              &file_path,
+              source,
+              &rules_config,
+              settings.plugins.as_ref(),
+            )?;
+            return Ok(Some(r));
+          }
+          let file_diagnostics = lint_file(
+            &file_path,
+            source.clone(),
+            &rules_config,
+            settings.plugins.as_ref(),
+          )?
+          .into_iter()
+          .map(|p| p.diagnostic)
+          .collect();
+          Ok(Some((file_diagnostics, source)))
+        });
       let mut reporter = reporter_lock.lock().unwrap();
+      reporter.visit_file(&file_path.to_string_lossy());
 
       match r {
-        Ok((mut file_diagnostics, source)) => {
+        Ok(None) => {}
+        Ok(Some((mut file_diagnostics, source))) => {
+          if file_diagnostics.is_empty() {
+            incremental_cache.update_file(&file_path, &source);
+          }
           sort_diagnostics(&mut file_diagnostics);
           for d in file_diagnostics.iter() {
             has_error.store(true, Ordering::Relaxed);
@@ -91,6 +355,10 @@ pub async fn lint_files(
   })
   .await?;
 
+  if let Err(err) = incremental_cache.save() {
+    debug!("Failed to save lint cache: {}", err);
+  }
+
   let has_error = has_error.load(Ordering::Relaxed);
 
   reporter_lock.lock().unwrap().close(target_files_len);
@@ -102,8 +370,62 @@ pub async fn lint_files(
   Ok(())
 }
 
-pub fn print_rules_list() {
-  let lint_rules = rules::get_recommended_rules();
+/// Maximum number of lint and fix passes over a single file. Fixes are
+/// re-applied until no edits are left, as overlapping edits are deferred to
+/// the next pass.
+const MAX_FIX_PASSES: usize = 10;
+
+/// Lint `file_path`, apply the fixes suggested by the rules and write the
+/// result back to disk. Every applied fix is printed. Returns the remaining
+/// diagnostics and the updated source text.
+fn lint_and_fix_file(
+  file_path: &Path,
+  source: String,
+  rules_config: &LintRulesConfig,
+  maybe_plugins: Option<&LintPlugins>,
+) -> Result<(Vec<LintDiagnostic>, String), AnyError> {
+  let mut source = source;
+  let mut is_fixed = false;
+  let mut problems =
+    lint_file(file_path, source.clone(), rules_config, maybe_plugins)?;
+  for _ in 0..MAX_FIX_PASSES {
+    let (new_source, applied) = lint_fix::apply_fixes(&source, &problems);
+    if applied.is_empty() {
+      break;
+    }
+    // The fixes should still be printed even if `--quiet` option is enabled,
+    // so use `eprintln!` here instead of `info!`.
+    for problem in applied {
+      let d = &problem.diagnostic;
+      eprintln!(
+        "{} ({}) {} at {}:{}:{}",
+        colors::green("Fixed"),
+        colors::gray(&d.code),
+        problem.fix.as_ref().unwrap().description,
+        d.filename,
+        d.range.start.line,
+        d.range.start.col + 1
+      );
+    }
+    is_fixed = true;
+    source = new_source;
+    problems =
+      lint_file(file_path, source.clone(), rules_config, maybe_plugins)?;
+  }
+  if is_fixed {
+    fs::write(file_path, &source)?;
+  }
+  let file_diagnostics = problems.into_iter().map(|p| p.diagnostic).collect();
+  Ok((file_diagnostics, source))
+}
+
+/// Print the rules enabled by the configuration, followed by the rules
+/// enabled for each override.
+pub fn print_rules_list(
+  maybe_config_path: Option<String>,
+) -> Result<(), AnyError> {
+  let settings = LintSettings::new(maybe_config_path)?;
+  let lint_rules = get_configured_rules(&settings.config.rules);
 
   // The rules should still be printed even if `--quiet` option is enabled,
   // so use `println!` here instead of `info!`.
@@ -111,6 +433,29 @@ pub fn print_rules_list() {
   for rule in lint_rules {
     println!(" - {}", rule.code());
   }
+  if let Some(plugins) = &settings.plugins {
+    for code in get_configured_plugin_rules(&settings.config.rules, plugins) {
+      println!(" - {}", code);
+    }
+  }
+  for (paths, override_rules_config) in &settings.overrides {
+    let mut rules_config = settings.config.rules.clone();
+    rules_config.merge(override_rules_config);
+    let paths = paths
+      .iter()
+      .map(|p| p.to_string_lossy().to_string())
+      .collect::<Vec<_>>();
+    println!("Rules for {}:", paths.join(", "));
+    for rule in get_configured_rules(&rules_config) {
+      println!(" - {}", rule.code());
+    }
+    if let Some(plugins) = &settings.plugins {
+      for code in get_configured_plugin_rules(&rules_config, plugins) {
+        println!(" - {}", code);
+      }
+    }
+  }
+  Ok(())
 }
 
 fn create_linter(syntax: Syntax, rules: Vec<Box<dyn LintRule>>) -> Linter {
@@ -129,49 +474,77 @@ fn create_linter(syntax: Syntax, rules: Vec<Box<dyn LintRule>>) -> Linter {
 }
 
 fn lint_file(
-  file_path: PathBuf,
-) -> Result<(Vec<LintDiagnostic>, String), AnyError> {
+  file_path: &Path,
+  source_code: String,
+  rules_config: &LintRulesConfig,
+  maybe_plugins: Option<&LintPlugins>,
+) -> Result<Vec<LintProblem>, AnyError> {
   let file_name = file_path.to_string_lossy().to_string();
-  let source_code = fs::read_to_string(&file_path)?;
-  let media_type = MediaType::from(&file_path);
+  let media_type = MediaType::from(file_path);
   let syntax = ast::get_syntax(&media_type);
 
-  let lint_rules = rules::get_recommended_rules();
+  let lint_rules = get_configured_rules(rules_config);
   let mut linter = create_linter(syntax, lint_rules);
 
-  let file_diagnostics = linter.lint(file_name, source_code.clone())?;
+  let mut file_diagnostics = linter.lint(file_name, source_code.clone())?;
+  let mut plugin_problems = Vec::new();
+
+  if let Some(plugins) = maybe_plugins {
+    let codes = get_configured_plugin_rules(rules_config, plugins);
+    plugin_problems = plugins.lint_file(file_path, &source_code, &codes)?;
+    // The built-in linter doesn't know the plugin rules, so it considers
+    // ignore directives for them unused.
+    file_diagnostics.retain(|d| match get_unused_ignore_code(d) {
+      Some(code) => !plugins.rule_codes.iter().any(|c| c == code),
+      None => true,
+    });
+  }
 
-  Ok((file_diagnostics, source_code))
+  let mut problems =
+    lint_fix::attach_fixes(file_path, &source_code, file_diagnostics);
+  problems.extend(plugin_problems);
+  Ok(problems)
+}
+
+/// Returns the rule code of an ignore directive reported by
+/// `ban-unused-ignore`, which is only part of the message.
+fn get_unused_ignore_code(d: &LintDiagnostic) -> Option<&str> {
+  if d.code != "ban-unused-ignore" {
+    return None;
+  }
+  d.message
+    .strip_prefix("Ignore for code \"")?
+    .strip_suffix("\" was not used.")
 }
 
 /// Lint stdin and write result to stdout.
 /// Treats input as TypeScript.
 /// Compatible with `--json` flag.
-fn lint_stdin(json: bool) -> Result<(), AnyError> {
+fn lint_stdin(
+  reporter_kind: LintReporterKind,
+  rules_config: &LintRulesConfig,
+  maybe_plugins: Option<&LintPlugins>,
+) -> Result<(), AnyError> {
   let mut source = String::new();
   if stdin().read_to_string(&mut source).is_err() {
     return Err(generic_error("Failed to read from stdin"));
   }
 
-  let reporter_kind = if json {
-    LintReporterKind::Json
-  } else {
-    LintReporterKind::Pretty
-  };
   let mut reporter = create_reporter(reporter_kind);
-  let lint_rules = rules::get_recommended_rules();
-  let syntax = ast::get_syntax(&MediaType::TypeScript);
-  let mut linter = create_linter(syntax, lint_rules);
   let mut has_error = false;
   let pseudo_file_name = "_stdin.ts";
-  match linter
-    .lint(pseudo_file_name.to_string(), source.clone())
-    .map_err(|e| e.into())
-  {
-    Ok(diagnostics) => {
-      for d in diagnostics {
+  reporter.visit_file(pseudo_file_name);
+  match lint_file(
+    Path::new(pseudo_file_name),
+    source.clone(),
+    rules_config,
+    maybe_plugins,
+  ) {
+    Ok(problems) => {
+      for problem in problems {
         has_error = true;
-        reporter.visit_diagnostic(&d, source.split('\n').collect());
+        reporter
+          .visit_diagnostic(&problem.diagnostic, source.split('\n').collect());
       }
     }
     Err(err) => {
@@ -190,6 +563,8 @@ fn lint_stdin(json: bool) -> Result<(), AnyError> {
 }
 
 trait LintReporter {
+  /// Called for every file that is linted, before its diagnostics.
+  fn visit_file(&mut self, _file_path: &str) {}
   fn visit_diagnostic(&mut self, d: &LintDiagnostic, source_lines: Vec<&str>);
   fn visit_error(&mut self, file_path: &str, err: &AnyError);
   fn close(&mut self, check_count: usize);
@@ -292,40 +667,297 @@ pub fn format_diagnostic(
   )
 }
 
-#[derive(Serialize)]
-struct JsonLintReporter {
+/// The results of all the linted files.
+#[derive(Default)]
+struct LintResults {
+  files: Vec<String>,
   diagnostics: Vec<LintDiagnostic>,
   errors: Vec<LintError>,
 }
 
-impl JsonLintReporter {
-  fn new() -> JsonLintReporter {
-    JsonLintReporter {
-      diagnostics: Vec::new(),
-      errors: Vec::new(),
+/// Reporter collecting the results of all files, so that they can be printed
+/// as a single document by `print` once linting is done.
+struct CollectingLintReporter {
+  results: LintResults,
+  print: fn(&LintResults),
+}
+
+impl CollectingLintReporter {
+  fn new(print: fn(&LintResults)) -> CollectingLintReporter {
+    CollectingLintReporter {
+      results: LintResults::default(),
+      print,
     }
   }
 }
 
-impl LintReporter for JsonLintReporter {
+impl LintReporter for CollectingLintReporter {
+  fn visit_file(&mut self, file_path: &str) {
+    self.results.files.push(file_path.to_string());
+  }
+
   fn visit_diagnostic(&mut self, d: &LintDiagnostic, _source_lines: Vec<&str>) {
-    self.diagnostics.push(d.clone());
+    self.results.diagnostics.push(d.clone());
   }
 
   fn visit_error(&mut self, file_path: &str, err: &AnyError) {
-    self.errors.push(LintError {
+    self.results.errors.push(LintError {
       file_path: file_path.to_string(),
       message: err.to_string(),
     });
   }
 
   fn close(&mut self, _check_count: usize) {
-    sort_diagnostics(&mut self.diagnostics);
-    let json = serde_json::to_string_pretty(&self);
-    eprintln!("{}", json.unwrap());
+    sort_diagnostics(&mut self.results.diagnostics);
+    self
+      .results
+      .errors
+      .sort_by(|a, b| a.file_path.cmp(&b.file_path));
+    self.results.files.sort();
+    self.results.files.dedup();
+    (self.print)(&self.results);
+  }
+}
+
+fn print_json(results: &LintResults) {
+  let json = json!({
+    "diagnostics": results.diagnostics,
+    "errors": results.errors,
+  });
+  eprintln!("{}", serde_json::to_string_pretty(&json).unwrap());
+}
+
+/// Returns `file_path` relative to the current directory if it is located
+/// inside of it, using `/` as separator.
+fn relative_path(file_path: &str) -> String {
+  let path = Path::new(file_path);
+  let relative = std::env::current_dir()
+    .ok()
+    .and_then(|cwd| cwd.canonicalize().ok())
+    .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf));
+  match relative {
+    Some(relative) => relative.to_string_lossy().replace('\\', "/"),
+    None => path.to_string_lossy().replace('\\', "/"),
   }
 }
 
+/// Group sorted diagnostics by file name.
+fn group_by_file(
+  diagnostics: &[LintDiagnostic],
+) -> Vec<(&str, Vec<&LintDiagnostic>)> {
+  let mut groups: Vec<(&str, Vec<&LintDiagnostic>)> = Vec::new();
+  for d in diagnostics {
+    match groups.last_mut() {
+      Some((filename, group)) if *filename == d.filename => group.push(d),
+      _ => groups.push((d.filename.as_str(), vec![d])),
+    }
+  }
+  groups
+}
+
+/// Print the results in the Static Analysis Results Interchange Format
+/// (SARIF) 2.1.0, as consumed by code scanning services.
+fn print_sarif(results: &LintResults) {
+  let rule_ids = results
+    .diagnostics
+    .iter()
+    .map(|d| d.code.as_str())
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .collect::<Vec<_>>();
+  let rules = rule_ids
+    .iter()
+    .map(|id| json!({ "id": id }))
+    .collect::<Vec<_>>();
+  let sarif_results = results
+    .diagnostics
+    .iter()
+    .map(|d| {
+      json!({
+        "ruleId": d.code,
+        "ruleIndex": rule_ids.iter().position(|id| *id == d.code).unwrap(),
+        "level": "error",
+        "message": { "text": d.message },
+        "locations": [{
+          "physicalLocation": {
+            "artifactLocation": { "uri": relative_path(&d.filename) },
+            "region": {
+              "startLine": d.range.start.line,
+              "startColumn": d.range.start.col + 1,
+              "endLine": d.range.end.line,
+              "endColumn": d.range.end.col + 1,
+            },
+          },
+        }],
+      })
+    })
+    .collect::<Vec<_>>();
+  let notifications = results
+    .errors
+    .iter()
+    .map(|err| {
+      json!({
+        "level": "error",
+        "message": { "text": err.message },
+        "locations": [{
+          "physicalLocation": {
+            "artifactLocation": { "uri": relative_path(&err.file_path) },
+          },
+        }],
+      })
+    })
+    .collect::<Vec<_>>();
+
+  let sarif = json!({
+    "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+    "version": "2.1.0",
+    "runs": [{
+      "tool": {
+        "driver": {
+          "name": "deno lint",
+          "version": crate::version::DENO,
+          "informationUri": "https://deno.land/manual/tools/linter",
+          "rules": rules,
+        },
+      },
+      // Columns reported by the linter are counted in characters.
+      "columnKind": "unicodeCodePoints",
+      "results": sarif_results,
+      "invocations": [{
+        "executionSuccessful": results.errors.is_empty(),
+        "toolExecutionNotifications": notifications,
+      }],
+    }],
+  });
+  println!("{}", serde_json::to_string_pretty(&sarif).unwrap());
+}
+
+/// Print the results in the checkstyle XML format.
+fn print_checkstyle(results: &LintResults) {
+  let mut xml = String::new();
+  xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  xml.push_str("<checkstyle version=\"4.3\">\n");
+  for (filename, diagnostics) in group_by_file(&results.diagnostics) {
+    xml.push_str(&format!(
+      "  <file name=\"{}\">\n",
+      xml_escape(&relative_path(filename))
+    ));
+    for d in diagnostics {
+      xml.push_str(&format!(
+        "    <error line=\"{}\" column=\"{}\" severity=\"error\" message=\"{}\" source=\"deno-lint.{}\" />\n",
+        d.range.start.line,
+        d.range.start.col + 1,
+        xml_escape(&d.message),
+        xml_escape(&d.code)
+      ));
+    }
+    xml.push_str("  </file>\n");
+  }
+  for err in &results.errors {
+    xml.push_str(&format!(
+      "  <file name=\"{}\">\n",
+      xml_escape(&relative_path(&err.file_path))
+    ));
+    xml.push_str(&format!(
+      "    <error severity=\"error\" message=\"{}\" source=\"deno-lint\" />\n",
+      xml_escape(&err.message)
+    ));
+    xml.push_str("  </file>\n");
+  }
+  xml.push_str("</checkstyle>");
+  println!("{}", xml);
+}
+
+/// Print the results in the JUnit XML format, with a test suite for every
+/// linted file, and a failed test case for every problem or a passed test
+/// case if the file has none.
+fn print_junit(results: &LintResults) {
+  let mut filenames: Vec<&str> =
+    results.files.iter().map(String::as_str).collect();
+  filenames.extend(results.diagnostics.iter().map(|d| d.filename.as_str()));
+  filenames.extend(results.errors.iter().map(|e| e.file_path.as_str()));
+  filenames.sort_unstable();
+  filenames.dedup();
+  let groups = group_by_file(&results.diagnostics);
+  let passed = filenames
+    .iter()
+    .filter(|f| {
+      !groups.iter().any(|(filename, _)| filename == *f)
+        && !results.errors.iter().any(|e| e.file_path == **f)
+    })
+    .count();
+
+  let mut xml = String::new();
+  xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  xml.push_str(&format!(
+    "<testsuites name=\"deno lint\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+    results.diagnostics.len() + results.errors.len() + passed,
+    results.diagnostics.len(),
+    results.errors.len()
+  ));
+  for filename in filenames {
+    let diagnostics = groups
+      .iter()
+      .find(|(f, _)| *f == filename)
+      .map(|(_, diagnostics)| diagnostics.as_slice())
+      .unwrap_or(&[]);
+    let errors: Vec<&LintError> = results
+      .errors
+      .iter()
+      .filter(|e| e.file_path == filename)
+      .collect();
+    let filename = xml_escape(&relative_path(filename));
+    let tests = (diagnostics.len() + errors.len()).max(1);
+    xml.push_str(&format!(
+      "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+      filename,
+      tests,
+      diagnostics.len(),
+      errors.len()
+    ));
+    for d in diagnostics {
+      let location = format!(
+        "{}:{}:{}",
+        filename,
+        d.range.start.line,
+        d.range.start.col + 1
+      );
+      xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\">\n",
+        xml_escape(&format!("{} at {}", d.code, location)),
+        filename
+      ));
+      xml.push_str(&format!(
+        "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+        xml_escape(&d.message),
+        xml_escape(&d.code),
+        location
+      ));
+      xml.push_str("    </testcase>\n");
+    }
+    for err in &errors {
+      xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\">\n",
+        filename, filename
+      ));
+      xml.push_str(&format!(
+        "      <error message=\"{}\" />\n",
+        xml_escape(&err.message)
+      ));
+      xml.push_str("    </testcase>\n");
+    }
+    if diagnostics.is_empty() && errors.is_empty() {
+      xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\" />\n",
+        filename, filename
+      ));
+    }
+    xml.push_str("  </testsuite>\n");
+  }
+  xml.push_str("</testsuites>");
+  println!("{}", xml);
+}
+
 fn sort_diagnostics(diagnostics: &mut Vec<LintDiagnostic>) {
   // Sort so that we guarantee a deterministic output which is useful for tests
   diagnostics.sort_by(|a, b| {
@@ -343,3 +975,33 @@ fn sort_diagnostics(diagnostics: &mut Vec<LintDiagnostic>) {
     }
   });
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use deno_lint::diagnostic::Position;
+  use deno_lint::diagnostic::Range;
+
+  fn diagnostic(code: &str, message: &str) -> LintDiagnostic {
+    LintDiagnostic {
+      range: Range {
+        start: Position { line: 1, col: 0 },
+        end: Position { line: 1, col: 1 },
+      },
+      filename: "mod.ts".to_string(),
+      message: message.to_string(),
+      code: code.to_string(),
+    }
+  }
+
+  #[test]
+  fn test_get_unused_ignore_code() {
+    let d = diagnostic(
+      "ban-unused-ignore",
+      "Ignore for code \"no-lodash-import\" was not used.",
+    );
+    assert_eq!(get_unused_ignore_code(&d), Some("no-lodash-import"));
+    let d = diagnostic("no-empty", "Ignore for code \"a\" was not used.");
+    assert_eq!(get_unused_ignore_code(&d), None);
+  }
+}
@@ -0,0 +1,409 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Automatic fixes for the problems reported by `deno lint`.
+//!
+//! Every problem carries the fix suggested by the rule that reported it, if
+//! any. Plugin rules provide their fixes together with their diagnostics.
+//! The built-in rules live in `deno_lint`, so their fixes are provided by the
+//! `RuleFix` implementations below, one per rule for which the correct edit
+//! is unambiguous.
+
+use crate::ast;
+use crate::media_type::MediaType;
+use deno_core::ModuleSpecifier;
+use deno_lint::diagnostic::LintDiagnostic;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+/// Replacement of a byte range of the source text.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextEdit {
+  pub range: Range<usize>,
+  pub new_text: String,
+}
+
+/// A change suggested by a rule to fix one of its problems.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LintFix {
+  pub description: String,
+  pub edits: Vec<TextEdit>,
+}
+
+/// A diagnostic together with the fix suggested by the rule reporting it.
+#[derive(Debug, Clone)]
+pub struct LintProblem {
+  pub diagnostic: LintDiagnostic,
+  pub fix: Option<LintFix>,
+}
+
+/// Provides the fixes of the problems reported by one of the built-in rules.
+trait RuleFix: Sync {
+  /// The code of the rule.
+  fn code(&self) -> &'static str;
+
+  /// Returns the fix of each of the problems of the rule, reported at the
+  /// byte `ranges` of `source`.
+  fn fix(
+    &self,
+    file_path: &Path,
+    source: &str,
+    ranges: &[Range<usize>],
+  ) -> Vec<Option<LintFix>>;
+}
+
+struct NoExtraSemiFix;
+
+impl RuleFix for NoExtraSemiFix {
+  fn code(&self) -> &'static str {
+    "no-extra-semi"
+  }
+
+  fn fix(
+    &self,
+    _file_path: &Path,
+    source: &str,
+    ranges: &[Range<usize>],
+  ) -> Vec<Option<LintFix>> {
+    ranges
+      .iter()
+      .map(|range| {
+        if &source[range.clone()] != ";" {
+          return None;
+        }
+        Some(LintFix {
+          description: "Remove the extra semicolon".to_string(),
+          edits: vec![TextEdit {
+            range: range.clone(),
+            new_text: String::new(),
+          }],
+        })
+      })
+      .collect()
+  }
+}
+
+struct BanUntaggedIgnoreFix;
+
+impl RuleFix for BanUntaggedIgnoreFix {
+  fn code(&self) -> &'static str {
+    "ban-untagged-ignore"
+  }
+
+  fn fix(
+    &self,
+    _file_path: &Path,
+    source: &str,
+    ranges: &[Range<usize>],
+  ) -> Vec<Option<LintFix>> {
+    ranges
+      .iter()
+      .map(|range| {
+        Some(LintFix {
+          description: "Remove the ignore directive".to_string(),
+          edits: vec![TextEdit {
+            range: extend_to_blank_line(source, range.clone()),
+            new_text: String::new(),
+          }],
+        })
+      })
+      .collect()
+  }
+}
+
+struct PreferConstFix;
+
+impl RuleFix for PreferConstFix {
+  fn code(&self) -> &'static str {
+    "prefer-const"
+  }
+
+  /// A `let` declaration is only changed if all of its bindings are
+  /// reported, otherwise the declaration would have to be split.
+  fn fix(
+    &self,
+    file_path: &Path,
+    source: &str,
+    ranges: &[Range<usize>],
+  ) -> Vec<Option<LintFix>> {
+    let declarations = match parse_let_declarations(file_path, source) {
+      Some(declarations) => declarations,
+      None => return vec![None; ranges.len()],
+    };
+    let offsets: HashSet<usize> = ranges.iter().map(|r| r.start).collect();
+    ranges
+      .iter()
+      .map(|range| {
+        let decl = declarations
+          .iter()
+          .find(|decl| decl.binding_offsets.contains(&range.start))?;
+        if !decl.binding_offsets.iter().all(|o| offsets.contains(o)) {
+          return None;
+        }
+        Some(LintFix {
+          description: "Replace `let` with `const`".to_string(),
+          edits: vec![TextEdit {
+            range: decl.keyword_offset..decl.keyword_offset + "let".len(),
+            new_text: "const".to_string(),
+          }],
+        })
+      })
+      .collect()
+  }
+}
+
+fn parse_let_declarations(
+  file_path: &Path,
+  source: &str,
+) -> Option<Vec<ast::LetDeclaration>> {
+  let specifier =
+    ModuleSpecifier::resolve_url_or_path(&file_path.to_string_lossy()).ok()?;
+  let media_type = MediaType::from(file_path);
+  let parsed_module = ast::parse(&specifier, source, &media_type).ok()?;
+  Some(parsed_module.get_let_declarations())
+}
+
+static RULE_FIXES: &[&dyn RuleFix] =
+  &[&NoExtraSemiFix, &BanUntaggedIgnoreFix, &PreferConstFix];
+
+/// Attach the fixes of the built-in rules to their `diagnostics`.
+pub fn attach_fixes(
+  file_path: &Path,
+  source: &str,
+  diagnostics: Vec<LintDiagnostic>,
+) -> Vec<LintProblem> {
+  let mut problems: Vec<LintProblem> = diagnostics
+    .into_iter()
+    .map(|diagnostic| LintProblem {
+      diagnostic,
+      fix: None,
+    })
+    .collect();
+  for rule_fix in RULE_FIXES {
+    let mut indexes = Vec::new();
+    let mut ranges = Vec::new();
+    for (i, problem) in problems.iter().enumerate() {
+      if problem.diagnostic.code != rule_fix.code() {
+        continue;
+      }
+      if let Some(range) = get_byte_range(source, &problem.diagnostic) {
+        indexes.push(i);
+        ranges.push(range);
+      }
+    }
+    if ranges.is_empty() {
+      continue;
+    }
+    let fixes = rule_fix.fix(file_path, source, &ranges);
+    for (i, fix) in indexes.into_iter().zip(fixes) {
+      problems[i].fix = fix;
+    }
+  }
+  problems
+}
+
+/// Apply the fixes of `problems` to `source`. A fix is skipped if one of its
+/// edits overlaps an edit of a fix that was applied before; it can be applied
+/// in a subsequent pass. Returns the new text and the problems whose fix was
+/// applied.
+pub fn apply_fixes<'a>(
+  source: &str,
+  problems: &'a [LintProblem],
+) -> (String, Vec<&'a LintProblem>) {
+  let mut fixable: Vec<(&LintProblem, &LintFix)> = problems
+    .iter()
+    .filter_map(|p| p.fix.as_ref().map(|fix| (p, fix)))
+    .filter(|(_, fix)| !fix.edits.is_empty())
+    .collect();
+  fixable.sort_by_key(|(_, fix)| {
+    fix.edits.iter().map(|e| e.range.start).min().unwrap()
+  });
+
+  let mut edits: Vec<&TextEdit> = Vec::new();
+  let mut applied = Vec::new();
+  for (problem, fix) in fixable {
+    let overlaps = fix
+      .edits
+      .iter()
+      .any(|edit| edits.iter().any(|other| overlaps(edit, other)));
+    if !overlaps {
+      edits.extend(fix.edits.iter());
+      applied.push(problem);
+    }
+  }
+
+  edits.sort_by_key(|edit| edit.range.start);
+  let mut text = String::with_capacity(source.len());
+  let mut last_end = 0;
+  for edit in edits {
+    text.push_str(&source[last_end..edit.range.start]);
+    text.push_str(&edit.new_text);
+    last_end = edit.range.end;
+  }
+  text.push_str(&source[last_end..]);
+  (text, applied)
+}
+
+/// Edits starting at the same offset are considered overlapping, as the
+/// order of their insertions would be ambiguous.
+fn overlaps(a: &TextEdit, b: &TextEdit) -> bool {
+  a.range.start == b.range.start
+    || (a.range.start < b.range.end && b.range.start < a.range.end)
+}
+
+fn get_byte_range(source: &str, d: &LintDiagnostic) -> Option<Range<usize>> {
+  let start = get_byte_offset(source, d.range.start.line, d.range.start.col)?;
+  let end = get_byte_offset(source, d.range.end.line, d.range.end.col)?;
+  if start <= end {
+    Some(start..end)
+  } else {
+    None
+  }
+}
+
+/// Convert a 1-based line and a 0-based column, counted in characters, to a
+/// byte offset in `source`.
+fn get_byte_offset(source: &str, line: usize, col: usize) -> Option<usize> {
+  let mut line_start = 0;
+  for _ in 1..line {
+    line_start += source[line_start..].find('\n')? + 1;
+  }
+  let line_text = &source[line_start..];
+  let line_text = match line_text.find('\n') {
+    Some(end) => &line_text[..end],
+    None => line_text,
+  };
+  if col == line_text.chars().count() {
+    return Some(line_start + line_text.len());
+  }
+  line_text
+    .char_indices()
+    .nth(col)
+    .map(|(offset, _)| line_start + offset)
+}
+
+/// If `range` is the only non-whitespace text on its line, extend it to the
+/// whole line so that no blank line is left behind.
+fn extend_to_blank_line(source: &str, range: Range<usize>) -> Range<usize> {
+  let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+  let line_end = source[range.end..]
+    .find('\n')
+    .map_or(source.len(), |i| range.end + i + 1);
+  let before = &source[line_start..range.start];
+  let after = &source[range.end..line_end];
+  if before.trim().is_empty() && after.trim().is_empty() {
+    line_start..line_end
+  } else {
+    range
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_get_byte_offset() {
+    let source = "let a = 1;\nconst é = \"b\";\n";
+    assert_eq!(get_byte_offset(source, 1, 0), Some(0));
+    assert_eq!(get_byte_offset(source, 2, 6), Some(17));
+    assert_eq!(get_byte_offset(source, 2, 8), Some(20));
+    assert_eq!(get_byte_offset(source, 2, 14), Some(26));
+    assert_eq!(get_byte_offset(source, 2, 15), None);
+    assert_eq!(get_byte_offset(source, 4, 0), None);
+  }
+
+  fn problem(
+    code: &str,
+    start: (usize, usize),
+    end: (usize, usize),
+  ) -> LintDiagnostic {
+    LintDiagnostic {
+      range: deno_lint::diagnostic::Range {
+        start: deno_lint::diagnostic::Position {
+          line: start.0,
+          col: start.1,
+        },
+        end: deno_lint::diagnostic::Position {
+          line: end.0,
+          col: end.1,
+        },
+      },
+      filename: "mod.ts".to_string(),
+      message: "message".to_string(),
+      code: code.to_string(),
+    }
+  }
+
+  fn fixed(edits: Vec<(Range<usize>, &str)>) -> LintProblem {
+    LintProblem {
+      diagnostic: problem("rule", (1, 0), (1, 0)),
+      fix: Some(LintFix {
+        description: "description".to_string(),
+        edits: edits
+          .into_iter()
+          .map(|(range, new_text)| TextEdit {
+            range,
+            new_text: new_text.to_string(),
+          })
+          .collect(),
+      }),
+    }
+  }
+
+  #[test]
+  fn test_attach_fixes() {
+    let source = "let a = 1;;\nlet b = 2, c = 3;\nlet d = 4;\n";
+    let diagnostics = vec![
+      problem("no-extra-semi", (1, 10), (1, 11)),
+      problem("prefer-const", (2, 4), (2, 5)),
+      problem("prefer-const", (3, 4), (3, 5)),
+      problem("no-unused-vars", (3, 4), (3, 5)),
+    ];
+    let problems = attach_fixes(Path::new("mod.ts"), source, diagnostics);
+    let edits: Vec<Option<Vec<TextEdit>>> = problems
+      .into_iter()
+      .map(|p| p.fix.map(|fix| fix.edits))
+      .collect();
+    assert_eq!(
+      edits,
+      vec![
+        Some(vec![TextEdit {
+          range: 10..11,
+          new_text: "".to_string(),
+        }]),
+        // `c` is not reported, so the declaration is left alone.
+        None,
+        Some(vec![TextEdit {
+          range: 30..33,
+          new_text: "const".to_string(),
+        }]),
+        None,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_apply_fixes() {
+    let source = "let a = 1;;\nlet b = 2;\n";
+    let problems = vec![
+      fixed(vec![(12..15, "const")]),
+      fixed(vec![(10..11, "")]),
+      fixed(vec![(0..3, "const")]),
+      // Overlaps the previous fix, so it is skipped.
+      fixed(vec![(0..5, "var b")]),
+      // One of the edits overlaps, so none of them is applied.
+      fixed(vec![(20..21, ""), (1..2, "x")]),
+    ];
+    let (text, applied) = apply_fixes(source, &problems);
+    assert_eq!(text, "const a = 1;\nconst b = 2;\n");
+    assert_eq!(applied.len(), 3);
+  }
+
+  #[test]
+  fn test_extend_to_blank_line() {
+    let source = "a;\n  // deno-lint-ignore\nb; // deno-lint-ignore\n";
+    assert_eq!(extend_to_blank_line(source, 5..24), 3..25);
+    assert_eq!(extend_to_blank_line(source, 28..47), 28..47);
+  }
+}
@@ -0,0 +1,124 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+// Runtime of lint plugins. It is executed in a bare isolate without any of
+// the `Deno` namespace ops: plugins only get to see the AST and source of the
+// linted file and can't access the file system or the network.
+
+((window) => {
+  const core = window.Deno.core;
+  delete window.Deno;
+
+  // Plugins are assigned by the generated entry module.
+  window.__lintPlugins = [];
+
+  function respond(data) {
+    core.jsonOpSync("op_lint_respond", data);
+  }
+
+  function getRules() {
+    const rules = new Map();
+    for (const { specifier, plugin } of window.__lintPlugins) {
+      if (plugin === null || typeof plugin !== "object") {
+        throw new TypeError(
+          `Lint plugin must default export an object: ${specifier}`,
+        );
+      }
+      for (const [code, rule] of Object.entries(plugin.rules ?? {})) {
+        if (typeof rule?.create !== "function") {
+          throw new TypeError(
+            `Lint rule "${code}" must have a "create" function: ${specifier}`,
+          );
+        }
+        if (rules.has(code)) {
+          throw new TypeError(
+            `Lint rule "${code}" is defined more than once: ${specifier}`,
+          );
+        }
+        rules.set(code, rule);
+      }
+    }
+    return rules;
+  }
+
+  function lintPluginsGetRules() {
+    core.ops();
+    respond({ rules: [...getRules().keys()] });
+  }
+
+  /** Call the handlers for `node` and all of its descendants, in source
+   * order. Handlers are keyed by the node type, as serialized by swc. */
+  function visit(node, visitors) {
+    if (Array.isArray(node)) {
+      for (const child of node) {
+        visit(child, visitors);
+      }
+      return;
+    }
+    if (node === null || typeof node !== "object") {
+      return;
+    }
+    if (typeof node.type === "string") {
+      for (const visitor of visitors) {
+        visitor[node.type]?.(node);
+      }
+    }
+    for (const [key, value] of Object.entries(node)) {
+      if (key !== "span" && value !== null && typeof value === "object") {
+        visit(value, visitors);
+      }
+    }
+  }
+
+  function toFix(code, { description, edits }) {
+    if (typeof description !== "string" || !Array.isArray(edits)) {
+      throw new TypeError(
+        `Lint rule "${code}" must suggest a fix with a description and edits`,
+      );
+    }
+    return {
+      description,
+      edits: edits.map(({ span, text }) => {
+        if (span === undefined || typeof text !== "string") {
+          throw new TypeError(
+            `Lint rule "${code}" must suggest edits with a span and a text`,
+          );
+        }
+        return { start: span.start, end: span.end, text };
+      }),
+    };
+  }
+
+  function lintPluginsLintFile({ filename, source, ast, codes }) {
+    core.ops();
+    const rules = getRules();
+    const diagnostics = [];
+    const visitors = [];
+    for (const code of codes) {
+      const context = {
+        filename,
+        source,
+        report({ node, span, message, fix }) {
+          span = span ?? node?.span;
+          if (span === undefined || typeof message !== "string") {
+            throw new TypeError(
+              `Lint rule "${code}" must report a node or span and a message`,
+            );
+          }
+          diagnostics.push({
+            code,
+            message,
+            start: span.start,
+            end: span.end,
+            fix: fix === undefined ? null : toFix(code, fix),
+          });
+        },
+      };
+      visitors.push(rules.get(code).create(context));
+    }
+    visit(ast, visitors);
+    respond({ diagnostics });
+  }
+
+  window.lintPluginsGetRules = lintPluginsGetRules;
+  window.lintPluginsLintFile = lintPluginsLintFile;
+})(this);
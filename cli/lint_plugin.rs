@@ -0,0 +1,461 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Lint rules defined in JavaScript or TypeScript modules, called plugins.
+//!
+//! A plugin default exports an object with a `rules` map. Each rule has a
+//! `create(context)` function returning handlers keyed by swc AST node type:
+//!
+//! ```ts
+//! export default {
+//!   rules: {
+//!     "no-lodash": {
+//!       create(context) {
+//!         return {
+//!           ImportDeclaration(node) {
+//!             if (node.source.value === "lodash") {
+//!               context.report({ node, message: "Don't use lodash" });
+//!             }
+//!           },
+//!         };
+//!       },
+//!     },
+//!   },
+//! };
+//! ```
+//!
+//! A report may also suggest a fix, applied by `deno lint --fix`:
+//! `fix: { description, edits: [{ span, text }] }` replaces the source text
+//! of each span with `text`.
+//!
+//! Plugins run in a `JsRuntime` whose only op is used to hand results back
+//! to the linter, and may not import other modules. Every thread linting
+//! files evaluates the plugins once in its own runtime, which is reused for
+//! the following files. The linted module is passed as the swc AST produced
+//! by `ast::parse`, serialized to JSON.
+
+use crate::ast;
+use crate::lint_fix::LintFix;
+use crate::lint_fix::LintProblem;
+use crate::lint_fix::TextEdit;
+use crate::media_type::MediaType;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::futures;
+use deno_core::futures::FutureExt;
+use deno_core::json_op_sync;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::JsRuntime;
+use deno_core::ModuleLoader;
+use deno_core::ModuleSource;
+use deno_core::ModuleSourceFuture;
+use deno_core::ModuleSpecifier;
+use deno_core::OpState;
+use deno_core::RuntimeOptions;
+use deno_lint::diagnostic::LintDiagnostic;
+use deno_lint::diagnostic::Position;
+use deno_lint::diagnostic::Range;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use swc_common::BytePos;
+use swc_common::Span;
+use swc_common::SyntaxContext;
+
+static LINT_PLUGIN_JS: &str = include_str!("lint_plugin.js");
+static ENTRY_SPECIFIER: &str = "file:///$deno$lint_plugins.js";
+
+/// Maximum time the plugins may take to lint a single file.
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+static NEXT_PLUGINS_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+  static PLUGIN_RUNTIME: RefCell<Option<PluginRuntime>> = RefCell::new(None);
+}
+
+/// The transpiled plugin modules and the rules they define.
+#[derive(Clone, Debug)]
+pub struct LintPlugins {
+  /// Identifies the plugins evaluated in a `PluginRuntime`.
+  id: usize,
+  modules: Vec<(ModuleSpecifier, String)>,
+  /// Codes of the rules defined by the plugins.
+  pub rule_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesResponse {
+  rules: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginEdit {
+  start: u32,
+  end: u32,
+  text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginFix {
+  description: String,
+  edits: Vec<PluginEdit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginDiagnostic {
+  code: String,
+  message: String,
+  start: u32,
+  end: u32,
+  fix: Option<PluginFix>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LintFileResponse {
+  diagnostics: Vec<PluginDiagnostic>,
+}
+
+/// Only serves the plugin modules, so that plugins can't load code from
+/// anywhere else.
+struct PluginModuleLoader {
+  modules: Vec<(ModuleSpecifier, String)>,
+}
+
+impl ModuleLoader for PluginModuleLoader {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &str,
+    _is_main: bool,
+  ) -> Result<ModuleSpecifier, AnyError> {
+    Ok(ModuleSpecifier::resolve_import(specifier, referrer)?)
+  }
+
+  fn load(
+    &self,
+    _op_state: Rc<RefCell<OpState>>,
+    module_specifier: &ModuleSpecifier,
+    _maybe_referrer: Option<ModuleSpecifier>,
+    _is_dyn_import: bool,
+  ) -> Pin<Box<ModuleSourceFuture>> {
+    let maybe_module = self.modules.iter().find(|(s, _)| s == module_specifier);
+    let result = match maybe_module {
+      Some((specifier, code)) => Ok(ModuleSource {
+        code: code.clone(),
+        module_url_specified: specifier.to_string(),
+        module_url_found: specifier.to_string(),
+      }),
+      None => Err(generic_error(format!(
+        "Lint plugins can't import other modules: {}",
+        module_specifier
+      ))),
+    };
+    futures::future::ready(result).boxed_local()
+  }
+}
+
+impl LintPlugins {
+  /// Read and transpile the plugin modules at `paths` and collect the rules
+  /// they define.
+  pub fn load(paths: &[PathBuf]) -> Result<Self, AnyError> {
+    let mut modules = Vec::new();
+    for path in paths {
+      let specifier =
+        ModuleSpecifier::resolve_url_or_path(&path.to_string_lossy())?;
+      let source = fs::read_to_string(path).map_err(|err| {
+        generic_error(format!(
+          "Failed to read lint plugin {}: {}",
+          path.to_string_lossy(),
+          err
+        ))
+      })?;
+      let media_type = MediaType::from(path.as_path());
+      let parsed_module = ast::parse(&specifier, &source, &media_type)?;
+      let (code, _) =
+        parsed_module.transpile(&ast::TranspileOptions::default())?;
+      modules.push((specifier, code.to_string()?));
+    }
+
+    let mut plugins = LintPlugins {
+      id: NEXT_PLUGINS_ID.fetch_add(1, Ordering::Relaxed),
+      modules,
+      rule_codes: vec![],
+    };
+    let response = plugins.execute("globalThis.lintPluginsGetRules();")?;
+    let response: RulesResponse = serde_json::from_value(response)?;
+    plugins.rule_codes = response.rules;
+    Ok(plugins)
+  }
+
+  /// The plugin sources, which affect the result of linting.
+  pub fn sources(&self) -> Vec<&[u8]> {
+    self
+      .modules
+      .iter()
+      .map(|(_, code)| code.as_bytes())
+      .collect()
+  }
+
+  /// Run the plugin rules `codes` on the given file. The problems carry the
+  /// fixes suggested by the rules.
+  pub fn lint_file(
+    &self,
+    file_path: &Path,
+    source: &str,
+    codes: &[String],
+  ) -> Result<Vec<LintProblem>, AnyError> {
+    if codes.is_empty() {
+      return Ok(vec![]);
+    }
+    let specifier =
+      ModuleSpecifier::resolve_url_or_path(&file_path.to_string_lossy())?;
+    let media_type = MediaType::from(file_path);
+    let parsed_module = ast::parse(&specifier, source, &media_type)?;
+    let filename = file_path.to_string_lossy().to_string();
+    let request = json!({
+      "filename": filename,
+      "source": source,
+      "ast": parsed_module.to_json()?,
+      "codes": codes,
+    });
+    let response =
+      self.execute(&format!("globalThis.lintPluginsLintFile({});", request))?;
+    let response: LintFileResponse = serde_json::from_value(response)?;
+
+    let source_lines = source.split('\n').collect::<Vec<_>>();
+    let get_position = |pos: u32| {
+      let location = parsed_module.get_location(&Span::new(
+        BytePos(pos),
+        BytePos(pos),
+        SyntaxContext::empty(),
+      ));
+      Position {
+        line: location.line,
+        col: location.col,
+      }
+    };
+    let get_offset = |pos: u32| parsed_module.get_byte_offset(BytePos(pos));
+    let problems = response
+      .diagnostics
+      .into_iter()
+      .map(|d| LintProblem {
+        diagnostic: LintDiagnostic {
+          range: Range {
+            start: get_position(d.start),
+            end: get_position(d.end),
+          },
+          filename: filename.clone(),
+          message: d.message,
+          code: d.code,
+        },
+        fix: d.fix.map(|fix| LintFix {
+          description: fix.description,
+          edits: fix
+            .edits
+            .into_iter()
+            .map(|edit| TextEdit {
+              range: get_offset(edit.start)..get_offset(edit.end),
+              new_text: edit.text,
+            })
+            .collect(),
+        }),
+      })
+      .filter(|p| !is_ignored(&source_lines, &p.diagnostic))
+      .collect();
+    Ok(problems)
+  }
+
+  /// Run `script` in the runtime of the current thread, in which the plugins
+  /// are evaluated on first use, and return the value it passed to
+  /// `op_lint_respond`. The runtime is discarded if the script fails, as its
+  /// state is unknown.
+  fn execute(&self, script: &str) -> Result<Value, AnyError> {
+    PLUGIN_RUNTIME.with(|cell| {
+      let mut maybe_runtime = cell.borrow_mut();
+      if maybe_runtime.as_ref().map(|r| r.plugins_id) != Some(self.id) {
+        *maybe_runtime = None;
+        maybe_runtime.replace(PluginRuntime::new(self)?);
+      }
+      let result = maybe_runtime.as_mut().unwrap().execute(script);
+      if result.is_err() {
+        *maybe_runtime = None;
+      }
+      result
+    })
+  }
+}
+
+/// A runtime in which the plugin modules were evaluated.
+struct PluginRuntime {
+  plugins_id: usize,
+  js_runtime: JsRuntime,
+  response: Arc<Mutex<Option<Value>>>,
+}
+
+impl PluginRuntime {
+  fn new(plugins: &LintPlugins) -> Result<Self, AnyError> {
+    let mut js_runtime = JsRuntime::new(RuntimeOptions {
+      module_loader: Some(Rc::new(PluginModuleLoader {
+        modules: plugins.modules.clone(),
+      })),
+      ..Default::default()
+    });
+    let response = Arc::new(Mutex::new(None));
+    {
+      let res = response.clone();
+      js_runtime.register_op(
+        "op_lint_respond",
+        json_op_sync(move |_state, args, _bufs| {
+          res.lock().unwrap().replace(args);
+          Ok(json!({}))
+        }),
+      );
+    }
+    let mut runtime = PluginRuntime {
+      plugins_id: plugins.id,
+      js_runtime,
+      response,
+    };
+    runtime.with_timeout(|js_runtime| {
+      js_runtime.execute("$deno$lint_plugin.js", LINT_PLUGIN_JS)?;
+      let entry_specifier = ModuleSpecifier::resolve_url(ENTRY_SPECIFIER)?;
+      let module_id = futures::executor::block_on(
+        js_runtime.load_module(&entry_specifier, Some(get_entry_code(plugins))),
+      )?;
+      js_runtime.mod_evaluate(module_id)
+    })?;
+    Ok(runtime)
+  }
+
+  /// Run `script` and return the value it passed to `op_lint_respond`.
+  fn execute(&mut self, script: &str) -> Result<Value, AnyError> {
+    self.with_timeout(|js_runtime| {
+      js_runtime.execute("$deno$lint_plugin.js", script)
+    })?;
+    let maybe_response = self.response.lock().unwrap().take();
+    maybe_response.ok_or_else(|| {
+      generic_error("Unexpected missing response from lint plugins")
+    })
+  }
+
+  /// Call `f`, terminating the execution of JavaScript if it takes longer
+  /// than `PLUGIN_TIMEOUT`.
+  fn with_timeout<F>(&mut self, f: F) -> Result<(), AnyError>
+  where
+    F: FnOnce(&mut JsRuntime) -> Result<(), AnyError>,
+  {
+    let isolate_handle = self.js_runtime.thread_safe_handle();
+    let (done_sender, done_receiver) = mpsc::channel::<()>();
+    let watchdog = thread::spawn(move || {
+      let timed_out = matches!(
+        done_receiver.recv_timeout(PLUGIN_TIMEOUT),
+        Err(RecvTimeoutError::Timeout)
+      );
+      if timed_out {
+        isolate_handle.terminate_execution();
+      }
+      timed_out
+    });
+    let result = f(&mut self.js_runtime);
+    done_sender.send(()).ok();
+    if watchdog.join().unwrap() {
+      return Err(generic_error(format!(
+        "Lint plugins did not finish within {} seconds",
+        PLUGIN_TIMEOUT.as_secs()
+      )));
+    }
+    result
+  }
+}
+
+fn get_entry_code(plugins: &LintPlugins) -> String {
+  let mut code = String::new();
+  for (i, (specifier, _)) in plugins.modules.iter().enumerate() {
+    let specifier = serde_json::to_string(&specifier.to_string()).unwrap();
+    code.push_str(&format!("import plugin{} from {};\n", i, specifier));
+    code.push_str(&format!(
+      "globalThis.__lintPlugins.push({{ specifier: {}, plugin: plugin{} }});\n",
+      specifier, i
+    ));
+  }
+  code
+}
+
+/// Plugin rules honor the same ignore directives as the built-in rules:
+/// `// deno-lint-ignore-file` and `// deno-lint-ignore <codes>` on the line
+/// preceding the problem.
+fn is_ignored(source_lines: &[&str], d: &LintDiagnostic) -> bool {
+  let is_comment = |line: &str, directive: &str| {
+    let line = line.trim();
+    line.starts_with("//") && line[2..].trim_start().starts_with(directive)
+  };
+  let ignore_file = source_lines
+    .iter()
+    .take_while(|line| line.trim().is_empty() || line.trim().starts_with("//"))
+    .any(|line| is_comment(line, "deno-lint-ignore-file"));
+  if ignore_file {
+    return true;
+  }
+  if d.range.start.line < 2 {
+    return false;
+  }
+  let previous_line = source_lines[d.range.start.line - 2].trim();
+  if !is_comment(previous_line, "deno-lint-ignore ") {
+    return false;
+  }
+  previous_line[2..]
+    .trim_start()
+    .split_whitespace()
+    .skip(1)
+    .any(|code| code == d.code)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn diagnostic(line: usize, code: &str) -> LintDiagnostic {
+    LintDiagnostic {
+      range: Range {
+        start: Position { line, col: 0 },
+        end: Position { line, col: 1 },
+      },
+      filename: "mod.ts".to_string(),
+      message: "message".to_string(),
+      code: code.to_string(),
+    }
+  }
+
+  #[test]
+  fn test_is_ignored() {
+    let source_lines = vec![
+      "a;",
+      "// deno-lint-ignore no-foo no-bar",
+      "b;",
+      "  //deno-lint-ignore no-baz",
+      "c;",
+    ];
+    assert!(!is_ignored(&source_lines, &diagnostic(1, "no-foo")));
+    assert!(is_ignored(&source_lines, &diagnostic(3, "no-foo")));
+    assert!(is_ignored(&source_lines, &diagnostic(3, "no-bar")));
+    assert!(!is_ignored(&source_lines, &diagnostic(3, "no-baz")));
+    assert!(is_ignored(&source_lines, &diagnostic(5, "no-baz")));
+
+    let source_lines = vec!["// deno-lint-ignore-file", "a;"];
+    assert!(is_ignored(&source_lines, &diagnostic(2, "no-foo")));
+  }
+}
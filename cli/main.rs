@@ -8,14 +8,19 @@ extern crate lazy_static;
 extern crate log;
 
 mod ast;
+mod bench_runner;
 mod checksum;
 pub mod colors;
+mod config_file;
 mod coverage;
 pub mod deno_dir;
 pub mod diagnostics;
 mod diff;
 mod disk_cache;
+mod doc_html;
+mod doc_test;
 pub mod errors;
+mod escape;
 mod file_fetcher;
 mod file_watcher;
 pub mod flags;
@@ -29,11 +34,14 @@ mod graph;
 pub mod http_cache;
 mod http_util;
 mod import_map;
+mod incremental_cache;
 mod info;
 mod inspector;
 pub mod installer;
 mod js;
 mod lint;
+mod lint_fix;
+mod lint_plugin;
 mod lockfile;
 mod media_type;
 mod metrics;
@@ -48,6 +56,7 @@ pub mod source_maps;
 mod specifier_handler;
 pub mod state;
 mod test_runner;
+mod test_snapshot;
 mod text_encoding;
 mod tokio_util;
 mod tsc;
@@ -56,15 +65,26 @@ mod upgrade;
 pub mod version;
 pub mod worker;
 
+use crate::bench_runner::BenchOptions;
+use crate::config_file::FmtOptionsConfig;
+use crate::coverage::Coverage;
 use crate::coverage::CoverageCollector;
+use crate::coverage::CoverageReport;
+use crate::coverage::CoverageReporter;
+use crate::coverage::CoverageReporterKind;
+use crate::coverage::CoverageThreshold;
 use crate::coverage::PrettyCoverageReporter;
+use crate::deno_dir::DenoDir;
 use crate::file_fetcher::SourceFile;
 use crate::file_fetcher::SourceFileFetcher;
 use crate::file_fetcher::TextDocument;
 use crate::fs as deno_fs;
 use crate::global_state::GlobalState;
+use crate::lint::LintReporterKind;
 use crate::media_type::MediaType;
 use crate::permissions::Permissions;
+use crate::test_runner::TestOptions;
+use crate::test_runner::TestReporterKind;
 use crate::worker::MainWorker;
 use deno_core::error::AnyError;
 use deno_core::futures::future::FutureExt;
@@ -81,10 +101,12 @@ use flags::Flags;
 use global_state::exit_unstable;
 use log::Level;
 use log::LevelFilter;
+use std::collections::HashSet;
 use std::env;
 use std::io::Read;
 use std::io::Write;
 use std::iter::once;
+use std::path::Path;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -199,23 +221,57 @@ async fn install_command(
   installer::install(flags, &module_url, args, name, root, force)
 }
 
+fn get_deno_dir() -> Result<DenoDir, AnyError> {
+  let custom_root = env::var("DENO_DIR").map(String::into).ok();
+  let deno_dir = DenoDir::new(custom_root)?;
+  Ok(deno_dir)
+}
+
 async fn lint_command(
   flags: Flags,
   files: Vec<String>,
   list_rules: bool,
   ignore: Vec<String>,
-  json: bool,
+  reporter: LintReporterKind,
+  fix: bool,
 ) -> Result<(), AnyError> {
   if !flags.unstable {
     exit_unstable("lint");
   }
 
   if list_rules {
-    lint::print_rules_list();
-    return Ok(());
+    return lint::print_rules_list(flags.config_path);
   }
 
-  lint::lint_files(files, ignore, json).await
+  let deno_dir = get_deno_dir()?;
+  lint::lint_files(
+    files,
+    ignore,
+    reporter,
+    fix,
+    flags.config_path,
+    deno_dir.lint_incremental_cache_dir(),
+  )
+  .await
+}
+
+async fn format_command(
+  flags: Flags,
+  files: Vec<String>,
+  check: bool,
+  ignore: Vec<String>,
+  options: FmtOptionsConfig,
+) -> Result<(), AnyError> {
+  let deno_dir = get_deno_dir()?;
+  fmt::format(
+    files,
+    check,
+    ignore,
+    flags.config_path,
+    options,
+    deno_dir.fmt_incremental_cache_dir(),
+  )
+  .await
 }
 
 async fn cache_command(
@@ -237,6 +293,47 @@ async fn cache_command(
   Ok(())
 }
 
+async fn coverage_command(
+  flags: Flags,
+  dir: PathBuf,
+  reporter_kind: CoverageReporterKind,
+  output: Option<PathBuf>,
+) -> Result<(), AnyError> {
+  if !flags.unstable {
+    exit_unstable("coverage");
+  }
+
+  let reports = coverage::load_coverage_reports(&dir)?;
+  let output = match (reporter_kind, output) {
+    (CoverageReporterKind::Html, None) => Some(dir.join("html")),
+    (_, output) => output,
+  };
+  let mut reporter =
+    coverage::create_coverage_reporter(reporter_kind, output, false)?;
+  for report in &reports {
+    reporter.visit_report(report)?;
+  }
+  reporter.done()?;
+
+  if let Some(threshold) = &flags.coverage_threshold {
+    exit_if_below_coverage_threshold(&reports, threshold);
+  }
+  Ok(())
+}
+
+fn exit_if_below_coverage_threshold(
+  reports: &[CoverageReport],
+  threshold: &CoverageThreshold,
+) {
+  let failures = coverage::check_coverage_threshold(reports, threshold);
+  if !failures.is_empty() {
+    for failure in failures {
+      eprintln!("{}: {}", colors::red_bold("error"), failure);
+    }
+    std::process::exit(1);
+  }
+}
+
 async fn eval_command(
   flags: Flags,
   code: String,
@@ -320,59 +417,53 @@ async fn bundle_command(
   Ok(())
 }
 
-async fn doc_command(
-  flags: Flags,
-  source_file: Option<String>,
-  json: bool,
-  maybe_filter: Option<String>,
-  private: bool,
-) -> Result<(), AnyError> {
-  let global_state = GlobalState::new(flags.clone())?;
-  let source_file = source_file.unwrap_or_else(|| "--builtin".to_string());
-
-  impl DocFileLoader for SourceFileFetcher {
-    fn resolve(
-      &self,
-      specifier: &str,
-      referrer: &str,
-    ) -> Result<String, doc::DocError> {
-      ModuleSpecifier::resolve_import(specifier, referrer)
-        .map(|specifier| specifier.to_string())
-        .map_err(|e| doc::DocError::Resolve(e.to_string()))
-    }
+impl DocFileLoader for SourceFileFetcher {
+  fn resolve(
+    &self,
+    specifier: &str,
+    referrer: &str,
+  ) -> Result<String, doc::DocError> {
+    ModuleSpecifier::resolve_import(specifier, referrer)
+      .map(|specifier| specifier.to_string())
+      .map_err(|e| doc::DocError::Resolve(e.to_string()))
+  }
 
-    fn load_source_code(
-      &self,
-      specifier: &str,
-    ) -> Pin<Box<dyn Future<Output = Result<String, doc::DocError>>>> {
-      let fetcher = self.clone();
-      let specifier = ModuleSpecifier::resolve_url_or_path(specifier)
-        .expect("Expected valid specifier");
-      async move {
-        let source_file = fetcher
-          .fetch_source_file(&specifier, None, Permissions::allow_all())
-          .await
-          .map_err(|e| {
-            doc::DocError::Io(std::io::Error::new(
-              std::io::ErrorKind::Other,
-              e.to_string(),
-            ))
-          })?;
-        source_file.source_code.to_string().map_err(|e| {
+  fn load_source_code(
+    &self,
+    specifier: &str,
+  ) -> Pin<Box<dyn Future<Output = Result<String, doc::DocError>>>> {
+    let fetcher = self.clone();
+    let specifier = ModuleSpecifier::resolve_url_or_path(specifier)
+      .expect("Expected valid specifier");
+    async move {
+      let source_file = fetcher
+        .fetch_source_file(&specifier, None, Permissions::allow_all())
+        .await
+        .map_err(|e| {
           doc::DocError::Io(std::io::Error::new(
             std::io::ErrorKind::Other,
             e.to_string(),
           ))
-        })
-      }
-      .boxed_local()
+        })?;
+      source_file.source_code.to_string().map_err(|e| {
+        doc::DocError::Io(std::io::Error::new(
+          std::io::ErrorKind::Other,
+          e.to_string(),
+        ))
+      })
     }
+    .boxed_local()
   }
+}
 
-  let loader = Box::new(global_state.file_fetcher.clone());
-  let doc_parser = doc::DocParser::new(loader, private);
-
-  let parse_result = if source_file == "--builtin" {
+/// Parse the doc nodes of `source_file`, or of the runtime built-ins if it
+/// is `--builtin`.
+async fn parse_doc_nodes(
+  flags: &Flags,
+  doc_parser: &doc::DocParser,
+  source_file: &str,
+) -> Result<Vec<doc::DocNode>, doc::DocError> {
+  if source_file == "--builtin" {
     let syntax = ast::get_syntax(&MediaType::Dts);
     doc_parser.parse_source(
       "lib.deno.d.ts",
@@ -380,15 +471,31 @@ async fn doc_command(
       get_types(flags.unstable).as_str(),
     )
   } else {
-    let path = PathBuf::from(&source_file);
+    let path = PathBuf::from(source_file);
     let media_type = MediaType::from(&path);
     let syntax = ast::get_syntax(&media_type);
     let module_specifier =
-      ModuleSpecifier::resolve_url_or_path(&source_file).unwrap();
+      ModuleSpecifier::resolve_url_or_path(source_file).unwrap();
     doc_parser
       .parse_with_reexports(&module_specifier.to_string(), syntax)
       .await
-  };
+  }
+}
+
+async fn doc_command(
+  flags: Flags,
+  source_file: Option<String>,
+  json: bool,
+  maybe_filter: Option<String>,
+  private: bool,
+) -> Result<(), AnyError> {
+  let global_state = GlobalState::new(flags.clone())?;
+  let source_file = source_file.unwrap_or_else(|| "--builtin".to_string());
+
+  let loader = Box::new(global_state.file_fetcher.clone());
+  let doc_parser = doc::DocParser::new(loader, private);
+
+  let parse_result = parse_doc_nodes(&flags, &doc_parser, &source_file).await;
 
   let mut doc_nodes = match parse_result {
     Ok(nodes) => nodes,
@@ -424,14 +531,53 @@ async fn doc_command(
   }
 }
 
+async fn doc_html_command(
+  flags: Flags,
+  entry_points: Vec<String>,
+  output: PathBuf,
+  private: bool,
+) -> Result<(), AnyError> {
+  let global_state = GlobalState::new(flags.clone())?;
+  let loader = Box::new(global_state.file_fetcher.clone());
+  let doc_parser = doc::DocParser::new(loader, private);
+
+  let entry_points = if entry_points.is_empty() {
+    vec!["--builtin".to_string()]
+  } else {
+    entry_points
+  };
+  let mut modules = Vec::new();
+  for source_file in entry_points {
+    let doc_nodes =
+      match parse_doc_nodes(&flags, &doc_parser, &source_file).await {
+        Ok(nodes) => nodes,
+        Err(e) => {
+          eprintln!("{}", e);
+          std::process::exit(1);
+        }
+      };
+    let specifier = if source_file == "--builtin" {
+      "lib.deno.d.ts".to_string()
+    } else {
+      ModuleSpecifier::resolve_url_or_path(&source_file)?.to_string()
+    };
+    modules.push(doc_html::DocModule {
+      specifier,
+      doc_nodes,
+    });
+  }
+
+  doc_html::generate(modules, &output)?;
+  info!("Documentation written to {}", output.to_string_lossy());
+  Ok(())
+}
+
 async fn run_repl(flags: Flags) -> Result<(), AnyError> {
   let main_module =
     ModuleSpecifier::resolve_url_or_path("./$deno$repl.ts").unwrap();
   let global_state = GlobalState::new(flags)?;
   let mut worker = MainWorker::new(&global_state, main_module);
-  loop {
-    (&mut *worker).await?;
-  }
+  repl::run(&mut worker).await
 }
 
 async fn run_from_stdin(flags: Flags) -> Result<(), AnyError> {
@@ -465,10 +611,11 @@ async fn run_from_stdin(flags: Flags) -> Result<(), AnyError> {
   Ok(())
 }
 
-async fn run_with_watch(flags: Flags, script: String) -> Result<(), AnyError> {
-  let main_module = ModuleSpecifier::resolve_url_or_path(&script)?;
-  let global_state = GlobalState::new(flags.clone())?;
-
+/// Find all local files in the module graph of `module_specifier`.
+async fn get_local_graph_files(
+  global_state: &Arc<GlobalState>,
+  module_specifier: &ModuleSpecifier,
+) -> Result<Vec<PathBuf>, AnyError> {
   let mut module_graph_loader = module_graph::ModuleGraphLoader::new(
     global_state.file_fetcher.clone(),
     global_state.maybe_import_map.clone(),
@@ -476,23 +623,41 @@ async fn run_with_watch(flags: Flags, script: String) -> Result<(), AnyError> {
     false,
     false,
   );
-  module_graph_loader.add_to_graph(&main_module, None).await?;
+  module_graph_loader
+    .add_to_graph(module_specifier, None)
+    .await?;
   let module_graph = module_graph_loader.get_graph();
 
-  // Find all local files in graph
-  let mut paths_to_watch: Vec<PathBuf> = module_graph
-    .values()
-    .map(|f| Url::parse(&f.url).unwrap())
-    .filter(|url| url.scheme() == "file")
-    .map(|url| url.to_file_path().unwrap())
-    .collect();
+  Ok(
+    module_graph
+      .values()
+      .map(|f| Url::parse(&f.url).unwrap())
+      .filter(|url| url.scheme() == "file")
+      .map(|url| url.to_file_path().unwrap())
+      .collect(),
+  )
+}
 
-  if let Some(import_map) = global_state.flags.import_map_path.clone() {
-    paths_to_watch.push(
+fn get_import_map_path(flags: &Flags) -> Result<Option<PathBuf>, AnyError> {
+  match flags.import_map_path.clone() {
+    Some(import_map) => Ok(Some(
       Url::parse(&format!("file://{}", &import_map))?
         .to_file_path()
         .unwrap(),
-    );
+    )),
+    None => Ok(None),
+  }
+}
+
+async fn run_with_watch(flags: Flags, script: String) -> Result<(), AnyError> {
+  let main_module = ModuleSpecifier::resolve_url_or_path(&script)?;
+  let global_state = GlobalState::new(flags.clone())?;
+
+  let mut paths_to_watch =
+    get_local_graph_files(&global_state, &main_module).await?;
+
+  if let Some(import_map) = get_import_map_path(&global_state.flags)? {
+    paths_to_watch.push(import_map);
   }
 
   // FIXME(bartlomieju): new file watcher is created on after each restart
@@ -529,27 +694,135 @@ async fn run_command(flags: Flags, script: String) -> Result<(), AnyError> {
   let global_state = GlobalState::new(flags.clone())?;
   let mut worker = MainWorker::new(&global_state, main_module.clone());
   debug!("main_module {}", main_module);
-  worker.execute_module(&main_module).await?;
+  if let Some(coverage_dir) = &flags.coverage_dir {
+    return run_with_coverage(
+      &global_state,
+      worker,
+      &main_module,
+      coverage_dir,
+    )
+    .await;
+  }
+  run_main_worker(&mut worker, &main_module).await
+}
+
+async fn run_main_worker(
+  worker: &mut MainWorker,
+  main_module: &ModuleSpecifier,
+) -> Result<(), AnyError> {
+  worker.execute_module(main_module).await?;
   worker.execute("window.dispatchEvent(new Event('load'))")?;
   (&mut *worker).await?;
   worker.execute("window.dispatchEvent(new Event('unload'))")?;
   Ok(())
 }
 
+/// Run the main module while collecting coverage, which is saved to
+/// `coverage_dir` once the module completes, calls `Deno.exit()`, or the
+/// process receives SIGINT or SIGTERM.
+async fn run_with_coverage(
+  global_state: &Arc<GlobalState>,
+  mut worker: MainWorker,
+  main_module: &ModuleSpecifier,
+  coverage_dir: &Path,
+) -> Result<(), AnyError> {
+  let inspector = worker
+    .inspector
+    .as_mut()
+    .expect("Inspector is not created.");
+  let mut coverage_collector = CoverageCollector::new(&mut **inspector);
+  coverage_collector.start_collecting().await?;
+
+  let deferred_exit = worker.defer_exit();
+  let result = tokio::select! {
+    result = run_main_worker(&mut worker, main_module) => result,
+    code = exit_signal() => {
+      deferred_exit.code.set(Some(code));
+      Ok(())
+    }
+  };
+  // The execution is terminated when the module calls `Deno.exit()`.
+  deferred_exit.isolate_handle.cancel_terminate_execution();
+
+  let coverages = coverage_collector.collect().await?;
+  coverage_collector.stop_collecting().await?;
+  let mut coverages = coverage::filter_run_coverages(coverages);
+  coverage::attach_source_maps(global_state, &mut coverages);
+  coverage::save_coverages(coverage_dir, &coverages)?;
+
+  match deferred_exit.code.get() {
+    Some(code) => std::process::exit(code),
+    None => result,
+  }
+}
+
+/// Resolve to the exit code of a process killed by SIGINT or SIGTERM.
+async fn exit_signal() -> i32 {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::signal;
+    use tokio::signal::unix::SignalKind;
+    let mut sigterm =
+      signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+    tokio::select! {
+      _ = tokio::signal::ctrl_c() => 130,
+      _ = sigterm.recv() => 143,
+    }
+  }
+  #[cfg(not(unix))]
+  {
+    let _ = tokio::signal::ctrl_c().await;
+    130
+  }
+}
+
+async fn bench_command(
+  flags: Flags,
+  include: Option<Vec<String>>,
+  options: BenchOptions,
+) -> Result<(), AnyError> {
+  if !flags.unstable {
+    exit_unstable("bench");
+  }
+
+  let global_state = GlobalState::new(flags.clone())?;
+  let cwd = std::env::current_dir().expect("No current directory");
+  let include = include.unwrap_or_else(|| vec![".".to_string()]);
+  let bench_modules = bench_runner::prepare_bench_modules_urls(include, &cwd)?;
+
+  if bench_modules.is_empty() {
+    println!("No matching bench modules found");
+    std::process::exit(1);
+  }
+
+  let bench_file_path = cwd.join("$deno$bench.ts");
+  let bench_file_url =
+    Url::from_file_path(&bench_file_path).expect("Should be valid file url");
+  let bench_file = bench_runner::render_bench_file(bench_modules, &options);
+  let (mut worker, main_module) =
+    test_runner::create_test_worker(&global_state, &bench_file_url, bench_file);
+  test_runner::run_test_worker(&mut worker, &main_module).await
+}
+
 async fn test_command(
   flags: Flags,
   include: Option<Vec<String>>,
-  fail_fast: bool,
-  quiet: bool,
   allow_none: bool,
-  filter: Option<String>,
+  options: TestOptions,
+  jobs: usize,
+  reporter_kind: TestReporterKind,
+  reporter_output: Option<PathBuf>,
 ) -> Result<(), AnyError> {
   let global_state = GlobalState::new(flags.clone())?;
   let cwd = std::env::current_dir().expect("No current directory");
   let include = include.unwrap_or_else(|| vec![".".to_string()]);
-  let test_modules = test_runner::prepare_test_modules_urls(include, &cwd)?;
+  let test_modules =
+    prepare_test_modules(&global_state, &include, &cwd, &options).await?;
 
-  if test_modules.is_empty() {
+  if test_modules.modules.is_empty() {
+    if test_modules.checked_examples() > 0 {
+      return Ok(());
+    }
     println!("No matching test modules found");
     if !allow_none {
       std::process::exit(1);
@@ -557,34 +830,52 @@ async fn test_command(
     return Ok(());
   }
 
+  if flags.watch {
+    return test_with_watch(
+      flags,
+      global_state,
+      include,
+      test_modules,
+      options,
+      jobs,
+      reporter_kind,
+      reporter_output,
+    )
+    .await;
+  }
+
+  // Results are collected from the workers when running in parallel, with
+  // any reporter but the one of the worker's test runner, or when they are
+  // written to a file.
+  if jobs > 1
+    || reporter_kind != TestReporterKind::Pretty
+    || reporter_output.is_some()
+  {
+    let passed = run_test_modules_in_workers(
+      &flags,
+      &global_state,
+      &cwd,
+      test_modules.modules,
+      jobs,
+      &options,
+      reporter_kind,
+      reporter_output,
+    )
+    .await?;
+    if !passed {
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+  let test_modules = test_modules.modules;
+
   let test_file_path = cwd.join("$deno$test.ts");
   let test_file_url =
     Url::from_file_path(&test_file_path).expect("Should be valid file url");
-  let test_file = test_runner::render_test_file(
-    test_modules.clone(),
-    fail_fast,
-    quiet,
-    filter,
-  );
-  let main_module =
-    ModuleSpecifier::resolve_url(&test_file_url.to_string()).unwrap();
-  let mut worker = MainWorker::new(&global_state, main_module.clone());
-  // Create a dummy source file.
-  let source_file = SourceFile {
-    filename: test_file_url.to_file_path().unwrap(),
-    url: test_file_url.clone(),
-    types_header: None,
-    media_type: MediaType::TypeScript,
-    source_code: TextDocument::new(
-      test_file.clone().into_bytes(),
-      Some("utf-8"),
-    ),
-  };
-  // Save our fake file into file fetcher cache
-  // to allow module access by TS compiler
-  global_state
-    .file_fetcher
-    .save_source_file_in_cache(&main_module, source_file);
+  let test_file =
+    test_runner::render_test_file(test_modules.clone(), &options, false);
+  let (mut worker, main_module) =
+    test_runner::create_test_worker(&global_state, &test_file_url, test_file);
 
   let mut maybe_coverage_collector = if flags.coverage {
     let inspector = worker
@@ -600,12 +891,7 @@ async fn test_command(
     None
   };
 
-  let execute_result = worker.execute_module(&main_module).await;
-  execute_result?;
-  worker.execute("window.dispatchEvent(new Event('load'))")?;
-  (&mut *worker).await?;
-  worker.execute("window.dispatchEvent(new Event('unload'))")?;
-  (&mut *worker).await?;
+  test_runner::run_test_worker(&mut worker, &main_module).await?;
 
   if let Some(coverage_collector) = maybe_coverage_collector.as_mut() {
     let coverages = coverage_collector.collect().await?;
@@ -613,16 +899,254 @@ async fn test_command(
 
     let filtered_coverages =
       coverage::filter_script_coverages(coverages, test_file_url, test_modules);
+    report_test_coverages(
+      &flags,
+      &global_state,
+      filtered_coverages,
+      options.quiet,
+    )?;
+  }
 
-    let mut coverage_reporter = PrettyCoverageReporter::new(quiet);
-    for coverage in filtered_coverages {
-      coverage_reporter.visit_coverage(&coverage);
+  Ok(())
+}
+
+/// The modules run by `deno test`.
+struct TestModules {
+  modules: Vec<Url>,
+  maybe_doc_tests: Option<doc_test::DocTests>,
+}
+
+impl TestModules {
+  /// The number of doc examples that were type checked.
+  fn checked_examples(&self) -> usize {
+    self
+      .maybe_doc_tests
+      .as_ref()
+      .map_or(0, |doc_tests| doc_tests.checked)
+  }
+}
+
+/// Find the test modules of `include`, with the module of its doc tests with
+/// `--doc`, in a random order with `--shuffle`.
+async fn prepare_test_modules(
+  global_state: &Arc<GlobalState>,
+  include: &[String],
+  cwd: &PathBuf,
+  options: &TestOptions,
+) -> Result<TestModules, AnyError> {
+  let mut modules =
+    test_runner::prepare_test_modules_urls(include.to_vec(), cwd)?;
+
+  let mut maybe_doc_tests = None;
+  if options.doc {
+    let doc_tests =
+      doc_test::prepare_doc_tests(global_state, include.to_vec(), cwd).await?;
+    modules.extend(doc_tests.maybe_test_module.clone());
+    maybe_doc_tests = Some(doc_tests);
+  }
+
+  if let Some(seed) = options.shuffle {
+    test_runner::shuffle_test_modules(&mut modules, seed);
+    if !options.quiet {
+      eprintln!(
+        "Running tests in a random order, use --shuffle={} to reproduce it",
+        seed
+      );
     }
   }
 
+  Ok(TestModules {
+    modules,
+    maybe_doc_tests,
+  })
+}
+
+/// Run the test modules in workers, then report the coverage they collected
+/// if they all passed. Returns whether they did.
+#[allow(clippy::too_many_arguments)]
+async fn run_test_modules_in_workers(
+  flags: &Flags,
+  global_state: &Arc<GlobalState>,
+  cwd: &Path,
+  test_modules: Vec<Url>,
+  jobs: usize,
+  options: &TestOptions,
+  reporter_kind: TestReporterKind,
+  reporter_output: Option<PathBuf>,
+) -> Result<bool, AnyError> {
+  let reporter = test_runner::create_reporter(
+    reporter_kind,
+    reporter_output,
+    options.quiet,
+  )?;
+  let (summary, coverages) = test_runner::run_tests_in_workers(
+    global_state,
+    cwd,
+    test_modules,
+    jobs,
+    options,
+    reporter,
+  )
+  .await?;
+  if summary.has_failed() {
+    return Ok(false);
+  }
+  if flags.coverage {
+    report_test_coverages(flags, global_state, coverages, options.quiet)?;
+  }
+  Ok(true)
+}
+
+/// Save the coverage collected by `deno test` or print it, then check it
+/// against the threshold.
+fn report_test_coverages(
+  flags: &Flags,
+  global_state: &GlobalState,
+  mut coverages: Vec<Coverage>,
+  quiet: bool,
+) -> Result<(), AnyError> {
+  coverage::attach_source_maps(global_state, &mut coverages);
+  let reports = coverage::create_coverage_reports(&coverages);
+  if let Some(coverage_dir) = &flags.coverage_dir {
+    coverage::save_coverages(coverage_dir, &coverages)?;
+  } else {
+    let mut coverage_reporter = PrettyCoverageReporter::new(quiet);
+    for report in &reports {
+      coverage_reporter.visit_report(report)?;
+    }
+    coverage_reporter.done()?;
+  }
+  if let Some(threshold) = &flags.coverage_threshold {
+    exit_if_below_coverage_threshold(&reports, threshold);
+  }
   Ok(())
 }
 
+/// Run the tests, then re-run the test modules whose module graph includes a
+/// file that changed. Failures are reported without exiting.
+#[allow(clippy::too_many_arguments)]
+async fn test_with_watch(
+  flags: Flags,
+  mut global_state: Arc<GlobalState>,
+  include: Vec<String>,
+  mut test_modules: TestModules,
+  options: TestOptions,
+  jobs: usize,
+  reporter_kind: TestReporterKind,
+  reporter_output: Option<PathBuf>,
+) -> Result<(), AnyError> {
+  let cwd = std::env::current_dir().expect("No current directory");
+  let maybe_import_map_path = get_import_map_path(&flags)?;
+  let mut modules_to_run = test_modules.modules.clone();
+  loop {
+    // A module that fails to load is reported by the test run, and only
+    // watched itself until it's fixed. The module of the doc tests is
+    // generated, so the files of the examples are watched instead.
+    let mut graphs = Vec::new();
+    for module in &test_modules.modules {
+      if let Some(doc_tests) =
+        test_modules.maybe_doc_tests.as_ref().filter(|doc_tests| {
+          doc_tests.maybe_test_module.as_ref() == Some(module)
+        })
+      {
+        let files = doc_tests
+          .sources
+          .iter()
+          .filter_map(|url| url.to_file_path().ok())
+          .map(|path| path.canonicalize().unwrap_or(path))
+          .collect::<HashSet<_>>();
+        graphs.push((module.clone(), files));
+        continue;
+      }
+      let module_specifier = ModuleSpecifier::from(module.clone());
+      let files = get_local_graph_files(&global_state, &module_specifier)
+        .await
+        .unwrap_or_else(|_| module.to_file_path().into_iter().collect());
+      let files = files
+        .into_iter()
+        .map(|path| path.canonicalize().unwrap_or(path))
+        .collect::<HashSet<_>>();
+      graphs.push((module.clone(), files));
+    }
+
+    if !modules_to_run.is_empty() {
+      let result = run_test_modules_in_workers(
+        &flags,
+        &global_state,
+        &cwd,
+        modules_to_run,
+        jobs,
+        &options,
+        reporter_kind,
+        reporter_output.clone(),
+      )
+      .await;
+      if let Err(err) = result {
+        eprintln!("{}: {}", colors::red_bold("error"), err);
+      }
+    }
+
+    let mut paths_to_watch = graphs
+      .iter()
+      .flat_map(|(_, files)| files.iter().cloned())
+      .collect::<HashSet<_>>()
+      .into_iter()
+      .collect::<Vec<_>>();
+    paths_to_watch.extend(maybe_import_map_path.clone());
+    info!(
+      "{} Tests finished! Restarting on file change...",
+      colors::intense_blue("Watcher")
+    );
+    let changed_paths = file_watcher::file_watcher(&paths_to_watch).await?;
+    let changed_paths = changed_paths
+      .into_iter()
+      .map(|path| path.canonicalize().unwrap_or(path))
+      .collect::<Vec<_>>();
+
+    if atty::is(atty::Stream::Stdout) {
+      // Clear the screen and move the cursor to the top left.
+      print!("\x1B[2J\x1B[1;1H");
+    }
+    info!(
+      "{} File change detected! Restarting!",
+      colors::intense_blue("Watcher")
+    );
+
+    // Test modules may have been added, which are run as well. A change of
+    // the import map may affect any module.
+    let import_map_changed =
+      maybe_import_map_path.as_ref().map_or(false, |p| {
+        let p = p.canonicalize().unwrap_or_else(|_| p.clone());
+        changed_paths.contains(&p)
+      });
+    // GlobalState must be created on each run, otherwise the file fetcher
+    // would use cached source files.
+    global_state = GlobalState::new(flags.clone())?;
+    let previous_modules = test_modules.modules.clone();
+    match prepare_test_modules(&global_state, &include, &cwd, &options).await {
+      Ok(modules) => test_modules = modules,
+      // Such as an example of the doc tests that doesn't type check.
+      Err(err) => {
+        eprintln!("{}: {}", colors::red_bold("error"), err);
+        modules_to_run = Vec::new();
+        continue;
+      }
+    }
+    modules_to_run = test_modules
+      .modules
+      .iter()
+      .filter(|module| {
+        import_map_changed
+          || !previous_modules.contains(module)
+          || graphs.iter().any(|(m, files)| {
+            m == *module && changed_paths.iter().any(|p| files.contains(p))
+          })
+      })
+      .cloned()
+      .collect();
+  }
+}
+
 pub fn main() {
   #[cfg(windows)]
   colors::enable_ansi(); // For Windows 10
@@ -687,6 +1211,13 @@ pub fn main() {
   .init();
 
   let fut = match flags.clone().subcommand {
+    DenoSubcommand::Bench {
+      include,
+      filter,
+      json,
+    } => {
+      bench_command(flags, include, BenchOptions { filter, json }).boxed_local()
+    }
     DenoSubcommand::Bundle {
       source_file,
       out_file,
@@ -696,7 +1227,17 @@ pub fn main() {
       json,
       filter,
       private,
-    } => doc_command(flags, source_file, json, filter, private).boxed_local(),
+      html_output,
+      entry_points,
+    } => match html_output {
+      Some(html_output) => {
+        doc_html_command(flags, entry_points, html_output, private)
+          .boxed_local()
+      }
+      None => {
+        doc_command(flags, source_file, json, filter, private).boxed_local()
+      }
+    },
     DenoSubcommand::Eval {
       print,
       code,
@@ -705,11 +1246,17 @@ pub fn main() {
     DenoSubcommand::Cache { files } => {
       cache_command(flags, files).boxed_local()
     }
+    DenoSubcommand::Coverage {
+      dir,
+      reporter,
+      output,
+    } => coverage_command(flags, dir, reporter, output).boxed_local(),
     DenoSubcommand::Fmt {
       check,
       files,
       ignore,
-    } => fmt::format(files, check, ignore).boxed_local(),
+      options,
+    } => format_command(flags, files, check, ignore, options).boxed_local(),
     DenoSubcommand::Info { file, json } => {
       info_command(flags, file, json).boxed_local()
     }
@@ -726,8 +1273,9 @@ pub fn main() {
       files,
       rules,
       ignore,
-      json,
-    } => lint_command(flags, files, rules, ignore, json).boxed_local(),
+      reporter,
+      fix,
+    } => lint_command(flags, files, rules, ignore, reporter, fix).boxed_local(),
     DenoSubcommand::Repl => run_repl(flags).boxed_local(),
     DenoSubcommand::Run { script } => run_command(flags, script).boxed_local(),
     DenoSubcommand::Test {
@@ -736,8 +1284,36 @@ pub fn main() {
       include,
       allow_none,
       filter,
-    } => test_command(flags, include, fail_fast, quiet, allow_none, filter)
-      .boxed_local(),
+      jobs,
+      reporter,
+      reporter_output,
+      timeout,
+      update_snapshots,
+      doc,
+      shuffle,
+      trace_ops,
+    } => {
+      let options = TestOptions {
+        fail_fast,
+        quiet,
+        filter,
+        timeout,
+        update_snapshots,
+        doc,
+        shuffle,
+        trace_ops,
+      };
+      test_command(
+        flags,
+        include,
+        allow_none,
+        options,
+        jobs,
+        reporter,
+        reporter_output,
+      )
+      .boxed_local()
+    }
     DenoSubcommand::Completions { buf } => {
       if let Err(e) = write_to_stdout_ignore_sigpipe(&buf) {
         eprintln!("{}", e);
@@ -20,6 +20,7 @@ pub mod repl;
 pub mod runtime;
 pub mod runtime_compiler;
 pub mod signal;
+pub mod testing;
 pub mod timers;
 pub mod tls;
 pub mod tty;
@@ -6,11 +6,14 @@ use deno_core::serde_json;
 use deno_core::serde_json::json;
 use deno_core::serde_json::Value;
 use deno_core::url::Url;
+use deno_core::v8;
 use deno_core::OpState;
 use deno_core::ZeroCopyBuf;
 use serde::Deserialize;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::env;
+use std::rc::Rc;
 
 pub fn init(rt: &mut deno_core::JsRuntime) {
   super::reg_json_sync(rt, "op_exit", op_exit);
@@ -108,12 +111,26 @@ struct Exit {
   code: i32,
 }
 
+/// Makes `Deno.exit()` terminate the execution of the isolate rather than
+/// exit the process, recording the exit code for the embedder to exit with
+/// once it cleaned up.
+#[derive(Clone)]
+pub struct DeferredExit {
+  pub isolate_handle: v8::IsolateHandle,
+  pub code: Rc<Cell<Option<i32>>>,
+}
+
 fn op_exit(
-  _state: &mut OpState,
+  state: &mut OpState,
   args: Value,
   _zero_copy: &mut [ZeroCopyBuf],
 ) -> Result<Value, AnyError> {
   let args: Exit = serde_json::from_value(args)?;
+  if let Some(deferred_exit) = state.try_borrow::<DeferredExit>() {
+    deferred_exit.code.set(Some(args.code));
+    deferred_exit.isolate_handle.terminate_execution();
+    return Ok(json!({}));
+  }
   std::process::exit(args.code)
 }
 
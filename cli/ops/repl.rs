@@ -19,9 +19,13 @@ use std::sync::Mutex;
 pub fn init(rt: &mut deno_core::JsRuntime) {
   super::reg_json_sync(rt, "op_repl_start", op_repl_start);
   super::reg_json_async(rt, "op_repl_readline", op_repl_readline);
+  super::reg_json_sync(rt, "op_repl_transpile", op_repl_transpile);
 }
 
-struct ReplResource(Arc<Mutex<Repl>>);
+struct ReplResource {
+  repl: Arc<Mutex<Repl>>,
+  entries: repl::ReplEntries,
+}
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -40,8 +44,15 @@ fn op_repl_start(
     let cli_state = super::global_state(state);
     repl::history_path(&cli_state.dir, &args.history_file)
   };
-  let repl = repl::Repl::new(history_path);
-  let resource = ReplResource(Arc::new(Mutex::new(repl)));
+  // The editor completes lines with the help of `repl::run()`, which takes
+  // the other end of its channels.
+  let (helper, helper_channels) = repl::EditorHelper::new();
+  state.put(helper_channels);
+  let repl = repl::Repl::new(history_path, helper);
+  let resource = ReplResource {
+    repl: Arc::new(Mutex::new(repl)),
+    entries: repl::ReplEntries::new(super::global_state(state)),
+  };
   let rid = state.resource_table.add("repl", Box::new(resource));
   Ok(json!(rid))
 }
@@ -67,7 +78,7 @@ async fn op_repl_readline(
       .resource_table
       .get::<ReplResource>(rid)
       .ok_or_else(bad_resource_id)?;
-    resource.0.clone()
+    resource.repl.clone()
   };
   tokio::task::spawn_blocking(move || {
     let line = repl.lock().unwrap().readline(&prompt)?;
@@ -76,3 +87,31 @@ async fn op_repl_readline(
   .await
   .unwrap()
 }
+
+#[derive(Deserialize)]
+struct ReplTranspileArgs {
+  rid: i32,
+  source: String,
+  index: usize,
+}
+
+fn op_repl_transpile(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: ReplTranspileArgs = serde_json::from_value(args)?;
+  let rid = args.rid as u32;
+  debug!("op_repl_transpile {} {}", rid, args.index);
+  let resource = state
+    .resource_table
+    .get_mut::<ReplResource>(rid)
+    .ok_or_else(bad_resource_id)?;
+  let entry = resource.entries.transpile(&args.source, args.index)?;
+  Ok(match entry {
+    repl::TranspiledEntry::Script { code, filename } => {
+      json!({ "code": code, "filename": filename })
+    }
+    repl::TranspiledEntry::Incomplete => json!({ "incomplete": true }),
+  })
+}
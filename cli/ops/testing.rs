@@ -0,0 +1,414 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+use crate::fs::resolve_from_cwd;
+use crate::permissions::PermissionState;
+use crate::permissions::Permissions;
+use crate::permissions::UnaryPermission;
+use crate::test_runner::TestEvent;
+use crate::test_runner::TestMessage;
+use crate::test_snapshot::SnapshotModule;
+use crate::test_snapshot::SnapshotStore;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::futures::channel::mpsc::UnboundedSender;
+use deno_core::futures::channel::oneshot;
+use deno_core::futures::future::select;
+use deno_core::futures::future::Either;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::BufVec;
+use deno_core::OpState;
+use deno_core::ZeroCopyBuf;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+struct TestTimeout {
+  deadline: Instant,
+  tx: oneshot::Sender<()>,
+  rx: Option<oneshot::Receiver<()>>,
+}
+
+/// Timeouts of the running tests, keyed by an id chosen by the test runner.
+#[derive(Default)]
+struct TestTimeouts(HashMap<u32, TestTimeout>);
+
+/// The permissions of the worker while a test runs with permissions of its
+/// own, with the token of the pledge. The token is random, so that only the
+/// test runner that made the pledge can restore them.
+#[derive(Default)]
+struct PledgedPermissions {
+  maybe_original: Option<(String, Permissions)>,
+}
+
+pub fn init(rt: &mut deno_core::JsRuntime) {
+  {
+    let op_state = rt.op_state();
+    let mut state = op_state.borrow_mut();
+    state.put::<TestTimeouts>(TestTimeouts::default());
+    state.put::<SnapshotStore>(SnapshotStore::default());
+  }
+  super::reg_json_sync(rt, "op_test_timeout_start", op_test_timeout_start);
+  super::reg_json_sync(rt, "op_test_timeout_cancel", op_test_timeout_cancel);
+  super::reg_json_async(rt, "op_test_timeout", op_test_timeout);
+  super::reg_json_sync(rt, "op_assert_snapshot", op_assert_snapshot);
+  super::reg_json_sync(rt, "op_finish_snapshots", op_finish_snapshots);
+}
+
+/// Register the ops giving a test permissions of its own, which only the
+/// workers of the test runner may use.
+pub fn init_permissions(rt: &mut deno_core::JsRuntime) {
+  {
+    let op_state = rt.op_state();
+    let mut state = op_state.borrow_mut();
+    state.put::<PledgedPermissions>(PledgedPermissions::default());
+  }
+  super::reg_json_sync(
+    rt,
+    "op_pledge_test_permissions",
+    op_pledge_test_permissions,
+  );
+  super::reg_json_sync(
+    rt,
+    "op_restore_test_permissions",
+    op_restore_test_permissions,
+  );
+}
+
+/// Register the op used by the test runner of a worker running the test
+/// module at index `module` to post its progress. `timed_out_sender` is
+/// notified once the module is done if some of its tests timed out, as they
+/// may keep the event loop of the worker alive.
+pub fn init_events(
+  rt: &mut deno_core::JsRuntime,
+  module: usize,
+  sender: UnboundedSender<TestEvent>,
+  timed_out_sender: UnboundedSender<()>,
+) {
+  super::reg_json_sync(rt, "op_post_test_event", move |_state, args, _bufs| {
+    let message: TestMessage = serde_json::from_value(args)?;
+    let timed_out = matches!(
+      message,
+      TestMessage::End {
+        timed_out: true,
+        ..
+      }
+    );
+    sender
+      .unbounded_send(TestEvent { module, message })
+      .map_err(|_| generic_error("Test runner is not listening"))?;
+    if timed_out {
+      timed_out_sender.unbounded_send(()).ok();
+    }
+    Ok(json!({}))
+  });
+}
+
+#[derive(Deserialize)]
+struct TestTimeoutArgs {
+  id: u32,
+  timeout: Option<u64>,
+}
+
+// Like the global timer, the timeout is set up by a separate op so that it
+// can be cancelled before the op awaiting it has been polled.
+fn op_test_timeout_start(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: TestTimeoutArgs = serde_json::from_value(args)?;
+  let timeout = args
+    .timeout
+    .ok_or_else(|| generic_error("Missing test timeout"))?;
+  let (tx, rx) = oneshot::channel();
+  state.borrow_mut::<TestTimeouts>().0.insert(
+    args.id,
+    TestTimeout {
+      deadline: Instant::now() + Duration::from_millis(timeout),
+      tx,
+      rx: Some(rx),
+    },
+  );
+  Ok(json!({}))
+}
+
+fn op_test_timeout_cancel(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: TestTimeoutArgs = serde_json::from_value(args)?;
+  if let Some(timeout) = state.borrow_mut::<TestTimeouts>().0.remove(&args.id) {
+    timeout.tx.send(()).ok();
+  }
+  Ok(json!({}))
+}
+
+/// Resolves with whether the timeout expired, rather than being cancelled.
+async fn op_test_timeout(
+  state: Rc<RefCell<OpState>>,
+  args: Value,
+  _zero_copy: BufVec,
+) -> Result<Value, AnyError> {
+  let args: TestTimeoutArgs = serde_json::from_value(args)?;
+  let maybe_timeout = {
+    let mut state = state.borrow_mut();
+    let timeouts = state.borrow_mut::<TestTimeouts>();
+    timeouts
+      .0
+      .get_mut(&args.id)
+      .map(|timeout| (timeout.deadline, timeout.rx.take().unwrap()))
+  };
+  // Cancelled before being awaited.
+  let (deadline, rx) = match maybe_timeout {
+    Some(timeout) => timeout,
+    None => return Ok(json!({ "expired": false })),
+  };
+  let delay = tokio::time::delay_until(deadline.into());
+  let expired = match select(delay, rx).await {
+    Either::Left(_) => true,
+    Either::Right(_) => false,
+  };
+  let mut state = state.borrow_mut();
+  state.borrow_mut::<TestTimeouts>().0.remove(&args.id);
+  Ok(json!({ "expired": expired }))
+}
+
+#[derive(Deserialize)]
+struct AssertSnapshotArgs {
+  origin: String,
+  key: String,
+  value: String,
+  update: bool,
+}
+
+fn op_assert_snapshot(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: AssertSnapshotArgs = serde_json::from_value(args)?;
+  let maybe_error = state.borrow_mut::<SnapshotStore>().assert(
+    &args.origin,
+    &args.key,
+    &args.value,
+    args.update,
+  )?;
+  Ok(json!({ "ok": maybe_error.is_none(), "message": maybe_error }))
+}
+
+#[derive(Deserialize)]
+struct FinishSnapshotsArgs {
+  modules: Vec<SnapshotModule>,
+  update: bool,
+}
+
+/// Called once all tests have run, to write the updated snapshots and find
+/// the obsolete ones.
+fn op_finish_snapshots(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: FinishSnapshotsArgs = serde_json::from_value(args)?;
+  let obsolete = state
+    .borrow_mut::<SnapshotStore>()
+    .finish(&args.modules, args.update)?;
+  Ok(json!({ "obsolete": obsolete }))
+}
+
+/// Either all or none of a permission, or a list of the paths or hosts that
+/// are granted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UnaryPermissionArg {
+  All(bool),
+  List(Vec<String>),
+}
+
+/// The `permissions` of a test definition. Permissions that aren't set are
+/// those of the worker.
+#[derive(Deserialize)]
+struct TestPermissionsArgs {
+  read: Option<UnaryPermissionArg>,
+  write: Option<UnaryPermissionArg>,
+  net: Option<UnaryPermissionArg>,
+  env: Option<bool>,
+  run: Option<bool>,
+  plugin: Option<bool>,
+  hrtime: Option<bool>,
+}
+
+fn as_permission_state(allow: bool) -> PermissionState {
+  if allow {
+    PermissionState::Granted
+  } else {
+    PermissionState::Denied
+  }
+}
+
+fn as_unary_permission<T: Eq + std::hash::Hash>(
+  arg: UnaryPermissionArg,
+  parse: impl Fn(&str) -> Result<T, AnyError>,
+) -> Result<UnaryPermission<T>, AnyError> {
+  Ok(match arg {
+    UnaryPermissionArg::All(allow) => UnaryPermission {
+      global_state: as_permission_state(allow),
+      deny_unlisted: true,
+      ..Default::default()
+    },
+    UnaryPermissionArg::List(list) => UnaryPermission {
+      global_state: PermissionState::Prompt,
+      granted_list: list
+        .iter()
+        .map(|item| parse(item))
+        .collect::<Result<HashSet<T>, AnyError>>()?,
+      deny_unlisted: true,
+      ..Default::default()
+    },
+  })
+}
+
+/// Restrict `permissions` to those of a test, which can't escalate them. What
+/// a test sets is all it gets, as the rest can't be requested either.
+fn create_test_permissions(
+  permissions: &Permissions,
+  args: TestPermissionsArgs,
+) -> Result<Permissions, AnyError> {
+  let resolve_path = |path: &str| resolve_from_cwd(Path::new(path));
+  let parse_host = |host: &str| Ok(host.to_string());
+  let read = match args.read {
+    Some(arg) => as_unary_permission::<PathBuf>(arg, resolve_path)?,
+    None => permissions.read.clone(),
+  };
+  let write = match args.write {
+    Some(arg) => as_unary_permission::<PathBuf>(arg, resolve_path)?,
+    None => permissions.write.clone(),
+  };
+  let net = match args.net {
+    Some(arg) => as_unary_permission::<String>(arg, parse_host)?,
+    None => permissions.net.clone(),
+  };
+  permissions.fork(
+    read,
+    write,
+    net,
+    args.env.map_or(permissions.env, as_permission_state),
+    args.run.map_or(permissions.run, as_permission_state),
+    args.plugin.map_or(permissions.plugin, as_permission_state),
+    args.hrtime.map_or(permissions.hrtime, as_permission_state),
+  )
+}
+
+/// Replace the permissions of the worker by those of the test that is about
+/// to run, until they are restored.
+fn op_pledge_test_permissions(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: TestPermissionsArgs = serde_json::from_value(args)?;
+  if state
+    .borrow::<PledgedPermissions>()
+    .maybe_original
+    .is_some()
+  {
+    return Err(generic_error("Test permissions are already pledged"));
+  }
+  let permissions =
+    create_test_permissions(state.borrow::<Permissions>(), args)?;
+  let original = state.take::<Permissions>();
+  state.put::<Permissions>(permissions);
+  let token = format!("{:032x}", rand::random::<u128>());
+  let pledged = state.borrow_mut::<PledgedPermissions>();
+  pledged.maybe_original = Some((token.clone(), original));
+  Ok(json!({ "token": token }))
+}
+
+#[derive(Deserialize)]
+struct RestoreTestPermissionsArgs {
+  token: String,
+}
+
+/// Restore the permissions of the worker once the test has finished. Without
+/// the token of the pledge, the permissions are left alone.
+fn op_restore_test_permissions(
+  state: &mut OpState,
+  args: Value,
+  _zero_copy: &mut [ZeroCopyBuf],
+) -> Result<Value, AnyError> {
+  let args: RestoreTestPermissionsArgs = serde_json::from_value(args)?;
+  let pledged = state.borrow_mut::<PledgedPermissions>();
+  let is_pledge = matches!(
+    &pledged.maybe_original,
+    Some((token, _)) if *token == args.token
+  );
+  if is_pledge {
+    let (_, original) = pledged.maybe_original.take().unwrap();
+    state.put::<Permissions>(original);
+  }
+  Ok(json!({}))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse_args(args: Value) -> TestPermissionsArgs {
+    serde_json::from_value(args).unwrap()
+  }
+
+  #[test]
+  fn test_create_test_permissions() {
+    let permissions = Permissions::allow_all();
+    let test_permissions = create_test_permissions(
+      &permissions,
+      parse_args(json!({ "read": ["fixtures"], "net": false })),
+    )
+    .unwrap();
+    assert_eq!(
+      test_permissions.query_read(&Some(Path::new("fixtures/a.txt"))),
+      PermissionState::Granted
+    );
+    assert_eq!(
+      test_permissions.query_read(&Some(Path::new("a.txt"))),
+      PermissionState::Denied
+    );
+    assert_eq!(test_permissions.query_read(&None), PermissionState::Denied);
+    assert_eq!(
+      test_permissions.query_net("deno.land", None),
+      PermissionState::Denied
+    );
+    // The permissions that aren't set are inherited.
+    assert_eq!(test_permissions.write, permissions.write);
+    assert_eq!(test_permissions.env, PermissionState::Granted);
+  }
+
+  #[test]
+  fn test_create_test_permissions_escalation() {
+    let permissions = Permissions::default();
+    assert!(create_test_permissions(
+      &permissions,
+      parse_args(json!({ "env": true }))
+    )
+    .is_err());
+    assert!(create_test_permissions(
+      &permissions,
+      parse_args(json!({ "read": ["fixtures"] }))
+    )
+    .is_err());
+    assert!(create_test_permissions(
+      &permissions,
+      parse_args(json!({ "read": false, "run": false }))
+    )
+    .is_ok());
+  }
+}
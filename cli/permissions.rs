@@ -96,20 +96,38 @@ pub struct UnaryPermission<T: Eq + Hash> {
   pub global_state: PermissionState,
   pub granted_list: HashSet<T>,
   pub denied_list: HashSet<T>,
+  /// Whether what isn't granted is denied rather than prompted for, so that
+  /// it can't be requested.
+  #[serde(default)]
+  pub deny_unlisted: bool,
 }
 
 impl<T: Eq + Hash> UnaryPermission<T> {
   /// Check that the permissions represented by `other` don't escalate ours.
   fn check_fork(&self, other: &Self) -> Result<(), AnyError> {
     self.global_state.check_fork(&other.global_state)?;
-    if !self.granted_list.is_superset(&other.granted_list) {
+    if self.global_state != PermissionState::Granted
+      && !self.granted_list.is_superset(&other.granted_list)
+    {
       return Err(permission_escalation_error());
     }
     if !self.denied_list.is_subset(&other.denied_list) {
       return Err(permission_escalation_error());
     }
+    if self.deny_unlisted && !other.deny_unlisted {
+      return Err(permission_escalation_error());
+    }
     Ok(())
   }
+
+  /// The state of what is neither granted nor denied.
+  fn unlisted_state(&self) -> PermissionState {
+    if self.deny_unlisted {
+      PermissionState::Denied
+    } else {
+      PermissionState::Prompt
+    }
+  }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
@@ -212,7 +230,7 @@ impl Permissions {
     {
       return PermissionState::Granted;
     }
-    PermissionState::Prompt
+    self.read.unlisted_state()
   }
 
   pub fn query_write(&self, path: &Option<&Path>) -> PermissionState {
@@ -233,7 +251,7 @@ impl Permissions {
     {
       return PermissionState::Granted;
     }
-    PermissionState::Prompt
+    self.write.unlisted_state()
   }
 
   pub fn query_net(&self, host: &str, port: Option<u16>) -> PermissionState {
@@ -247,7 +265,7 @@ impl Permissions {
     {
       return PermissionState::Granted;
     }
-    PermissionState::Prompt
+    self.net.unlisted_state()
   }
 
   pub fn query_net_url(
@@ -255,6 +273,9 @@ impl Permissions {
     url: &Option<&str>,
   ) -> Result<PermissionState, AnyError> {
     if url.is_none() {
+      if self.net.global_state == PermissionState::Prompt {
+        return Ok(self.net.unlisted_state());
+      }
       return Ok(self.net.global_state);
     }
     let url: &str = url.unwrap();
@@ -1104,6 +1125,36 @@ mod tests {
     };
   }
 
+  #[test]
+  fn test_request_deny_unlisted() {
+    let mut perms = Permissions {
+      read: UnaryPermission {
+        global_state: PermissionState::Prompt,
+        granted_list: resolve_fs_allowlist(&[PathBuf::from("/foo")]),
+        deny_unlisted: true,
+        ..Default::default()
+      },
+      net: UnaryPermission {
+        global_state: PermissionState::Prompt,
+        granted_list: svec!["127.0.0.1:8000"].into_iter().collect(),
+        deny_unlisted: true,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    #[rustfmt::skip]
+    {
+      let _guard = PERMISSION_PROMPT_GUARD.lock().unwrap();
+      set_prompt_result(true);
+      assert_eq!(perms.request_read(&Some(&Path::new("/foo/bar"))), PermissionState::Granted);
+      assert_eq!(perms.request_read(&Some(&Path::new("/bar"))), PermissionState::Denied);
+      assert_eq!(perms.request_read(&None), PermissionState::Denied);
+      assert_eq!(perms.request_net(&Some("http://127.0.0.1:8000")).unwrap(), PermissionState::Granted);
+      assert_eq!(perms.request_net(&Some("http://deno.land")).unwrap(), PermissionState::Denied);
+      assert_eq!(perms.request_net(&None).unwrap(), PermissionState::Denied);
+    };
+  }
+
   #[test]
   fn test_revoke() {
     let mut perms = Permissions {
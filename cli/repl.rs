@@ -1,20 +1,527 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::ast;
+use crate::ast::TranspileOptions;
 use crate::deno_dir::DenoDir;
+use crate::file_fetcher::SourceFile;
+use crate::file_fetcher::TextDocument;
+use crate::global_state::GlobalState;
+use crate::inspector::InspectorSession;
+use crate::media_type::MediaType;
+use crate::worker::MainWorker;
+use deno_core::error::generic_error;
 use deno_core::error::AnyError;
+use deno_core::futures::channel::mpsc;
+use deno_core::futures::StreamExt;
+use deno_core::serde_json;
+use deno_core::serde_json::json;
+use deno_core::serde_json::Value;
+use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
+use regex::Regex;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::Context;
 use rustyline::Editor;
+use rustyline::Helper;
 use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+lazy_static! {
+  /// Matches the string literal of an import specifier being typed.
+  static ref IMPORT_SPECIFIER_RE: Regex =
+    Regex::new(r#"(?:\bimport\s*\(?|\bfrom)\s*["']([^"'\s]*)$"#).unwrap();
+  /// Matches a property being typed, after a chain of identifiers.
+  static ref PROPERTY_RE: Regex = Regex::new(
+    r"(?:^|[^\w$.\])])([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)\.([\w$]*)$"
+  )
+  .unwrap();
+  /// Matches a global name being typed.
+  static ref GLOBAL_RE: Regex =
+    Regex::new(r"(?:^|[^\w$.])([A-Za-z_$][\w$]*)$").unwrap();
+  static ref IDENTIFIER_RE: Regex = Regex::new(r"^[A-Za-z_$][\w$]*$").unwrap();
+}
+
+/// The objects evaluated to complete a line, released once it is completed.
+const COMPLETION_OBJECT_GROUP: &str = "completion";
+
+/// What is being typed at the end of a line.
+#[derive(Debug, PartialEq)]
+enum Completion<'a> {
+  /// A path in the string literal of an import specifier.
+  Path {
+    specifier: &'a str,
+  },
+  /// A property of the object `object` evaluates to.
+  Property {
+    object: &'a str,
+    prefix: &'a str,
+  },
+  Global {
+    prefix: &'a str,
+  },
+}
+
+impl<'a> Completion<'a> {
+  fn parse(line: &'a str) -> Option<Self> {
+    if let Some(captures) = IMPORT_SPECIFIER_RE.captures(line) {
+      let specifier = captures.get(1).unwrap().as_str();
+      return Some(Completion::Path { specifier });
+    }
+    if let Some(captures) = PROPERTY_RE.captures(line) {
+      let object = captures.get(1).unwrap().as_str();
+      let prefix = captures.get(2).unwrap().as_str();
+      return Some(Completion::Property { object, prefix });
+    }
+    if let Some(captures) = GLOBAL_RE.captures(line) {
+      let prefix = captures.get(1).unwrap().as_str();
+      return Some(Completion::Global { prefix });
+    }
+    None
+  }
+}
+
+/// The names of the isolate a completion is looked up in.
+#[derive(Debug)]
+enum NamesQuery {
+  Globals,
+  Properties(String),
+}
+
+/// Complete the names typed in the editor with the names of the live isolate,
+/// and the import specifiers with local paths. It runs on the thread reading
+/// lines, so it queries the isolate through the channels answered by `run()`.
+pub struct EditorHelper {
+  query_tx: mpsc::UnboundedSender<NamesQuery>,
+  names_rx: Receiver<Vec<String>>,
+}
+
+/// The end of the channels of an `EditorHelper` on the thread of the isolate.
+pub struct EditorHelperChannels {
+  query_rx: mpsc::UnboundedReceiver<NamesQuery>,
+  names_tx: Sender<Vec<String>>,
+}
+
+impl EditorHelper {
+  pub fn new() -> (Self, EditorHelperChannels) {
+    let (query_tx, query_rx) = mpsc::unbounded();
+    let (names_tx, names_rx) = channel();
+    let helper = Self { query_tx, names_rx };
+    let channels = EditorHelperChannels { query_rx, names_tx };
+    (helper, channels)
+  }
+
+  fn query_names(&self, query: NamesQuery) -> Vec<String> {
+    if self.query_tx.unbounded_send(query).is_err() {
+      return vec![];
+    }
+    self.names_rx.recv().unwrap_or_default()
+  }
+}
+
+/// The names among `names` that start with `prefix` and can follow a dot.
+fn matching_names(names: Vec<String>, prefix: &str) -> Vec<String> {
+  let mut names: Vec<String> = names
+    .into_iter()
+    .filter(|name| name.starts_with(prefix) && IDENTIFIER_RE.is_match(name))
+    .collect();
+  names.sort();
+  names.dedup();
+  names
+}
+
+/// Complete the last component of the local path `specifier`, relative to
+/// `cwd`. Returns the length of that component with the completions.
+fn complete_path(specifier: &str, cwd: &Path) -> (usize, Vec<String>) {
+  let is_local = specifier.starts_with("./")
+    || specifier.starts_with("../")
+    || specifier.starts_with('/');
+  let (dir, prefix) = match specifier.rfind('/') {
+    Some(index) if is_local => specifier.split_at(index + 1),
+    _ => return (0, vec![]),
+  };
+  let entries = match fs::read_dir(cwd.join(dir)) {
+    Ok(entries) => entries,
+    Err(_) => return (prefix.len(), vec![]),
+  };
+
+  let mut paths: Vec<String> = entries
+    .filter_map(|entry| {
+      let entry = entry.ok()?;
+      let name = entry.file_name().into_string().ok()?;
+      // Hidden entries are only completed once their dot is typed.
+      if !name.starts_with(prefix)
+        || (name.starts_with('.') && prefix.is_empty())
+      {
+        return None;
+      }
+      if entry.file_type().ok()?.is_dir() {
+        Some(format!("{}/", name))
+      } else {
+        Some(name)
+      }
+    })
+    .collect();
+  paths.sort();
+  (prefix.len(), paths)
+}
+
+impl Completer for EditorHelper {
+  type Candidate = String;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    let (len, candidates) = match Completion::parse(&line[..pos]) {
+      Some(Completion::Path { specifier }) => {
+        let cwd = std::env::current_dir()?;
+        complete_path(specifier, &cwd)
+      }
+      Some(Completion::Property { object, prefix }) => {
+        let names =
+          self.query_names(NamesQuery::Properties(object.to_string()));
+        (prefix.len(), matching_names(names, prefix))
+      }
+      // Listing every global for an empty line isn't helpful.
+      Some(Completion::Global { prefix }) if !prefix.is_empty() => {
+        let names = self.query_names(NamesQuery::Globals);
+        (prefix.len(), matching_names(names, prefix))
+      }
+      _ => (0, vec![]),
+    };
+    Ok((pos - len, candidates))
+  }
+}
+
+impl Hinter for EditorHelper {}
+
+impl Highlighter for EditorHelper {}
+
+impl Validator for EditorHelper {}
+
+impl Helper for EditorHelper {}
+
+/// An inspector session looking up the names of the global context of the
+/// isolate, to complete the lines of the REPL.
+struct ReplSession {
+  session: Box<InspectorSession>,
+  context_id: u64,
+}
+
+impl ReplSession {
+  async fn new(worker: &mut MainWorker) -> Result<Self, AnyError> {
+    let inspector = worker
+      .inspector
+      .as_mut()
+      .expect("Inspector is not created.");
+    let mut session = InspectorSession::new(&mut **inspector);
+    session.subscribe("Runtime.executionContextCreated");
+
+    // The inspector has no default context to evaluate expressions in, but
+    // enabling the runtime domain reports the contexts that exist.
+    session
+      .post_message("Runtime.enable".to_string(), None)
+      .await?;
+    let context_id = session
+      .notifications()
+      .iter()
+      .find_map(|n| n["params"]["context"]["id"].as_u64())
+      .ok_or_else(|| generic_error("No execution context found"))?;
+    // Otherwise every log of the REPL would be sent to the session.
+    session
+      .post_message("Runtime.disable".to_string(), None)
+      .await?;
+
+    Ok(Self {
+      session,
+      context_id,
+    })
+  }
+
+  async fn names(
+    &mut self,
+    query: &NamesQuery,
+  ) -> Result<Vec<String>, AnyError> {
+    let names = match query {
+      NamesQuery::Globals => {
+        let mut names = self.global_lexical_scope_names().await?;
+        names.extend(self.property_names("globalThis").await?);
+        names
+      }
+      NamesQuery::Properties(object) => self.property_names(object).await?,
+    };
+    self
+      .session
+      .post_message(
+        "Runtime.releaseObjectGroup".to_string(),
+        Some(json!({ "objectGroup": COMPLETION_OBJECT_GROUP })),
+      )
+      .await?;
+    Ok(names)
+  }
+
+  /// The names declared with `let`, `const` and `class` in the REPL.
+  async fn global_lexical_scope_names(
+    &mut self,
+  ) -> Result<Vec<String>, AnyError> {
+    let result = self
+      .session
+      .post_message(
+        "Runtime.globalLexicalScopeNames".to_string(),
+        Some(json!({ "executionContextId": self.context_id })),
+      )
+      .await?;
+    Ok(
+      result["names"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str().map(String::from))
+        .collect(),
+    )
+  }
+
+  /// Evaluate `expression` without side effects, so that getters and calls
+  /// typed before the property aren't run.
+  async fn evaluate(&mut self, expression: &str) -> Result<Value, AnyError> {
+    let result = self
+      .session
+      .post_message(
+        "Runtime.evaluate".to_string(),
+        Some(json!({
+          "expression": expression,
+          "contextId": self.context_id,
+          "objectGroup": COMPLETION_OBJECT_GROUP,
+          "silent": true,
+          "throwOnSideEffect": true,
+          "timeout": 200,
+        })),
+      )
+      .await?;
+    if result.get("exceptionDetails").is_some() {
+      return Ok(Value::Null);
+    }
+    Ok(result["result"].clone())
+  }
+
+  /// The names of the properties of the object `expression` evaluates to,
+  /// and of its prototype chain.
+  async fn property_names(
+    &mut self,
+    expression: &str,
+  ) -> Result<Vec<String>, AnyError> {
+    let mut object = self.evaluate(expression).await?;
+    // Primitives have the properties of the prototype of their wrapper.
+    let wrapper = match object["type"].as_str() {
+      Some("string") => Some("String"),
+      Some("number") => Some("Number"),
+      Some("boolean") => Some("Boolean"),
+      Some("bigint") => Some("BigInt"),
+      Some("symbol") => Some("Symbol"),
+      _ => None,
+    };
+    if let Some(wrapper) = wrapper {
+      object = self.evaluate(&format!("{}.prototype", wrapper)).await?;
+    }
+    let object_id = match object["objectId"].as_str() {
+      Some(object_id) => object_id.to_string(),
+      None => return Ok(vec![]),
+    };
+
+    let result = self
+      .session
+      .post_message(
+        "Runtime.getProperties".to_string(),
+        Some(json!({
+          "objectId": object_id,
+          "ownProperties": false,
+        })),
+      )
+      .await?;
+    Ok(
+      result["result"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|property| property.get("symbol").is_none())
+        .filter_map(|property| property["name"].as_str().map(String::from))
+        .collect(),
+    )
+  }
+}
+
+/// Run the REPL of `worker`, answering the completion queries of its editor
+/// between the polls of the worker.
+pub async fn run(worker: &mut MainWorker) -> Result<(), AnyError> {
+  let EditorHelperChannels {
+    mut query_rx,
+    names_tx,
+  } = worker
+    .op_state()
+    .borrow_mut()
+    .take::<EditorHelperChannels>();
+  let mut session = ReplSession::new(worker).await?;
+
+  loop {
+    tokio::select! {
+      result = &mut **worker => result?,
+      Some(query) = query_rx.next() => {
+        let names = session.names(&query).await.unwrap_or_else(|e| {
+          debug!("Unable to complete {:?}: {}", query, e);
+          vec![]
+        });
+        // The editor may have been closed while the names were looked up.
+        let _ = names_tx.send(names);
+      }
+    }
+  }
+}
+
+/// An entry of the REPL, transpiled from TypeScript to a script.
+#[derive(Debug, PartialEq)]
+pub enum TranspiledEntry {
+  Script {
+    code: String,
+    /// The path the script is evaluated as, and its errors refer to.
+    filename: PathBuf,
+  },
+  /// The entry is continued by the next line.
+  Incomplete,
+}
+
+/// Make the script emitted for an entry strict, like the code typed in the
+/// REPL always is. The directive is on a line of its own, so the lines of
+/// the source map are shifted.
+fn prepend_use_strict(
+  code: &str,
+  source_map: &[u8],
+) -> Result<(String, Vec<u8>), AnyError> {
+  let mut source_map: Value = serde_json::from_slice(source_map)?;
+  let mappings = source_map["mappings"].as_str().unwrap_or("");
+  source_map["mappings"] = json!(format!(";{}", mappings));
+  let code = format!("\"use strict\";\n{}", code);
+  Ok((code, serde_json::to_vec(&source_map)?))
+}
+
+/// Transpile the `index`th entry of the REPL. Types are stripped, so the
+/// types declared by an entry can be used by the following ones, and the
+/// enums and namespaces they declare are merged like in a module.
+///
+/// The source of the entry and its source map are kept for its errors to
+/// refer to the lines typed.
+fn transpile_entry(
+  global_state: &GlobalState,
+  source: &str,
+  index: usize,
+) -> Result<TranspiledEntry, AnyError> {
+  let media_type = MediaType::TypeScript;
+  let filename =
+    std::env::current_dir()?.join(format!("$deno$repl_{}.ts", index));
+  let url = Url::from_file_path(&filename).unwrap();
+  let specifier = ModuleSpecifier::from(url.clone());
+
+  let parsed_module = match ast::parse(&specifier, source, &media_type) {
+    Ok(parsed_module) => parsed_module,
+    Err(_) if ast::is_incomplete(source, &media_type) => {
+      return Ok(TranspiledEntry::Incomplete)
+    }
+    Err(err) => return Err(err),
+  };
+  // An entry is evaluated as a script, which can't import the helpers.
+  let options = TranspileOptions {
+    inline_source_map: false,
+    inject_helpers: true,
+    ..TranspileOptions::default()
+  };
+  let (code, maybe_source_map) = parsed_module.transpile(&options)?;
+  let source_map = maybe_source_map.expect("source map is not emitted");
+  let (code, source_map) =
+    prepend_use_strict(&code.to_string()?, source_map.as_bytes())?;
+
+  global_state
+    .ts_compiler
+    .save_emitted_source_map(url.clone(), source_map);
+  let source_file = SourceFile {
+    url,
+    filename: filename.clone(),
+    types_header: None,
+    media_type,
+    source_code: TextDocument::new(source.as_bytes().to_vec(), Some("utf-8")),
+  };
+  global_state
+    .file_fetcher
+    .save_source_file_in_cache(&specifier, source_file);
+
+  Ok(TranspiledEntry::Script { code, filename })
+}
+
+/// The entries transpiled during a session of the REPL. Their sources and
+/// source maps are kept until the session ends, as the code of any of them
+/// may still throw.
+pub struct ReplEntries {
+  global_state: Arc<GlobalState>,
+  specifiers: Vec<ModuleSpecifier>,
+}
+
+impl ReplEntries {
+  pub fn new(global_state: Arc<GlobalState>) -> Self {
+    Self {
+      global_state,
+      specifiers: Vec::new(),
+    }
+  }
+
+  /// Transpile the `index`th entry of the session, with `transpile_entry()`.
+  pub fn transpile(
+    &mut self,
+    source: &str,
+    index: usize,
+  ) -> Result<TranspiledEntry, AnyError> {
+    let entry = transpile_entry(&self.global_state, source, index)?;
+    if let TranspiledEntry::Script { filename, .. } = &entry {
+      let url = Url::from_file_path(filename).unwrap();
+      self.specifiers.push(ModuleSpecifier::from(url));
+    }
+    Ok(entry)
+  }
+}
+
+impl Drop for ReplEntries {
+  fn drop(&mut self) {
+    for specifier in &self.specifiers {
+      self
+        .global_state
+        .ts_compiler
+        .remove_emitted_source_map(specifier.as_url());
+      self
+        .global_state
+        .file_fetcher
+        .remove_source_file_from_cache(specifier);
+    }
+  }
+}
 
 pub struct Repl {
-  editor: Editor<()>,
+  editor: Editor<EditorHelper>,
   history_file: PathBuf,
 }
 
 impl Repl {
-  pub fn new(history_file: PathBuf) -> Self {
+  pub fn new(history_file: PathBuf, helper: EditorHelper) -> Self {
+    let mut editor = Editor::<EditorHelper>::new();
+    editor.set_helper(Some(helper));
     let mut repl = Self {
-      editor: Editor::<()>::new(),
+      editor,
       history_file,
     };
 
@@ -71,3 +578,113 @@ pub fn history_path(dir: &DenoDir, history_file: &str) -> PathBuf {
   p.push(history_file);
   p
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::permissions::Permissions;
+  use crate::source_maps::SourceMapGetter;
+
+  #[test]
+  fn test_parse_completion() {
+    assert_eq!(
+      Completion::parse("import { foo } from \"./sub"),
+      Some(Completion::Path { specifier: "./sub" })
+    );
+    assert_eq!(
+      Completion::parse("await import('../"),
+      Some(Completion::Path { specifier: "../" })
+    );
+    assert_eq!(
+      Completion::parse("import \""),
+      Some(Completion::Path { specifier: "" })
+    );
+    assert_eq!(
+      Completion::parse("Deno.re"),
+      Some(Completion::Property {
+        object: "Deno",
+        prefix: "re"
+      })
+    );
+    assert_eq!(
+      Completion::parse("const x = Deno.build."),
+      Some(Completion::Property {
+        object: "Deno.build",
+        prefix: ""
+      })
+    );
+    assert_eq!(
+      Completion::parse("console.log(fo"),
+      Some(Completion::Global { prefix: "fo" })
+    );
+    assert_eq!(Completion::parse("foo()."), None);
+    assert_eq!(Completion::parse("1."), None);
+    assert_eq!(Completion::parse("x = 1 + "), None);
+  }
+
+  #[test]
+  fn test_matching_names() {
+    let names = vec![
+      "readFile".to_string(),
+      "read".to_string(),
+      "readFile".to_string(),
+      "run".to_string(),
+      "0".to_string(),
+      "re-export".to_string(),
+    ];
+    assert_eq!(matching_names(names, "re"), vec!["read", "readFile"]);
+  }
+
+  #[test]
+  fn test_complete_path() {
+    let tests_path = test_util::root_path().join("cli/tests");
+    let (len, paths) = complete_path("./subdir/mod", &tests_path);
+    assert_eq!(len, 3);
+    assert!(paths.contains(&"mod1.ts".to_string()));
+    assert!(paths.contains(&"mod3.js".to_string()));
+    assert!(paths.iter().all(|path| path.starts_with("mod")));
+
+    let (len, paths) = complete_path("./sub", &tests_path);
+    assert_eq!(len, 3);
+    assert!(paths.contains(&"subdir/".to_string()));
+
+    let (_, paths) = complete_path("https://deno.land/", &tests_path);
+    assert!(paths.is_empty());
+  }
+
+  #[test]
+  fn test_prepend_use_strict() {
+    let source_map = br#"{"version":3,"sources":["a.ts"],"mappings":"AAAA"}"#;
+    let (code, source_map) =
+      prepend_use_strict("const a = 1;\n", source_map).unwrap();
+    assert_eq!(code, "\"use strict\";\nconst a = 1;\n");
+    let source_map: Value = serde_json::from_slice(&source_map).unwrap();
+    assert_eq!(source_map["mappings"], ";AAAA");
+  }
+
+  #[test]
+  fn test_repl_entries() {
+    let global_state = GlobalState::mock(vec![], None);
+    let mut entries = ReplEntries::new(global_state.clone());
+    let filename = match entries.transpile("const n: number = 1;", 0).unwrap() {
+      TranspiledEntry::Script { filename, .. } => filename,
+      TranspiledEntry::Incomplete => unreachable!(),
+    };
+    let specifier =
+      ModuleSpecifier::from(Url::from_file_path(&filename).unwrap());
+    let is_kept = || {
+      let source_map = global_state
+        .ts_compiler
+        .get_source_map(&specifier.to_string());
+      let source_file = global_state
+        .file_fetcher
+        .fetch_cached_source_file(&specifier, Permissions::allow_all());
+      (source_map.is_some(), source_file.is_some())
+    };
+    assert_eq!(is_kept(), (true, true));
+
+    // The entries of a session are forgotten once it ends.
+    drop(entries);
+    assert_eq!(is_kept(), (false, false));
+  }
+}
@@ -86,6 +86,10 @@
     } else {
       // Async result.
       promiseTableMin[promiseId] = promise;
+      if (core.isOpCallTracingEnabled()) {
+        const opCall = core.opCallStarted(opName);
+        promise.then(() => core.opCallCompleted(opCall));
+      }
     }
 
     const res = await promise;
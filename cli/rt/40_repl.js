@@ -14,6 +14,10 @@
     return core.jsonOpAsync("op_repl_readline", { rid, prompt });
   }
 
+  function opTranspile(rid, source, index) {
+    return core.jsonOpSync("op_repl_transpile", { rid, source, index });
+  }
+
   function replLog(...args) {
     core.print(inspectArgs(args) + "\n");
   }
@@ -22,24 +26,6 @@
     core.print(inspectArgs(args) + "\n", true);
   }
 
-  // Error messages that allow users to continue input
-  // instead of throwing an error to REPL
-  // ref: https://github.com/v8/v8/blob/master/src/message-template.h
-  // TODO(kevinkassimo): this list might not be comprehensive
-  const recoverableErrorMessages = [
-    "Unexpected end of input", // { or [ or (
-    "Missing initializer in const declaration", // const a
-    "Missing catch or finally after try", // try {}
-    "missing ) after argument list", // console.log(1
-    "Unterminated template literal", // `template
-    // TODO(kevinkassimo): need a parser to handling errors such as:
-    // "Missing } in template expression" // `${ or `${ a 123 }`
-  ];
-
-  function isRecoverableError(e) {
-    return recoverableErrorMessages.includes(e.message);
-  }
-
   // Returns `true` if `close()` is called in REPL.
   // We should quit the REPL when this function returns `true`.
   function isCloseCalled() {
@@ -48,11 +34,13 @@
 
   let lastEvalResult = undefined;
   let lastThrownError = undefined;
+  // The index of the next entry, which names the script it is evaluated as.
+  let entryIndex = 0;
 
   // Evaluate code.
   // Returns true if code is consumed (no error/irrecoverable error).
-  // Returns false if error is recoverable
-  function evaluate(code, preprocess = true) {
+  // Returns false if code is incomplete
+  function evaluate(rid, code, preprocess = true) {
     const rawCode = code;
     if (preprocess) {
       // It is a bit unexpected that { "foo": "bar" } is interpreted as a block
@@ -63,9 +51,27 @@
       }
     }
 
-    // each evalContext is a separate function body, and we want strict mode to
-    // work, so we should ensure that the code starts with "use strict"
-    const [result, errInfo] = core.evalContext(`"use strict";\n\n${code}`);
+    // Entries are TypeScript, transpiled to scripts which start with
+    // "use strict", as each evalContext is a separate function body and we
+    // want strict mode to work.
+    let entry;
+    try {
+      entry = opTranspile(rid, code, entryIndex);
+    } catch {
+      if (code.length != rawCode.length) {
+        return evaluate(rid, rawCode, false);
+      }
+      // Let V8 report the syntax error, like it does for a script.
+      entry = { code: `"use strict";\n\n${code}` };
+    }
+    if (entry.incomplete) {
+      return false; // don't consume code.
+    }
+    if (entry.filename) {
+      entryIndex++;
+    }
+
+    const [result, errInfo] = core.evalContext(entry.code, entry.filename);
 
     if (!errInfo) {
       // when a function is eval'ed with just "use strict" sometimes the result
@@ -77,10 +83,7 @@
         replLog("%o", lastEvalResult);
       }
     } else if (errInfo.isCompileError && code.length != rawCode.length) {
-      return evaluate(rawCode, false);
-    } else if (errInfo.isCompileError && isRecoverableError(errInfo.thrown)) {
-      // Recoverable compiler error
-      return false; // don't consume code.
+      return evaluate(rid, rawCode, false);
     } else {
       lastThrownError = errInfo.thrown;
       if (errInfo.isNativeError) {
@@ -168,7 +171,7 @@
         }
       }
       // Start continued read
-      while (!evaluate(code)) {
+      while (!evaluate(rid, code)) {
         code += "\n";
         try {
           code += await opReadline(rid, "  ");
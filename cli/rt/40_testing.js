@@ -4,11 +4,11 @@
   const core = window.Deno.core;
   const { gray, green, italic, red, yellow } = window.__bootstrap.colors;
   const { exit } = window.__bootstrap.os;
-  const { Console, inspectArgs } = window.__bootstrap.console;
+  const { Console, inspect, inspectArgs } = window.__bootstrap.console;
   const { stdout } = window.__bootstrap.files;
   const { exposeForTest } = window.__bootstrap.internals;
   const { metrics } = window.__bootstrap.metrics;
-  const { assert } = window.__bootstrap.util;
+  const { assert, AssertionError } = window.__bootstrap.util;
 
   const disabledConsole = new Console(() => {});
 
@@ -23,15 +23,33 @@
     return gray(italic(timeStr));
   }
 
+  // Where an op was dispatched, indented under the line describing it, when
+  // op call tracing is enabled.
+  function formatOpCallStack(opCall) {
+    if (opCall?.stack == null) {
+      return "";
+    }
+    const stack = opCall.stack.split("\n").map((line) => `      ${line}`);
+    return `, dispatched by ${opCall.opName}:\n${stack.join("\n")}`;
+  }
+
+  function formatPendingOp(opCall) {
+    return `  - ${opCall.opName}${formatOpCallStack(opCall)}`;
+  }
+
+  const TRACE_OPS_HINT =
+    "Run again with --trace-ops to show where they were dispatched.";
+
   // Wrap test function in additional assertion that makes sure
   // the test case does not leak async "ops" - ie. number of async
   // completed ops after the test is the same as number of dispatched
   // ops. Note that "unref" ops are ignored since in nature that are
   // optional.
   function assertOps(fn) {
-    return async function asyncOpSanitizer() {
+    return async function asyncOpSanitizer(...args) {
       const pre = metrics();
-      await fn();
+      const prePendingOps = new Set(core.pendingOps());
+      await fn(...args);
       // Defer until next event loop turn - that way timeouts and intervals
       // cleared can actually be removed from resource table, otherwise
       // false positives may occur (https://github.com/denoland/deno/issues/4591)
@@ -41,8 +59,22 @@
       // that will be a pending async op before test starts.
       const dispatchedDiff = post.opsDispatchedAsync - pre.opsDispatchedAsync;
       const completedDiff = post.opsCompletedAsync - pre.opsCompletedAsync;
+      if (dispatchedDiff === completedDiff) {
+        return;
+      }
+      const pendingOps = core.pendingOps()
+        .filter((opCall) => !prePendingOps.has(opCall))
+        .map(formatPendingOp);
+      let pending = "";
+      if (!core.isOpCallTracingEnabled()) {
+        // Pending async ops are only known when op calls are traced.
+        pending = `\n${TRACE_OPS_HINT}\n`;
+      } else if (pendingOps.length > 0) {
+        pending = `\nAsync ops started by the test that haven't completed:
+${pendingOps.join("\n")}\n`;
+      }
       assert(
-        dispatchedDiff === completedDiff,
+        false,
         `Test case is leaking async ops.
 Before:
   - dispatched: ${pre.opsDispatchedAsync}
@@ -50,36 +82,404 @@ Before:
 After:
   - dispatched: ${post.opsDispatchedAsync}
   - completed: ${post.opsCompletedAsync}
-
+${pending}
 Make sure to await all promises returned from Deno APIs before
 finishing test case.`,
       );
     };
   }
 
+  // Describe the resources opened by a test that are still open, with the op
+  // that opened each of them if it was traced.
+  function describeOpenedResources(preResources, postResources) {
+    return Object.entries(postResources)
+      .filter(([rid]) => !(rid in preResources))
+      .map(([rid, name]) =>
+        `  - ${name} (rid ${rid})` +
+        formatOpCallStack(core.resourceOpCall(Number(rid)))
+      );
+  }
+
   // Wrap test function in additional assertion that makes sure
   // the test case does not "leak" resources - ie. resource table after
   // the test has exactly the same contents as before the test.
   function assertResources(
     fn,
   ) {
-    return async function resourceSanitizer() {
+    return async function resourceSanitizer(...args) {
       const pre = core.resources();
-      await fn();
+      await fn(...args);
       const post = core.resources();
 
       const preStr = JSON.stringify(pre, null, 2);
       const postStr = JSON.stringify(post, null, 2);
+      if (preStr === postStr) {
+        return;
+      }
+      let details = "";
+      const opened = describeOpenedResources(pre, post);
+      if (opened.length > 0) {
+        details += `\nResources opened by the test and still open:
+${opened.join("\n")}\n`;
+        if (!core.isOpCallTracingEnabled()) {
+          details += `${TRACE_OPS_HINT}\n`;
+        }
+      }
+      const closed = Object.entries(pre)
+        .filter(([rid]) => !(rid in post))
+        .map(([rid, name]) => `  - ${name} (rid ${rid})`);
+      if (closed.length > 0) {
+        details += `\nResources opened before the test and closed by it:
+${closed.join("\n")}\n`;
+      }
       const msg = `Test case is leaking resources.
-Before: ${preStr}
-After: ${postStr}
-
+${details}
 Make sure to close all open resource handles returned from Deno APIs before
 finishing test case.`;
-      assert(preStr === postStr, msg);
+      assert(false, msg);
+    };
+  }
+
+  // Run the test function with the permissions of the test definition, which
+  // restrict those of the worker until the function has finished. The token
+  // of the pledge never leaves this function, so that the test can't restore
+  // the permissions of the worker itself.
+  function withPermissions(fn, permissions) {
+    return async function applyPermissions(...args) {
+      const { token } = core.jsonOpSync(
+        "op_pledge_test_permissions",
+        permissions,
+      );
+      try {
+        await fn(...args);
+      } finally {
+        core.jsonOpSync("op_restore_test_permissions", { token });
+      }
+    };
+  }
+
+  class TestTimeoutError extends Error {
+    constructor(message) {
+      super(message);
+      this.name = "TestTimeoutError";
+    }
+  }
+
+  // Describe what a test that timed out is waiting on: the async ops it
+  // dispatched that haven't completed and the resources it left open.
+  // `notRun` is the number of tests that won't run because of the timeout.
+  function describeTimeout(
+    timeout,
+    preMetrics,
+    preResources,
+    prePendingOps,
+    notRun,
+  ) {
+    const postMetrics = metrics();
+    const postResources = core.resources();
+    const dispatched = postMetrics.opsDispatchedAsync -
+      preMetrics.opsDispatchedAsync;
+    const completed = postMetrics.opsCompletedAsync -
+      preMetrics.opsCompletedAsync;
+    let ops = "";
+    if (dispatched > completed) {
+      const pendingOps = core.isOpCallTracingEnabled()
+        ? core.pendingOps()
+          .filter((opCall) => !prePendingOps.has(opCall))
+          .map(formatPendingOp)
+        : [TRACE_OPS_HINT];
+      ops = pendingOps.map((line) => `\n${line}`).join("");
+    }
+    const opened = describeOpenedResources(preResources, postResources);
+    const resources = opened.length > 0 ? `\n${opened.join("\n")}` : " none";
+    const skipped = notRun > 0
+      ? `\n\nThe test may still be running, so the remaining ${notRun} ` +
+        `test(s) were not run.`
+      : "";
+    return `Test timed out after ${timeout}ms.
+
+Pending async ops started by the test: ${dispatched - completed}${ops}
+Resources opened by the test and still open:${resources}${skipped}`;
+  }
+
+  let nextTimeoutId = 0;
+
+  // Run the function of a test or step, failing it if it left some of its
+  // steps running or if any of them failed.
+  async function runWithContext(fn, context) {
+    let stepsError;
+    try {
+      await fn(context);
+    } finally {
+      stepsError = context.finish();
+    }
+    if (stepsError != null) {
+      throw stepsError;
+    }
+  }
+
+  // Run the test, failing it if it hasn't finished after `timeout`
+  // milliseconds. The timeout is awaited before returning, so that it doesn't
+  // count as an async op leaked by the test that runs next. `notRun` is the
+  // number of tests that are skipped if this one times out.
+  async function runTest(test, timeout, context, notRun) {
+    if (timeout == null) {
+      await runWithContext(test.fn, context);
+      return;
+    }
+    const preMetrics = metrics();
+    const preResources = core.resources();
+    const prePendingOps = new Set(core.pendingOps());
+    const id = nextTimeoutId++;
+    core.jsonOpSync("op_test_timeout_start", {
+      id,
+      timeout: Math.ceil(timeout),
+    });
+    const timeoutPromise = core.jsonOpAsync("op_test_timeout", { id });
+    try {
+      await Promise.race([
+        runWithContext(test.fn, context),
+        timeoutPromise.then(({ expired }) => {
+          if (expired) {
+            throw new TestTimeoutError(
+              describeTimeout(
+                timeout,
+                preMetrics,
+                preResources,
+                prePendingOps,
+                notRun,
+              ),
+            );
+          }
+        }),
+      ]);
+    } finally {
+      context.finish();
+      core.jsonOpSync("op_test_timeout_cancel", { id });
+      await timeoutPromise;
+    }
+  }
+
+  // Options of `Deno.inspect()` used to serialize snapshots, which must not
+  // depend on the size of the terminal or abbreviate values.
+  const SNAPSHOT_INSPECT_OPTIONS = {
+    depth: Infinity,
+    sorted: true,
+    trailingComma: true,
+    compact: false,
+    iterableLimit: Infinity,
+    colors: false,
+  };
+
+  // Passed as the argument of the function of a test or step. The name of a
+  // step is prefixed with the names of the test and steps running it.
+  class TestContext {
+    #snapshotCount = 0;
+    #updateSnapshots;
+    #reportStep;
+    #level;
+    #steps = [];
+    #runningStep = null;
+    #finished = false;
+
+    constructor(name, origin, { updateSnapshots, reportStep, level = 0 }) {
+      this.name = name;
+      this.origin = origin;
+      this.#updateSnapshots = updateSnapshots;
+      this.#reportStep = reportStep;
+      this.#level = level;
+    }
+
+    // Results of the steps that have run, with the results of their own
+    // steps.
+    get steps() {
+      return this.#steps;
+    }
+
+    // Run a step of the test, with its own status and sanitizers. Resolves to
+    // whether the step passed.
+    async step(nameOrDef, fn) {
+      let stepDef;
+      const defaults = {
+        ignore: false,
+        sanitizeOps: true,
+        sanitizeResources: true,
+      };
+      if (typeof nameOrDef === "string") {
+        if (typeof fn !== "function") {
+          throw new TypeError("Missing test step function");
+        }
+        stepDef = { fn, name: nameOrDef, ...defaults };
+      } else {
+        if (typeof nameOrDef?.fn !== "function") {
+          throw new TypeError("Missing test step function");
+        }
+        stepDef = { ...defaults, ...nameOrDef };
+      }
+      if (!stepDef.name) {
+        throw new TypeError("The test step name can't be empty");
+      }
+      if (this.#finished) {
+        throw new Error(
+          `Cannot run test step "${stepDef.name}" because "${this.name}" ` +
+            "has already finished. Make sure to await t.step().",
+        );
+      }
+      if (this.#runningStep != null) {
+        throw new Error(
+          `Cannot run test step "${stepDef.name}" while ` +
+            `"${this.#runningStep}" is running. Make sure to await t.step() ` +
+            "before running the next step.",
+        );
+      }
+
+      const level = this.#level + 1;
+      const fullName = `${this.name} > ${stepDef.name}`;
+      const result = { name: stepDef.name, fullName, level, duration: 0 };
+      this.#runningStep = stepDef.name;
+      this.#steps.push(result);
+      await this.#reportStep({ stepStart: { name: stepDef.name, level } });
+
+      if (stepDef.ignore) {
+        result.status = "ignored";
+      } else {
+        let stepFn = stepDef.fn;
+        if (stepDef.sanitizeOps) {
+          stepFn = assertOps(stepFn);
+        }
+        if (stepDef.sanitizeResources) {
+          stepFn = assertResources(stepFn);
+        }
+        const context = new TestContext(fullName, this.origin, {
+          updateSnapshots: this.#updateSnapshots,
+          reportStep: this.#reportStep,
+          level,
+        });
+        const start = +new Date();
+        try {
+          await runWithContext(stepFn, context);
+          result.status = "passed";
+        } catch (err) {
+          result.status = "failed";
+          result.error = err;
+        }
+        result.duration = +new Date() - start;
+        result.steps = context.steps;
+      }
+
+      this.#runningStep = null;
+      await this.#reportStep({ stepEnd: result });
+      return result.status == "passed";
+    }
+
+    // Called once the function of the test or step has returned. Returns an
+    // error if some of its steps are still running or failed.
+    finish() {
+      if (this.#finished) {
+        return;
+      }
+      this.#finished = true;
+      if (this.#runningStep != null) {
+        return new Error(
+          `Test step "${this.#runningStep}" was still running when ` +
+            `"${this.name}" finished. Make sure to await t.step().`,
+        );
+      }
+      const failed = this.#steps.filter(({ status }) => status == "failed");
+      if (failed.length > 0) {
+        return new Error(`${failed.length} test step(s) failed.`);
+      }
+    }
+
+    // Compare the serialization of `actual` to the snapshot stored in the
+    // `__snapshots__` directory next to the test module, or store it when
+    // snapshots are updated.
+    assertSnapshot(actual, message) {
+      if (this.origin == null) {
+        throw new TypeError(
+          "Snapshots are only supported in tests registered by a module",
+        );
+      }
+      const result = core.jsonOpSync("op_assert_snapshot", {
+        origin: this.origin,
+        key: `${this.name} ${++this.#snapshotCount}`,
+        value: inspect(actual, SNAPSHOT_INSPECT_OPTIONS),
+        update: this.#updateSnapshots,
+      });
+      if (!result.ok) {
+        throw new AssertionError(
+          message ? `${message}\n\n${result.message}` : result.message,
+        );
+      }
+    }
+  }
+
+  // Find the module registering a test, the first file of the stack trace
+  // that isn't internal.
+  function getTestOrigin() {
+    const error = new Error();
+    // Accessing the stack trace evaluates the source mapped call sites.
+    error.stack;
+    const callSite = (error.__callSiteEvals ?? []).find(({ fileName }) =>
+      fileName != null && !fileName.startsWith("deno:")
+    );
+    return callSite?.fileName;
+  }
+
+  // The test modules that have run and the tests of each that haven't
+  // passed, whose snapshots are neither checked for being obsolete nor
+  // removed.
+  function getSnapshotModules(tests, passed) {
+    const modules = new Map();
+    for (const test of tests) {
+      if (test.origin == null) {
+        continue;
+      }
+      if (!modules.has(test.origin)) {
+        modules.set(test.origin, []);
+      }
+      if (!passed.has(test)) {
+        modules.get(test.origin).push(test.name);
+      }
+    }
+    return [...modules].map(([origin, skipped]) => ({ origin, skipped }));
+  }
+
+  // Mulberry32, a seedable PRNG returning numbers in [0, 1), so that the order
+  // of shuffled tests doesn't depend on `Math.random()`.
+  function createRandom(seed) {
+    let state = seed >>> 0;
+    return () => {
+      state = (state + 0x6d2b79f5) >>> 0;
+      let t = state;
+      t = Math.imul(t ^ (t >>> 15), t | 1);
+      t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+      return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
     };
   }
 
+  // Shuffle the tests of every module, keeping the modules in the order they
+  // were imported, which the runtime already shuffled.
+  function shuffleTests(tests, seed) {
+    const random = createRandom(seed);
+    const modules = new Map();
+    for (const test of tests) {
+      if (!modules.has(test.origin)) {
+        modules.set(test.origin, []);
+      }
+      modules.get(test.origin).push(test);
+    }
+    const shuffled = [];
+    for (const moduleTests of modules.values()) {
+      for (let i = moduleTests.length - 1; i > 0; i--) {
+        const j = Math.floor(random() * (i + 1));
+        [moduleTests[i], moduleTests[j]] = [moduleTests[j], moduleTests[i]];
+      }
+      shuffled.push(...moduleTests);
+    }
+    return shuffled;
+  }
+
   const TEST_REGISTRY = [];
 
   // Main test function provided by Deno, as you can see it merely
@@ -114,6 +514,25 @@ finishing test case.`;
       testDef = { ...defaults, ...t };
     }
 
+    testDef.origin = getTestOrigin();
+
+    if (
+      testDef.timeout !== undefined &&
+      !(typeof testDef.timeout === "number" && testDef.timeout > 0)
+    ) {
+      throw new TypeError("The test timeout must be a positive number");
+    }
+
+    if (
+      testDef.permissions != null && typeof testDef.permissions !== "object"
+    ) {
+      throw new TypeError("The test permissions must be an object");
+    }
+
+    if (testDef.permissions != null) {
+      testDef.fn = withPermissions(testDef.fn, testDef.permissions);
+    }
+
     if (testDef.sanitizeOps) {
       testDef.fn = assertOps(testDef.fn);
     }
@@ -138,31 +557,69 @@ finishing test case.`;
     stdout.writeSync(encoder.encode(msg));
   }
 
-  function reportToConsole(message) {
+  // Whether the name of the running test or step was logged without its
+  // status, which is then logged on the same line.
+  let consoleLineOpen = false;
+
+  function logStatus({ status, duration }, level) {
     const redFailed = red("FAILED");
     const greenOk = green("ok");
     const yellowIgnored = yellow("ignored");
+    const indent = consoleLineOpen ? "" : "  ".repeat(level);
+    switch (status) {
+      case "passed":
+        log(`${indent}${greenOk} ${formatDuration(duration)}`);
+        break;
+      case "failed":
+        log(`${indent}${redFailed} ${formatDuration(duration)}`);
+        break;
+      case "ignored":
+        log(`${indent}${yellowIgnored} ${formatDuration(duration)}`);
+        break;
+    }
+    consoleLineOpen = false;
+  }
+
+  // The failures of the tests and of their steps, steps first.
+  function getFailures(results) {
+    const failures = [];
+    for (const result of results) {
+      failures.push(...getFailures(result.steps ?? []));
+      if (result.error != null) {
+        failures.push({
+          name: result.fullName ?? result.name,
+          error: result.error,
+        });
+      }
+    }
+    return failures;
+  }
+
+  function reportToConsole(message) {
+    const redFailed = red("FAILED");
+    const greenOk = green("ok");
     if (message.start != null) {
       log(`running ${message.start.tests.length} tests`);
     } else if (message.testStart != null) {
       const { name } = message.testStart;
 
       log(`test ${name} ... `, true);
+      consoleLineOpen = true;
       return;
-    } else if (message.testEnd != null) {
-      switch (message.testEnd.status) {
-        case "passed":
-          log(`${greenOk} ${formatDuration(message.testEnd.duration)}`);
-          break;
-        case "failed":
-          log(`${redFailed} ${formatDuration(message.testEnd.duration)}`);
-          break;
-        case "ignored":
-          log(`${yellowIgnored} ${formatDuration(message.testEnd.duration)}`);
-          break;
+    } else if (message.stepStart != null) {
+      const { name, level } = message.stepStart;
+      if (consoleLineOpen) {
+        log("");
       }
+      log(`${"  ".repeat(level)}test ${name} ... `, true);
+      consoleLineOpen = true;
+      return;
+    } else if (message.stepEnd != null) {
+      logStatus(message.stepEnd, message.stepEnd.level);
+    } else if (message.testEnd != null) {
+      logStatus(message.testEnd, 0);
     } else if (message.end != null) {
-      const failures = message.end.results.filter((m) => m.error != null);
+      const failures = getFailures(message.end.results);
       if (failures.length > 0) {
         log(`\nfailures:\n`);
 
@@ -189,20 +646,74 @@ finishing test case.`;
       if (message.end.usedOnly && message.end.failed == 0) {
         log(`${redFailed} because the "only" option was used\n`);
       }
+
+      const obsoleteSnapshots = message.end.obsoleteSnapshots ?? [];
+      for (const { file, names, removed } of obsoleteSnapshots) {
+        log(
+          `${yellow("warning")}: ${names.length} obsolete snapshot(s) ` +
+            `${removed ? "removed from" : "found in"} ${file}`,
+        );
+        for (const name of names) {
+          log(`\t${name}`);
+        }
+        log("");
+      }
     }
   }
 
   exposeForTest("reportToConsole", reportToConsole);
 
+  // Forward test progress to the runtime, which aggregates the results of
+  // test modules run in parallel workers.
+  function reportToRuntime(message) {
+    let event;
+    if (message.start != null) {
+      event = { plan: { pending: message.start.tests.length } };
+    } else if (message.testStart != null) {
+      event = { wait: { name: message.testStart.name } };
+    } else if (message.stepStart != null) {
+      const { name, level } = message.stepStart;
+      event = { stepWait: { name, level } };
+    } else if (message.stepEnd != null) {
+      const { name, fullName, level, duration, status, error } =
+        message.stepEnd;
+      const result = status == "failed"
+        ? { failed: inspectArgs([error]) }
+        : status == "passed"
+        ? "ok"
+        : "ignored";
+      event = { stepResult: { name, fullName, level, duration, result } };
+    } else if (message.testEnd != null) {
+      const { name, duration, status, error } = message.testEnd;
+      const result = status == "failed"
+        ? { failed: inspectArgs([error]) }
+        : status == "passed"
+        ? "ok"
+        : "ignored";
+      event = { result: { name, duration, result } };
+    } else if (message.end != null) {
+      const { filtered, usedOnly, timedOut, obsoleteSnapshots } = message.end;
+      event = { end: { filtered, usedOnly, timedOut, obsoleteSnapshots } };
+    }
+    core.jsonOpSync("op_post_test_event", event);
+  }
+
+  // Console output of tests reported to the runtime is forwarded as well, so
+  // that it shows up next to the test that produced it.
+  const runtimeConsole = new Console((output) => {
+    core.jsonOpSync("op_post_test_event", { output });
+  });
+
   // TODO: already implements AsyncGenerator<RunTestsMessage>, but add as "implements to class"
   // TODO: implements PromiseLike<RunTestsEndResult>
   class TestRunner {
     #usedOnly = false;
+    #timedOut = false;
 
     constructor(
       tests,
       filterFn,
-      failFast,
+      { failFast, timeout, updateSnapshots, shuffle, reportStep },
     ) {
       this.stats = {
         filtered: 0,
@@ -213,19 +724,27 @@ finishing test case.`;
       };
       this.filterFn = filterFn;
       this.failFast = failFast;
+      this.timeout = timeout;
+      this.updateSnapshots = updateSnapshots;
+      this.reportStep = reportStep;
+      this.tests = tests;
       const onlyTests = tests.filter(({ only }) => only);
       this.#usedOnly = onlyTests.length > 0;
       const unfilteredTests = this.#usedOnly ? onlyTests : tests;
       this.testsToRun = unfilteredTests.filter(filterFn);
       this.stats.filtered = unfilteredTests.length - this.testsToRun.length;
+      if (shuffle != null) {
+        this.testsToRun = shuffleTests(this.testsToRun, shuffle);
+      }
     }
 
     async *[Symbol.asyncIterator]() {
       yield { start: { tests: this.testsToRun } };
 
       const results = [];
+      const passed = new Set();
       const suiteStart = +new Date();
-      for (const test of this.testsToRun) {
+      for (const [index, test] of this.testsToRun.entries()) {
         const endMessage = {
           name: test.name,
           duration: 0,
@@ -236,28 +755,59 @@ finishing test case.`;
           this.stats.ignored++;
         } else {
           const start = +new Date();
+          const context = new TestContext(test.name, test.origin, {
+            updateSnapshots: this.updateSnapshots,
+            reportStep: this.reportStep,
+          });
           try {
-            await test.fn();
+            await runTest(
+              test,
+              test.timeout ?? this.timeout,
+              context,
+              this.testsToRun.length - index - 1,
+            );
             endMessage.status = "passed";
+            passed.add(test);
             this.stats.passed++;
           } catch (err) {
             endMessage.status = "failed";
             endMessage.error = err;
             this.stats.failed++;
+            if (err instanceof TestTimeoutError) {
+              this.#timedOut = true;
+            }
           }
           endMessage.duration = +new Date() - start;
+          endMessage.steps = context.steps;
         }
         results.push(endMessage);
         yield { testEnd: endMessage };
         if (this.failFast && endMessage.error != null) {
           break;
         }
+        // The function of a test that timed out may still be running, and its
+        // ops and resources would be blamed on the tests that run after it.
+        if (this.#timedOut) {
+          break;
+        }
       }
 
       const duration = +new Date() - suiteStart;
 
+      const { obsolete } = core.jsonOpSync("op_finish_snapshots", {
+        modules: getSnapshotModules(this.tests, passed),
+        update: this.updateSnapshots,
+      });
+
       yield {
-        end: { ...this.stats, usedOnly: this.#usedOnly, duration, results },
+        end: {
+          ...this.stats,
+          usedOnly: this.#usedOnly,
+          timedOut: this.#timedOut,
+          obsoleteSnapshots: obsolete,
+          duration,
+          results,
+        },
       };
     }
   }
@@ -299,34 +849,62 @@ finishing test case.`;
     failFast = false,
     filter = undefined,
     skip = undefined,
+    timeout = undefined,
+    updateSnapshots = false,
+    shuffle = undefined,
+    traceOps = false,
     disableLog = false,
     reportToConsole: reportToConsole_ = true,
+    reportToRuntime: reportToRuntime_ = false,
     onMessage = undefined,
   } = {}) {
+    if (traceOps) {
+      core.enableOpCallTracing();
+    }
+
+    const report = async (message) => {
+      if (onMessage != null) {
+        await onMessage(message);
+      }
+      if (reportToConsole_) {
+        reportToConsole(message);
+      }
+      if (reportToRuntime_) {
+        reportToRuntime(message);
+      }
+    };
+
     const filterFn = createFilterFn(filter, skip);
-    const testRunner = new TestRunner(TEST_REGISTRY, filterFn, failFast);
+    const testRunner = new TestRunner(TEST_REGISTRY, filterFn, {
+      failFast,
+      timeout,
+      updateSnapshots,
+      shuffle,
+      // Steps are reported while their test runs.
+      reportStep: report,
+    });
 
     const originalConsole = globalThis.console;
 
+    // Some ops are registered by the test runner after bootstrap.
+    core.ops();
+
     if (disableLog) {
       globalThis.console = disabledConsole;
+    } else if (reportToRuntime_) {
+      globalThis.console = runtimeConsole;
     }
 
     let endMsg;
 
     for await (const message of testRunner) {
-      if (onMessage != null) {
-        await onMessage(message);
-      }
-      if (reportToConsole_) {
-        reportToConsole(message);
-      }
+      await report(message);
       if (message.end != null) {
         endMsg = message.end;
       }
     }
 
-    if (disableLog) {
+    if (disableLog || reportToRuntime_) {
       globalThis.console = originalConsole;
     }
 
@@ -340,6 +918,8 @@ finishing test case.`;
   exposeForTest("runTests", runTests);
 
   window.__bootstrap.testing = {
+    createFilterFn,
+    getTestOrigin,
     test,
   };
 })(this);
@@ -0,0 +1,359 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+((window) => {
+  const { gray, green, italic, red, yellow } = window.__bootstrap.colors;
+  const { exit } = window.__bootstrap.os;
+  const { Console, inspectArgs } = window.__bootstrap.console;
+  const { stdout } = window.__bootstrap.files;
+  const { exposeForTest } = window.__bootstrap.internals;
+  const { build } = window.__bootstrap.build;
+  const { version } = window.__bootstrap.version;
+  const { createFilterFn, getTestOrigin } = window.__bootstrap.testing;
+
+  // Time spent running a benchmark before measuring it, in milliseconds.
+  const WARMUP_TIME = 100;
+  // Minimum duration of a measured batch of iterations, in milliseconds, and
+  // in ticks of the clock, for its granularity not to skew the measurements.
+  const MIN_BATCH_TIME = 1;
+  const MIN_BATCH_TICKS = 100;
+  // Time spent measuring a benchmark, in milliseconds, as long as at least
+  // MIN_SAMPLES batches were measured.
+  const BENCH_TIME = 500;
+  const MIN_SAMPLES = 10;
+  const MAX_SAMPLES = 10000;
+
+  const BENCH_REGISTRY = [];
+
+  const disabledConsole = new Console(() => {});
+
+  function bench(
+    b,
+    fn,
+  ) {
+    let benchDef;
+    const defaults = {
+      ignore: false,
+      only: false,
+    };
+
+    if (typeof b === "string") {
+      if (!fn || typeof fn != "function") {
+        throw new TypeError("Missing bench function");
+      }
+      if (!b) {
+        throw new TypeError("The bench name can't be empty");
+      }
+      benchDef = { fn: fn, name: b, ...defaults };
+    } else {
+      if (!b.fn) {
+        throw new TypeError("Missing bench function");
+      }
+      if (!b.name) {
+        throw new TypeError("The bench name can't be empty");
+      }
+      benchDef = { ...defaults, ...b };
+    }
+
+    for (const option of ["n", "warmup"]) {
+      const value = benchDef[option];
+      if (value !== undefined && !(Number.isInteger(value) && value >= 0)) {
+        throw new TypeError(
+          `The bench option "${option}" must be a non-negative integer`,
+        );
+      }
+    }
+    if (benchDef.n === 0) {
+      throw new TypeError(`The bench option "n" must be a positive integer`);
+    }
+
+    benchDef.origin = getTestOrigin();
+
+    BENCH_REGISTRY.push(benchDef);
+  }
+
+  // The smallest step of `performance.now()` observed, in milliseconds, which
+  // is 2ms without the "hrtime" permission.
+  function measureClockGranularity() {
+    let granularity = Infinity;
+    for (let i = 0; i < 10; i++) {
+      const start = performance.now();
+      let now = start;
+      while (now === start) {
+        now = performance.now();
+      }
+      granularity = Math.min(granularity, now - start);
+    }
+    return granularity;
+  }
+
+  // Run `count` iterations of a benchmark, returning how long they took.
+  async function runIterations(fn, isAsync, count) {
+    const start = performance.now();
+    if (isAsync) {
+      for (let i = 0; i < count; i++) {
+        await fn();
+      }
+    } else {
+      for (let i = 0; i < count; i++) {
+        fn();
+      }
+    }
+    return performance.now() - start;
+  }
+
+  // Measure the duration of an iteration of a benchmark, in milliseconds.
+  // With `n`, every iteration is measured after `warmup` iterations.
+  // Otherwise, iterations are measured in batches large enough to be timed
+  // precisely, after warming up for WARMUP_TIME.
+  async function measure({ fn, n, warmup }) {
+    const first = fn();
+    const isAsync = typeof first?.then === "function";
+    if (isAsync) {
+      await first;
+    }
+
+    const samples = [];
+    if (n !== undefined) {
+      await runIterations(fn, isAsync, warmup ?? 0);
+      for (let i = 0; i < n; i++) {
+        samples.push(await runIterations(fn, isAsync, 1));
+      }
+      return { iterations: n, samples };
+    }
+
+    // The granularity is measured for every benchmark, as the "hrtime"
+    // permission may have been revoked.
+    const minBatchTime = Math.max(
+      MIN_BATCH_TIME,
+      MIN_BATCH_TICKS * measureClockGranularity(),
+    );
+    let batchSize = 1;
+    const warmupStart = performance.now();
+    if (warmup !== undefined) {
+      await runIterations(fn, isAsync, warmup);
+    }
+    while (true) {
+      const time = await runIterations(fn, isAsync, batchSize);
+      if (time < minBatchTime) {
+        batchSize *= 2;
+      } else if (performance.now() - warmupStart >= WARMUP_TIME) {
+        break;
+      }
+    }
+
+    const start = performance.now();
+    while (
+      samples.length < MIN_SAMPLES ||
+      (samples.length < MAX_SAMPLES && performance.now() - start < BENCH_TIME)
+    ) {
+      const time = await runIterations(fn, isAsync, batchSize);
+      samples.push(time / batchSize);
+    }
+    return { iterations: samples.length * batchSize, samples };
+  }
+
+  // The value below which `percentile` percent of the sorted samples are.
+  function percentileOf(sorted, percentile) {
+    const rank = Math.ceil((percentile / 100) * sorted.length);
+    return sorted[Math.max(rank - 1, 0)];
+  }
+
+  function computeStats({ iterations, samples }) {
+    const sorted = [...samples].sort((a, b) => a - b);
+    const mean = sorted.reduce((sum, time) => sum + time, 0) / sorted.length;
+    return {
+      iterations,
+      mean,
+      min: sorted[0],
+      max: sorted[sorted.length - 1],
+      p75: percentileOf(sorted, 75),
+      p99: percentileOf(sorted, 99),
+    };
+  }
+
+  function formatTime(time) {
+    if (time < 0.001) {
+      return `${(time * 1e6).toFixed(2)} ns`;
+    } else if (time < 1) {
+      return `${(time * 1e3).toFixed(2)} µs`;
+    } else if (time < 1000) {
+      return `${time.toFixed(2)} ms`;
+    }
+    return `${(time / 1000).toFixed(2)} s`;
+  }
+
+  const encoder = new TextEncoder();
+
+  function log(msg, noNewLine = false) {
+    if (!noNewLine) {
+      msg += "\n";
+    }
+    stdout.writeSync(encoder.encode(msg));
+  }
+
+  function reportToConsole(message) {
+    const redFailed = red("FAILED");
+    const greenOk = green("ok");
+    if (message.start != null) {
+      log(`running ${message.start.benches.length} benchmarks`);
+    } else if (message.benchStart != null) {
+      log(`bench ${message.benchStart.name} ... `, true);
+    } else if (message.benchEnd != null) {
+      const { status, stats } = message.benchEnd;
+      if (status === "ignored") {
+        log(yellow("ignored"));
+      } else if (status === "failed") {
+        log(redFailed);
+      } else {
+        const { mean, min, max, p75, p99 } = stats;
+        log(
+          `${formatTime(mean)}/iter ` +
+            gray(
+              `(min ${formatTime(min)}, max ${formatTime(max)}, ` +
+                `p75 ${formatTime(p75)}, p99 ${formatTime(p99)})`,
+            ),
+        );
+      }
+    } else if (message.end != null) {
+      const failures = message.end.results.filter(({ error }) =>
+        error != null
+      );
+      if (failures.length > 0) {
+        log(`\nfailures:\n`);
+        for (const { name, error } of failures) {
+          log(name);
+          log(inspectArgs([error]));
+          log("");
+        }
+        log(`failures:\n`);
+        for (const { name } of failures) {
+          log(`\t${name}`);
+        }
+      }
+      log(
+        `\nbench result: ${message.end.failed ? redFailed : greenOk}. ` +
+          `${message.end.passed} passed; ${message.end.failed} failed; ` +
+          `${message.end.ignored} ignored; ` +
+          `${message.end.filtered} filtered out ` +
+          gray(italic(`(${message.end.duration}ms)`)) + "\n",
+      );
+      if (message.end.usedOnly && message.end.failed == 0) {
+        log(`${redFailed} because the "only" option was used\n`);
+      }
+    }
+  }
+
+  // A document describing the results along with the runtime that produced
+  // them, to compare them with the results of other runs. Times are in
+  // milliseconds.
+  function reportToJson({ results }) {
+    const benchmarks = results.map(({ origin, name, status, stats, error }) => {
+      const result = { origin, name, status };
+      if (stats != null) {
+        Object.assign(result, stats);
+      }
+      if (error != null) {
+        result.error = error instanceof Error
+          ? error.stack ?? String(error)
+          : inspectArgs([error]);
+      }
+      return result;
+    });
+    const runtime = {
+      deno: version.deno,
+      v8: version.v8,
+      typescript: version.typescript,
+      target: build.target,
+    };
+    log(JSON.stringify({ runtime, benchmarks }, null, 2));
+  }
+
+  async function* runBenches(benches, filterFn) {
+    const stats = { filtered: 0, ignored: 0, passed: 0, failed: 0 };
+    const onlyBenches = benches.filter(({ only }) => only);
+    const usedOnly = onlyBenches.length > 0;
+    const unfilteredBenches = usedOnly ? onlyBenches : benches;
+    const benchesToRun = unfilteredBenches.filter(filterFn);
+    stats.filtered = unfilteredBenches.length - benchesToRun.length;
+
+    yield { start: { benches: benchesToRun } };
+
+    const results = [];
+    const suiteStart = +new Date();
+    for (const benchDef of benchesToRun) {
+      const { origin, name } = benchDef;
+      const endMessage = { origin, name };
+      yield { benchStart: { ...benchDef } };
+      if (benchDef.ignore) {
+        endMessage.status = "ignored";
+        stats.ignored++;
+      } else {
+        try {
+          endMessage.stats = computeStats(await measure(benchDef));
+          endMessage.status = "passed";
+          stats.passed++;
+        } catch (err) {
+          endMessage.status = "failed";
+          endMessage.error = err;
+          stats.failed++;
+        }
+      }
+      results.push(endMessage);
+      yield { benchEnd: endMessage };
+    }
+
+    yield {
+      end: {
+        ...stats,
+        usedOnly,
+        duration: +new Date() - suiteStart,
+        results,
+      },
+    };
+  }
+
+  async function runBenchmarks({
+    exitOnFail = true,
+    filter = undefined,
+    skip = undefined,
+    json = false,
+    onMessage = undefined,
+  } = {}) {
+    const filterFn = createFilterFn(filter, skip);
+    // Logs of the benchmarks would make the JSON document on stdout invalid.
+    const originalConsole = globalThis.console;
+    if (json) {
+      globalThis.console = disabledConsole;
+    }
+
+    let endMsg;
+    for await (const message of runBenches(BENCH_REGISTRY, filterFn)) {
+      if (onMessage != null) {
+        await onMessage(message);
+      }
+      if (!json) {
+        reportToConsole(message);
+      }
+      if (message.end != null) {
+        endMsg = message.end;
+      }
+    }
+
+    if (json) {
+      globalThis.console = originalConsole;
+      reportToJson(endMsg);
+    }
+
+    if ((endMsg.failed > 0 || endMsg.usedOnly) && exitOnFail) {
+      exit(1);
+    }
+
+    return endMsg;
+  }
+
+  exposeForTest("runBenchmarks", runBenchmarks);
+
+  window.__bootstrap.bench = {
+    bench,
+  };
+})(this);
@@ -129,4 +129,5 @@ __bootstrap.denoNsUnstable = {
   fsync: __bootstrap.fs.fsync,
   HttpClient: __bootstrap.fetch.HttpClient,
   createHttpClient: __bootstrap.fetch.createHttpClient,
+  bench: __bootstrap.bench.bench,
 };
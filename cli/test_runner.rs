@@ -1,40 +1,874 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::colors;
+use crate::coverage;
+use crate::coverage::Coverage;
+use crate::coverage::CoverageCollector;
+use crate::escape::xml_escape;
+use crate::file_fetcher::SourceFile;
+use crate::file_fetcher::TextDocument;
 use crate::fs as deno_fs;
+use crate::global_state::GlobalState;
 use crate::installer::is_remote_url;
+use crate::media_type::MediaType;
+use crate::ops;
+use crate::test_snapshot::ObsoleteSnapshots;
+use crate::tokio_util::create_basic_runtime;
+use crate::worker::MainWorker;
 use deno_core::error::AnyError;
+use deno_core::futures::channel::mpsc;
+use deno_core::futures::StreamExt;
 use deno_core::serde_json::json;
 use deno_core::url::Url;
+use deno_core::ModuleSpecifier;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Deserialize;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
-fn is_supported(p: &Path) -> bool {
-  use std::path::Component;
-  if let Some(Component::Normal(basename_os_str)) = p.components().next_back() {
-    let basename = basename_os_str.to_string_lossy();
-    basename.ends_with("_test.ts")
-      || basename.ends_with("_test.tsx")
-      || basename.ends_with("_test.js")
-      || basename.ends_with("_test.mjs")
-      || basename.ends_with("_test.jsx")
-      || basename.ends_with(".test.ts")
-      || basename.ends_with(".test.tsx")
-      || basename.ends_with(".test.js")
-      || basename.ends_with(".test.mjs")
-      || basename.ends_with(".test.jsx")
-      || basename == "test.ts"
-      || basename == "test.tsx"
-      || basename == "test.js"
-      || basename == "test.mjs"
-      || basename == "test.jsx"
-  } else {
-    false
+/// The outcome of a single test.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TestResult {
+  Ok,
+  Ignored,
+  Failed(String),
+}
+
+/// Progress of a test module, as posted by the test runner of its worker.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TestMessage {
+  Plan {
+    pending: usize,
+  },
+  Wait {
+    name: String,
+  },
+  Output(String),
+  Result {
+    name: String,
+    duration: u64,
+    result: TestResult,
+  },
+  /// A step of the running test or of one of its steps has started. The
+  /// steps run by a test are at level 1.
+  StepWait {
+    name: String,
+    level: usize,
+  },
+  StepResult {
+    name: String,
+    /// The name prefixed with the names of the test and steps running it.
+    #[serde(rename = "fullName")]
+    full_name: String,
+    level: usize,
+    duration: u64,
+    result: TestResult,
+  },
+  End {
+    filtered: usize,
+    #[serde(rename = "usedOnly")]
+    used_only: bool,
+    /// Whether some of the tests failed because of their timeout.
+    #[serde(rename = "timedOut", default)]
+    timed_out: bool,
+    #[serde(rename = "obsoleteSnapshots", default)]
+    obsolete_snapshots: Vec<ObsoleteSnapshots>,
+  },
+  /// The module failed to load or threw an uncaught error.
+  #[serde(skip)]
+  Error(String),
+  /// The worker of the module has finished.
+  #[serde(skip)]
+  Done,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestEvent {
+  /// Index of the module in the list of test modules.
+  pub module: usize,
+  pub message: TestMessage,
+}
+
+#[derive(Debug, Default)]
+pub struct TestSummary {
+  pub passed: usize,
+  pub failed: usize,
+  pub ignored: usize,
+  pub filtered: usize,
+  pub used_only: bool,
+  /// Names and errors of the failed tests, steps and modules.
+  pub failures: Vec<(String, String)>,
+  pub obsolete_snapshots: Vec<ObsoleteSnapshots>,
+}
+
+impl TestSummary {
+  fn update(&mut self, origin: &Url, message: &TestMessage) {
+    match message {
+      TestMessage::Result { name, result, .. } => match result {
+        TestResult::Ok => self.passed += 1,
+        TestResult::Ignored => self.ignored += 1,
+        TestResult::Failed(error) => {
+          self.failed += 1;
+          self.failures.push((name.clone(), error.clone()));
+        }
+      },
+      // Steps don't count as tests, a test fails if any of its steps do.
+      TestMessage::StepResult {
+        full_name,
+        result: TestResult::Failed(error),
+        ..
+      } => self.failures.push((full_name.clone(), error.clone())),
+      TestMessage::End {
+        filtered,
+        used_only,
+        obsolete_snapshots,
+        ..
+      } => {
+        self.filtered += filtered;
+        self.used_only |= used_only;
+        self
+          .obsolete_snapshots
+          .extend(obsolete_snapshots.iter().cloned());
+      }
+      TestMessage::Error(error) => {
+        self.failed += 1;
+        self.failures.push((origin.to_string(), error.clone()));
+      }
+      _ => {}
+    }
+  }
+
+  pub fn has_failed(&self) -> bool {
+    self.failed > 0 || self.used_only
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestReporterKind {
+  Pretty,
+  Junit,
+  Tap,
+  Json,
+}
+
+impl std::str::FromStr for TestReporterKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "pretty" => Ok(TestReporterKind::Pretty),
+      "junit" => Ok(TestReporterKind::Junit),
+      "tap" => Ok(TestReporterKind::Tap),
+      "json" => Ok(TestReporterKind::Json),
+      _ => Err(format!("Unknown test reporter: {}", s)),
+    }
   }
 }
 
+/// Receives the progress of the test modules in the order of the modules.
+pub trait TestReporter {
+  fn visit_message(
+    &mut self,
+    origin: &Url,
+    message: &TestMessage,
+  ) -> io::Result<()>;
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    elapsed: Duration,
+  ) -> io::Result<()>;
+}
+
+/// Create a reporter writing to the file at `maybe_output`, or to stdout.
+pub fn create_reporter(
+  kind: TestReporterKind,
+  maybe_output: Option<PathBuf>,
+  quiet: bool,
+) -> Result<Box<dyn TestReporter>, AnyError> {
+  let out: Box<dyn Write> = match maybe_output {
+    Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+    None => Box::new(io::stdout()),
+  };
+  let reporter: Box<dyn TestReporter> = match kind {
+    TestReporterKind::Pretty => Box::new(PrettyTestReporter::new(out, quiet)),
+    TestReporterKind::Junit => Box::new(JunitTestReporter::new(out)),
+    TestReporterKind::Tap => Box::new(TapTestReporter::new(out)),
+    TestReporterKind::Json => Box::new(JsonTestReporter { out }),
+  };
+  Ok(reporter)
+}
+
+/// Prints the results the same way the test runner of a worker does.
+struct PrettyTestReporter {
+  out: Box<dyn Write>,
+  quiet: bool,
+  /// Whether the name of the running test or step was printed without its
+  /// status, which is then printed on the same line.
+  line_open: bool,
+}
+
+impl PrettyTestReporter {
+  fn new(out: Box<dyn Write>, quiet: bool) -> Self {
+    Self {
+      out,
+      quiet,
+      line_open: false,
+    }
+  }
+
+  fn write_status(
+    &mut self,
+    level: usize,
+    duration: u64,
+    result: &TestResult,
+  ) -> io::Result<()> {
+    let status = match result {
+      TestResult::Ok => colors::green("ok").to_string(),
+      TestResult::Ignored => colors::yellow("ignored").to_string(),
+      TestResult::Failed(_) => colors::red("FAILED").to_string(),
+    };
+    let indent = if self.line_open {
+      String::new()
+    } else {
+      "  ".repeat(level)
+    };
+    self.line_open = false;
+    writeln!(
+      self.out,
+      "{}{} {}",
+      indent,
+      status,
+      format_duration(duration)
+    )
+  }
+}
+
+impl TestReporter for PrettyTestReporter {
+  fn visit_message(
+    &mut self,
+    origin: &Url,
+    message: &TestMessage,
+  ) -> io::Result<()> {
+    if self.quiet {
+      return Ok(());
+    }
+    match message {
+      TestMessage::Plan { pending } => {
+        writeln!(self.out, "running {} tests from {}", pending, origin)?;
+      }
+      TestMessage::Wait { name } => {
+        write!(self.out, "test {} ... ", name)?;
+        self.out.flush()?;
+        self.line_open = true;
+      }
+      TestMessage::Output(output) => {
+        write!(self.out, "{}", output)?;
+        self.out.flush()?;
+      }
+      TestMessage::Result {
+        duration, result, ..
+      } => self.write_status(0, *duration, result)?,
+      TestMessage::StepWait { name, level } => {
+        if self.line_open {
+          writeln!(self.out)?;
+        }
+        write!(self.out, "{}test {} ... ", "  ".repeat(*level), name)?;
+        self.out.flush()?;
+        self.line_open = true;
+      }
+      TestMessage::StepResult {
+        level,
+        duration,
+        result,
+        ..
+      } => self.write_status(*level, *duration, result)?,
+      TestMessage::Error(error) => {
+        writeln!(self.out, "{}: {}", colors::red_bold("error"), error.trim())?;
+      }
+      TestMessage::End { .. } | TestMessage::Done => {}
+    }
+    Ok(())
+  }
+
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    elapsed: Duration,
+  ) -> io::Result<()> {
+    if self.quiet {
+      return Ok(());
+    }
+    if !summary.failures.is_empty() {
+      writeln!(self.out, "\nfailures:\n")?;
+      for (name, error) in &summary.failures {
+        writeln!(self.out, "{}", name)?;
+        writeln!(self.out, "{}", error)?;
+        writeln!(self.out)?;
+      }
+      writeln!(self.out, "failures:\n")?;
+      for (name, _) in &summary.failures {
+        writeln!(self.out, "\t{}", name)?;
+      }
+    }
+    let status = if summary.failed > 0 {
+      colors::red("FAILED").to_string()
+    } else {
+      colors::green("ok").to_string()
+    };
+    writeln!(
+      self.out,
+      "\ntest result: {}. {} passed; {} failed; {} ignored; 0 measured; {} filtered out {}\n",
+      status,
+      summary.passed,
+      summary.failed,
+      summary.ignored,
+      summary.filtered,
+      format_duration(elapsed.as_millis() as u64),
+    )?;
+    if summary.used_only && summary.failed == 0 {
+      writeln!(
+        self.out,
+        "{} because the \"only\" option was used\n",
+        colors::red("FAILED")
+      )?;
+    }
+    for obsolete in &summary.obsolete_snapshots {
+      writeln!(
+        self.out,
+        "{}: {} obsolete snapshot(s) {} {}",
+        colors::yellow("warning"),
+        obsolete.names.len(),
+        if obsolete.removed {
+          "removed from"
+        } else {
+          "found in"
+        },
+        obsolete.file
+      )?;
+      for name in &obsolete.names {
+        writeln!(self.out, "\t{}", name)?;
+      }
+      writeln!(self.out)?;
+    }
+    self.out.flush()
+  }
+}
+
+fn format_duration(millis: u64) -> String {
+  colors::italic_gray(&format!("({}ms)", millis)).to_string()
+}
+
+/// Errors and output are printed by the workers with colors if the terminal
+/// supports them, which machine-readable formats don't want.
+fn plain_text(text: &str) -> String {
+  colors::strip_ansi_codes(text).to_string()
+}
+
+/// Writes an event per line, as a JSON object with a `type` field.
+struct JsonTestReporter {
+  out: Box<dyn Write>,
+}
+
+impl TestReporter for JsonTestReporter {
+  fn visit_message(
+    &mut self,
+    origin: &Url,
+    message: &TestMessage,
+  ) -> io::Result<()> {
+    let module = origin.to_string();
+    let event = match message {
+      TestMessage::Plan { pending } => {
+        json!({ "type": "plan", "module": module, "pending": pending })
+      }
+      TestMessage::Wait { name } => {
+        json!({ "type": "wait", "module": module, "name": name })
+      }
+      TestMessage::Output(output) => json!({
+        "type": "output",
+        "module": module,
+        "output": plain_text(output),
+      }),
+      TestMessage::Result {
+        name,
+        duration,
+        result,
+      } => {
+        let (status, error) = match result {
+          TestResult::Ok => ("ok", None),
+          TestResult::Ignored => ("ignored", None),
+          TestResult::Failed(error) => ("failed", Some(plain_text(error))),
+        };
+        json!({
+          "type": "result",
+          "module": module,
+          "name": name,
+          "status": status,
+          "duration": duration,
+          "error": error,
+        })
+      }
+      TestMessage::StepWait { name, level } => json!({
+        "type": "stepWait",
+        "module": module,
+        "name": name,
+        "level": level,
+      }),
+      TestMessage::StepResult {
+        name,
+        full_name,
+        level,
+        duration,
+        result,
+      } => {
+        let (status, error) = match result {
+          TestResult::Ok => ("ok", None),
+          TestResult::Ignored => ("ignored", None),
+          TestResult::Failed(error) => ("failed", Some(plain_text(error))),
+        };
+        json!({
+          "type": "stepResult",
+          "module": module,
+          "name": name,
+          "fullName": full_name,
+          "level": level,
+          "status": status,
+          "duration": duration,
+          "error": error,
+        })
+      }
+      TestMessage::End {
+        filtered,
+        used_only,
+        obsolete_snapshots,
+        ..
+      } => json!({
+        "type": "end",
+        "module": module,
+        "filtered": filtered,
+        "usedOnly": used_only,
+        "obsoleteSnapshots": obsolete_snapshots,
+      }),
+      TestMessage::Error(error) => json!({
+        "type": "error",
+        "module": module,
+        "error": plain_text(error),
+      }),
+      TestMessage::Done => return Ok(()),
+    };
+    writeln!(self.out, "{}", event)?;
+    self.out.flush()
+  }
+
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    elapsed: Duration,
+  ) -> io::Result<()> {
+    let event = json!({
+      "type": "summary",
+      "passed": summary.passed,
+      "failed": summary.failed,
+      "ignored": summary.ignored,
+      "filtered": summary.filtered,
+      "usedOnly": summary.used_only,
+      "duration": elapsed.as_millis() as u64,
+    });
+    writeln!(self.out, "{}", event)?;
+    self.out.flush()
+  }
+}
+
+/// Writes a TAP version 13 stream, with the failure message and stack of a
+/// failed test in its YAML diagnostics block. The steps of a test are written
+/// as an indented subtest before it.
+struct TapTestReporter {
+  out: Box<dyn Write>,
+  count: usize,
+  /// Names of the running test and steps, with the number of their steps
+  /// that have finished, by level.
+  running: Vec<(String, usize)>,
+}
+
+impl TapTestReporter {
+  fn new(out: Box<dyn Write>) -> Self {
+    Self {
+      out,
+      count: 0,
+      running: Vec::new(),
+    }
+  }
+
+  fn write_result(
+    &mut self,
+    level: usize,
+    number: usize,
+    name: &str,
+    duration: u64,
+    result: &TestResult,
+  ) -> io::Result<()> {
+    // The plan of the steps comes after them.
+    if let Some((_, steps)) = self.running.get(level) {
+      if *steps > 0 {
+        writeln!(self.out, "{}1..{}", "    ".repeat(level + 1), steps)?;
+      }
+    }
+    self.running.truncate(level);
+    let indent = "    ".repeat(level);
+    match result {
+      TestResult::Ok => writeln!(
+        self.out,
+        "{}ok {} - {} # time={}ms",
+        indent, number, name, duration
+      ),
+      TestResult::Ignored => {
+        writeln!(self.out, "{}ok {} - {} # SKIP", indent, number, name)
+      }
+      TestResult::Failed(error) => {
+        self.write_failure(level, number, name, Some(duration), error)
+      }
+    }
+  }
+
+  fn write_failure(
+    &mut self,
+    level: usize,
+    number: usize,
+    name: &str,
+    duration: Option<u64>,
+    error: &str,
+  ) -> io::Result<()> {
+    let indent = "    ".repeat(level);
+    let error = plain_text(error);
+    let message = error.lines().next().unwrap_or("");
+    writeln!(self.out, "{}not ok {} - {}", indent, number, name)?;
+    writeln!(self.out, "{}  ---", indent)?;
+    if let Some(duration) = duration {
+      writeln!(self.out, "{}  duration_ms: {}", indent, duration)?;
+    }
+    writeln!(self.out, "{}  message: {}", indent, json!(message))?;
+    writeln!(self.out, "{}  stack: |-", indent)?;
+    for line in error.trim_end().lines() {
+      writeln!(self.out, "{}    {}", indent, line)?;
+    }
+    writeln!(self.out, "{}  ...", indent)
+  }
+}
+
+impl TestReporter for TapTestReporter {
+  fn visit_message(
+    &mut self,
+    origin: &Url,
+    message: &TestMessage,
+  ) -> io::Result<()> {
+    if self.count == 0 && matches!(message, TestMessage::Plan { .. }) {
+      writeln!(self.out, "TAP version 13")?;
+    }
+    match message {
+      TestMessage::Plan { .. } => writeln!(self.out, "# {}", origin)?,
+      TestMessage::Wait { name } => {
+        self.running = vec![(name.clone(), 0)];
+      }
+      TestMessage::Output(output) => {
+        let indent = "    ".repeat(self.running.len().saturating_sub(1));
+        for line in plain_text(output).lines() {
+          writeln!(self.out, "{}# {}", indent, line)?;
+        }
+      }
+      TestMessage::Result {
+        name,
+        duration,
+        result,
+      } => {
+        self.count += 1;
+        let number = self.count;
+        self.write_result(0, number, name, *duration, result)?;
+      }
+      TestMessage::StepWait { name, level } => {
+        self.running.truncate(*level);
+        if let Some((parent, steps)) = self.running.last() {
+          if *steps == 0 {
+            let indent = "    ".repeat(*level);
+            writeln!(self.out, "{}# Subtest: {}", indent, parent)?;
+          }
+        }
+        self.running.push((name.clone(), 0));
+      }
+      TestMessage::StepResult {
+        name,
+        level,
+        duration,
+        result,
+        ..
+      } => {
+        let number = match self.running.get_mut(level.saturating_sub(1)) {
+          Some((_, steps)) => {
+            *steps += 1;
+            *steps
+          }
+          None => 1,
+        };
+        self.write_result(*level, number, name, *duration, result)?;
+      }
+      TestMessage::Error(error) => {
+        self.count += 1;
+        let number = self.count;
+        self.write_failure(0, number, &origin.to_string(), None, error)?
+      }
+      TestMessage::End {
+        obsolete_snapshots, ..
+      } => {
+        for obsolete in obsolete_snapshots {
+          let status = if obsolete.removed {
+            "removed"
+          } else {
+            "obsolete"
+          };
+          for name in &obsolete.names {
+            writeln!(self.out, "# {} snapshot: {}", status, name)?;
+          }
+        }
+      }
+      TestMessage::Done => {}
+    }
+    self.out.flush()
+  }
+
+  fn done(
+    &mut self,
+    summary: &TestSummary,
+    elapsed: Duration,
+  ) -> io::Result<()> {
+    if self.count == 0 {
+      writeln!(self.out, "TAP version 13")?;
+    }
+    writeln!(self.out, "1..{}", self.count)?;
+    writeln!(self.out, "# pass {}", summary.passed)?;
+    writeln!(self.out, "# fail {}", summary.failed)?;
+    writeln!(self.out, "# skip {}", summary.ignored)?;
+    writeln!(self.out, "# duration_ms {}", elapsed.as_millis())?;
+    self.out.flush()
+  }
+}
+
+struct JunitTestCase {
+  name: String,
+  duration: u64,
+  result: TestResult,
+  /// Whether the module failed, rather than one of its tests.
+  is_error: bool,
+  output: String,
+}
+
+struct JunitTestSuite {
+  name: String,
+  cases: Vec<JunitTestCase>,
+  /// Output of the test that is running.
+  output: String,
+}
+
+/// Writes a JUnit XML document with a test suite per module once all of
+/// them have run.
+struct JunitTestReporter {
+  out: Box<dyn Write>,
+  suites: Vec<JunitTestSuite>,
+}
+
+impl JunitTestReporter {
+  fn new(out: Box<dyn Write>) -> Self {
+    Self {
+      out,
+      suites: Vec::new(),
+    }
+  }
+}
+
+impl TestReporter for JunitTestReporter {
+  fn visit_message(
+    &mut self,
+    origin: &Url,
+    message: &TestMessage,
+  ) -> io::Result<()> {
+    let name = origin.to_string();
+    if self.suites.last().map(|suite| &suite.name) != Some(&name) {
+      self.suites.push(JunitTestSuite {
+        name,
+        cases: Vec::new(),
+        output: String::new(),
+      });
+    }
+    let suite = self.suites.last_mut().unwrap();
+    match message {
+      TestMessage::Wait { .. } => suite.output.clear(),
+      TestMessage::Output(output) => suite.output.push_str(output),
+      TestMessage::Result {
+        name,
+        duration,
+        result,
+      } => {
+        let output = std::mem::take(&mut suite.output);
+        suite.cases.push(JunitTestCase {
+          name: name.clone(),
+          duration: *duration,
+          result: result.clone(),
+          is_error: false,
+          output,
+        });
+      }
+      TestMessage::StepResult {
+        full_name,
+        duration,
+        result,
+        ..
+      } => {
+        let output = std::mem::take(&mut suite.output);
+        suite.cases.push(JunitTestCase {
+          name: full_name.clone(),
+          duration: *duration,
+          result: result.clone(),
+          is_error: false,
+          output,
+        });
+      }
+      TestMessage::Error(error) => suite.cases.push(JunitTestCase {
+        name: suite.name.clone(),
+        duration: 0,
+        result: TestResult::Failed(error.clone()),
+        is_error: true,
+        output: std::mem::take(&mut suite.output),
+      }),
+      TestMessage::Plan { .. }
+      | TestMessage::StepWait { .. }
+      | TestMessage::End { .. }
+      | TestMessage::Done => {}
+    }
+    Ok(())
+  }
+
+  fn done(
+    &mut self,
+    _summary: &TestSummary,
+    elapsed: Duration,
+  ) -> io::Result<()> {
+    let count = |suite: &JunitTestSuite, f: &dyn Fn(&JunitTestCase) -> bool| {
+      suite.cases.iter().filter(|case| f(case)).count()
+    };
+    let is_failure = |case: &JunitTestCase| {
+      !case.is_error && matches!(case.result, TestResult::Failed(_))
+    };
+    let is_skipped =
+      |case: &JunitTestCase| matches!(case.result, TestResult::Ignored);
+    // Steps are test cases too, so the totals are those of the suites rather
+    // than those of the summary.
+    let total = |f: &dyn Fn(&JunitTestCase) -> bool| {
+      self
+        .suites
+        .iter()
+        .map(|suite| count(suite, f))
+        .sum::<usize>()
+    };
+
+    writeln!(self.out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+      self.out,
+      r#"<testsuites name="deno test" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+      total(&|case| !case.is_error),
+      total(&is_failure),
+      total(&|case| case.is_error),
+      total(&is_skipped),
+      elapsed.as_secs_f64(),
+    )?;
+    for suite in &self.suites {
+      let name = xml_escape(&suite.name);
+      let time =
+        suite.cases.iter().map(|case| case.duration).sum::<u64>() as f64;
+      writeln!(
+        self.out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+        name,
+        count(suite, &|case| !case.is_error),
+        count(suite, &is_failure),
+        count(suite, &|case| case.is_error),
+        count(suite, &is_skipped),
+        time / 1000.0,
+      )?;
+      for case in &suite.cases {
+        write!(
+          self.out,
+          r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+          xml_escape(&case.name),
+          name,
+          case.duration as f64 / 1000.0,
+        )?;
+        if case.result == TestResult::Ok && case.output.is_empty() {
+          writeln!(self.out, "/>")?;
+          continue;
+        }
+        writeln!(self.out, ">")?;
+        match &case.result {
+          TestResult::Ok => {}
+          TestResult::Ignored => writeln!(self.out, "      <skipped/>")?,
+          TestResult::Failed(error) => {
+            let error = plain_text(error);
+            let message = error.lines().next().unwrap_or("");
+            let tag = if case.is_error { "error" } else { "failure" };
+            writeln!(
+              self.out,
+              r#"      <{} message="{}">{}</{}>"#,
+              tag,
+              xml_escape(message),
+              xml_escape(error.trim_end()),
+              tag
+            )?;
+          }
+        }
+        if !case.output.is_empty() {
+          writeln!(
+            self.out,
+            "      <system-out>{}</system-out>",
+            xml_escape(&plain_text(&case.output))
+          )?;
+        }
+        writeln!(self.out, "    </testcase>")?;
+      }
+      writeln!(self.out, "  </testsuite>")?;
+    }
+    writeln!(self.out, "</testsuites>")?;
+    self.out.flush()
+  }
+}
+
+fn is_supported(p: &Path) -> bool {
+  deno_fs::is_supported_test_path(p, "test")
+}
+
 pub fn prepare_test_modules_urls(
   include: Vec<String>,
   root_path: &PathBuf,
+) -> Result<Vec<Url>, AnyError> {
+  prepare_urls(include, root_path, is_supported)
+}
+
+/// Resolve the paths and URLs of `include`, expanding local directories to
+/// the files they contain that pass `filter`.
+pub fn prepare_urls(
+  include: Vec<String>,
+  root_path: &PathBuf,
+  filter: fn(&Path) -> bool,
 ) -> Result<Vec<Url>, AnyError> {
   let (include_paths, include_urls): (Vec<String>, Vec<String>) =
     include.into_iter().partition(|n| !is_remote_url(n));
@@ -44,12 +878,12 @@ pub fn prepare_test_modules_urls(
   for path in include_paths {
     let p = deno_fs::normalize_path(&root_path.join(path));
     if p.is_dir() {
-      let test_files = crate::fs::files_in_subtree(p, is_supported);
-      let test_files_as_urls = test_files
+      let files = crate::fs::files_in_subtree(p, filter);
+      let files_as_urls = files
         .iter()
         .map(|f| Url::from_file_path(f).unwrap())
         .collect::<Vec<Url>>();
-      prepared.extend(test_files_as_urls);
+      prepared.extend(files_as_urls);
     } else {
       let url = Url::from_file_path(p).unwrap();
       prepared.push(url);
@@ -64,11 +898,40 @@ pub fn prepare_test_modules_urls(
   Ok(prepared)
 }
 
+/// Shuffle `test_modules` in an order that only depends on `seed`, no matter
+/// the order in which directories were walked.
+pub fn shuffle_test_modules(test_modules: &mut Vec<Url>, seed: u64) {
+  test_modules.sort();
+  let mut rng = StdRng::seed_from_u64(seed);
+  test_modules.shuffle(&mut rng);
+}
+
+/// Options of a test run, passed to the test runner of the workers.
+#[derive(Clone, Debug, Default)]
+pub struct TestOptions {
+  pub fail_fast: bool,
+  pub quiet: bool,
+  pub filter: Option<String>,
+  /// Default timeout of a test in milliseconds.
+  pub timeout: Option<u64>,
+  /// Write missing and mismatching snapshots, and remove obsolete ones.
+  pub update_snapshots: bool,
+  /// Also test the examples of the documentation. Unlike the other options,
+  /// this one is handled before the test modules run.
+  pub doc: bool,
+  /// Seed of the random order of the tests, if they are shuffled.
+  pub shuffle: Option<u64>,
+  /// Capture where ops are dispatched, to report the leaks of tests.
+  pub trace_ops: bool,
+}
+
+/// Render the module that imports the test `modules` and runs their tests.
+/// With `report_to_runtime`, results are posted to the test runner of the
+/// CLI instead of being printed.
 pub fn render_test_file(
   modules: Vec<Url>,
-  fail_fast: bool,
-  quiet: bool,
-  filter: Option<String>,
+  options: &TestOptions,
+  report_to_runtime: bool,
 ) -> String {
   let mut test_file = "".to_string();
 
@@ -76,24 +939,267 @@ pub fn render_test_file(
     test_file.push_str(&format!("import \"{}\";\n", module.to_string()));
   }
 
-  let options = if let Some(filter) = filter {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet, "filter": filter })
-  } else {
-    json!({ "failFast": fail_fast, "reportToConsole": !quiet, "disableLog": quiet })
-  };
+  let quiet = options.quiet;
+  let mut run_options = json!({ "failFast": options.fail_fast, "reportToConsole": !quiet, "disableLog": quiet });
+  if let Some(filter) = &options.filter {
+    run_options["filter"] = json!(filter);
+  }
+  if let Some(timeout) = options.timeout {
+    run_options["timeout"] = json!(timeout);
+  }
+  if options.update_snapshots {
+    run_options["updateSnapshots"] = json!(true);
+  }
+  // The test runner shuffles tests with a 32-bit seed.
+  if let Some(seed) = options.shuffle {
+    run_options["shuffle"] = json!(seed as u32);
+  }
+  if options.trace_ops {
+    run_options["traceOps"] = json!(true);
+  }
+  if report_to_runtime {
+    run_options["reportToConsole"] = json!(false);
+    run_options["reportToRuntime"] = json!(true);
+    run_options["exitOnFail"] = json!(false);
+  }
 
   let run_tests_cmd = format!(
     "// @ts-ignore\nDeno[Deno.internal].runTests({});\n",
-    options
+    run_options
   );
   test_file.push_str(&run_tests_cmd);
 
   test_file
 }
 
+/// Create a worker whose main module is the generated `test_file`. It is
+/// saved in the file fetcher cache so that the compiler can access it.
+pub fn create_test_worker(
+  global_state: &Arc<GlobalState>,
+  test_file_url: &Url,
+  test_file: String,
+) -> (MainWorker, ModuleSpecifier) {
+  let main_module =
+    ModuleSpecifier::resolve_url(&test_file_url.to_string()).unwrap();
+  let mut worker = MainWorker::new(global_state, main_module.clone());
+  ops::testing::init_permissions(&mut worker);
+  // Create a dummy source file.
+  let source_file = SourceFile {
+    filename: test_file_url.to_file_path().unwrap(),
+    url: test_file_url.clone(),
+    types_header: None,
+    media_type: MediaType::TypeScript,
+    source_code: TextDocument::new(test_file.into_bytes(), Some("utf-8")),
+  };
+  // Save our fake file into file fetcher cache
+  // to allow module access by TS compiler
+  global_state
+    .file_fetcher
+    .save_source_file_in_cache(&main_module, source_file);
+  (worker, main_module)
+}
+
+/// Evaluate the generated test module, which runs the tests once loaded.
+pub async fn run_test_worker(
+  worker: &mut MainWorker,
+  main_module: &ModuleSpecifier,
+) -> Result<(), AnyError> {
+  worker.execute_module(main_module).await?;
+  worker.execute("window.dispatchEvent(new Event('load'))")?;
+  (&mut **worker).await?;
+  worker.execute("window.dispatchEvent(new Event('unload'))")?;
+  (&mut **worker).await?;
+  Ok(())
+}
+
+/// Run the test module at index `module` of `test_modules` in a new worker.
+/// Returns the coverage of the tested modules if it is collected.
+async fn run_test_module(
+  global_state: &Arc<GlobalState>,
+  cwd: &Path,
+  module: usize,
+  test_modules: &[Url],
+  options: &TestOptions,
+  sender: mpsc::UnboundedSender<TestEvent>,
+) -> Result<Vec<Coverage>, AnyError> {
+  // Every module gets its own generated test module, so that they can be
+  // compiled independently.
+  let test_file_path = cwd.join(format!("$deno$test_{}.ts", module));
+  let test_file_url =
+    Url::from_file_path(&test_file_path).expect("Should be valid file url");
+  let test_file =
+    render_test_file(vec![test_modules[module].clone()], options, true);
+  let (mut worker, main_module) =
+    create_test_worker(global_state, &test_file_url, test_file);
+  let (timed_out_sender, mut timed_out_receiver) = mpsc::unbounded();
+  ops::testing::init_events(&mut worker, module, sender, timed_out_sender);
+
+  let mut maybe_coverage_collector = if global_state.flags.coverage {
+    let inspector = worker
+      .inspector
+      .as_mut()
+      .expect("Inspector is not created.");
+    let mut coverage_collector = CoverageCollector::new(&mut **inspector);
+    coverage_collector.start_collecting().await?;
+    Some(coverage_collector)
+  } else {
+    None
+  };
+
+  // Async ops of a test that timed out may never complete, so the worker is
+  // dropped once its tests are done rather than waiting for its event loop.
+  let timed_out = tokio::select! {
+    result = run_test_worker(&mut worker, &main_module) => {
+      result?;
+      false
+    }
+    _ = timed_out_receiver.next() => true,
+  };
+
+  match maybe_coverage_collector.as_mut() {
+    // The coverage of a worker whose tests timed out is incomplete.
+    Some(coverage_collector) if !timed_out => {
+      let coverages = coverage_collector.collect().await?;
+      coverage_collector.stop_collecting().await?;
+      Ok(coverage::filter_script_coverages(
+        coverages,
+        test_file_url,
+        test_modules.to_vec(),
+      ))
+    }
+    _ => Ok(vec![]),
+  }
+}
+
+/// Run `test_modules` in `jobs` threads, each module in a worker of its own.
+/// Results are reported in the order of `test_modules`, regardless of the
+/// order in which the modules finish. Returns the summary of the run and,
+/// if coverage is collected, the coverage of every worker.
+pub async fn run_tests_in_workers(
+  global_state: &Arc<GlobalState>,
+  cwd: &Path,
+  test_modules: Vec<Url>,
+  jobs: usize,
+  options: &TestOptions,
+  mut reporter: Box<dyn TestReporter>,
+) -> Result<(TestSummary, Vec<Coverage>), AnyError> {
+  let start = Instant::now();
+  let test_modules = Arc::new(test_modules);
+  let next_module = Arc::new(AtomicUsize::new(0));
+  let stop = Arc::new(AtomicBool::new(false));
+  let (sender, mut receiver) = mpsc::unbounded::<TestEvent>();
+  // The threads aren't joined, as that would block the runtime of the caller.
+  let (coverage_sender, mut coverage_receiver) = mpsc::unbounded();
+
+  for i in 0..jobs.min(test_modules.len()) {
+    let global_state = global_state.clone();
+    let cwd = cwd.to_path_buf();
+    let test_modules = test_modules.clone();
+    let next_module = next_module.clone();
+    let stop = stop.clone();
+    let options = options.clone();
+    let sender = sender.clone();
+    let coverage_sender = coverage_sender.clone();
+    std::thread::Builder::new()
+      .name(format!("deno-test-{}", i))
+      .spawn(move || {
+        let mut rt = create_basic_runtime();
+        let mut coverages = Vec::new();
+        while !stop.load(Ordering::SeqCst) {
+          let module = next_module.fetch_add(1, Ordering::SeqCst);
+          if module >= test_modules.len() {
+            break;
+          }
+          let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            rt.block_on(run_test_module(
+              &global_state,
+              &cwd,
+              module,
+              &test_modules,
+              &options,
+              sender.clone(),
+            ))
+          }));
+          let maybe_error = match result {
+            Ok(Ok(module_coverages)) => {
+              coverages.extend(module_coverages);
+              None
+            }
+            Ok(Err(err)) => Some(err.to_string()),
+            Err(_) => {
+              // The runtime may be left in any state by the panic.
+              rt = create_basic_runtime();
+              Some("Test worker panicked".to_string())
+            }
+          };
+          if let Some(error) = maybe_error {
+            let message = TestMessage::Error(error);
+            if sender
+              .unbounded_send(TestEvent { module, message })
+              .is_err()
+            {
+              break;
+            }
+          }
+          let message = TestMessage::Done;
+          if sender
+            .unbounded_send(TestEvent { module, message })
+            .is_err()
+          {
+            break;
+          }
+        }
+        coverage_sender.unbounded_send(coverages).ok();
+      })?;
+  }
+  drop(sender);
+  drop(coverage_sender);
+
+  // Events of the module whose results are being printed are reported right
+  // away, those of later modules are buffered until it is done.
+  let mut summary = TestSummary::default();
+  let mut buffered = vec![Vec::new(); test_modules.len()];
+  let mut finished = vec![false; test_modules.len()];
+  let mut current = 0;
+  while let Some(TestEvent { module, message }) = receiver.next().await {
+    let failed = match &message {
+      TestMessage::Result { result, .. } => {
+        matches!(result, TestResult::Failed(_))
+      }
+      TestMessage::Error(_) => true,
+      _ => false,
+    };
+    if failed && options.fail_fast {
+      stop.store(true, Ordering::SeqCst);
+    }
+    match message {
+      TestMessage::Done => finished[module] = true,
+      message => buffered[module].push(message),
+    }
+    while current < test_modules.len() {
+      for message in buffered[current].drain(..) {
+        summary.update(&test_modules[current], &message);
+        reporter.visit_message(&test_modules[current], &message)?;
+      }
+      if !finished[current] {
+        break;
+      }
+      current += 1;
+    }
+  }
+  let mut coverages = Vec::new();
+  while let Some(thread_coverages) = coverage_receiver.next().await {
+    coverages.extend(thread_coverages);
+  }
+
+  reporter.done(&summary, start.elapsed())?;
+  Ok((summary, coverages))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use deno_core::serde_json;
 
   #[test]
   fn test_prepare_test_modules_urls() {
@@ -146,6 +1252,61 @@ mod tests {
     assert!(!is_supported(Path::new("NotAtest.ts")));
   }
 
+  #[test]
+  fn test_shuffle_test_modules() {
+    let modules = (0..10)
+      .map(|i| Url::parse(&format!("file:///{}_test.ts", i)).unwrap())
+      .collect::<Vec<_>>();
+    let mut shuffled = modules.clone();
+    shuffle_test_modules(&mut shuffled, 1234);
+    assert_ne!(shuffled, modules);
+
+    // The order only depends on the seed.
+    let mut reversed = modules.iter().rev().cloned().collect::<Vec<_>>();
+    shuffle_test_modules(&mut reversed, 1234);
+    assert_eq!(reversed, shuffled);
+    shuffled.sort();
+    assert_eq!(shuffled, modules);
+  }
+
+  #[test]
+  fn test_summary_step_failures() {
+    let origin = Url::parse("file:///a_test.ts").unwrap();
+    let mut summary = TestSummary::default();
+    let messages: Vec<TestMessage> = serde_json::from_value(json!([
+      { "wait": { "name": "a" } },
+      { "stepWait": { "name": "b", "level": 1 } },
+      {
+        "stepResult": {
+          "name": "b",
+          "fullName": "a > b",
+          "level": 1,
+          "duration": 1,
+          "result": { "failed": "Error: b" },
+        },
+      },
+      {
+        "result": {
+          "name": "a",
+          "duration": 2,
+          "result": { "failed": "Error: 1 test step(s) failed." },
+        },
+      },
+    ]))
+    .unwrap();
+    for message in &messages {
+      summary.update(&origin, message);
+    }
+    assert_eq!(summary.failed, 1);
+    assert_eq!(
+      summary.failures,
+      vec![
+        ("a > b".to_string(), "Error: b".to_string()),
+        ("a".to_string(), "Error: 1 test step(s) failed.".to_string()),
+      ]
+    );
+  }
+
   #[test]
   fn supports_dirs() {
     let root = test_util::root_path().join("std").join("http");
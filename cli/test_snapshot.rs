@@ -0,0 +1,338 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Snapshot testing with `t.assertSnapshot()`.
+//!
+//! The snapshots of a test module are stored beside it, in
+//! `__snapshots__/<module file name>.snap`. The file is a JavaScript module
+//! assigning every snapshot to a key made of the name of the test and the
+//! index of the assertion within the test:
+//!
+//! ```js
+//! export const snapshot = {};
+//!
+//! snapshot[`parses the config 1`] = `
+//! { debug: true }
+//! `;
+//! ```
+
+use crate::diff::diff;
+use deno_core::error::generic_error;
+use deno_core::error::AnyError;
+use deno_core::url::Url;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+const SNAPSHOT_HEADER: &str = "export const snapshot = {};\n";
+
+/// Snapshots of a test module that are no longer asserted by its tests.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ObsoleteSnapshots {
+  pub file: String,
+  pub names: Vec<String>,
+  /// Whether the snapshots were removed from the file.
+  pub removed: bool,
+}
+
+/// A test module that has run, with the tests that it registered but that
+/// didn't run, whose snapshots are kept as they are.
+#[derive(Debug, Deserialize)]
+pub struct SnapshotModule {
+  pub origin: String,
+  pub skipped: Vec<String>,
+}
+
+struct SnapshotFile {
+  path: PathBuf,
+  snapshots: IndexMap<String, String>,
+  /// Keys of the snapshots asserted during this run.
+  used: HashSet<String>,
+  changed: bool,
+}
+
+impl SnapshotFile {
+  fn load(path: PathBuf) -> Result<Self, AnyError> {
+    let snapshots = if path.is_file() {
+      let source = fs::read_to_string(&path)?;
+      parse_snapshots(&source).map_err(|err| {
+        generic_error(format!(
+          "Failed to parse snapshot file {}: {}",
+          path.to_string_lossy(),
+          err
+        ))
+      })?
+    } else {
+      IndexMap::new()
+    };
+    Ok(Self {
+      path,
+      snapshots,
+      used: HashSet::new(),
+      changed: false,
+    })
+  }
+
+  fn write(&self) -> Result<(), AnyError> {
+    if self.snapshots.is_empty() {
+      if self.path.is_file() {
+        fs::remove_file(&self.path)?;
+      }
+      return Ok(());
+    }
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(&self.path, serialize_snapshots(&self.snapshots))?;
+    Ok(())
+  }
+}
+
+/// The snapshot files of the test modules run by a worker. Changes are
+/// written once all tests have run.
+#[derive(Default)]
+pub struct SnapshotStore {
+  files: HashMap<PathBuf, SnapshotFile>,
+}
+
+impl SnapshotStore {
+  fn get_file(&mut self, origin: &str) -> Result<&mut SnapshotFile, AnyError> {
+    let path = get_snapshot_path(origin)?;
+    if !self.files.contains_key(&path) {
+      let file = SnapshotFile::load(path.clone())?;
+      self.files.insert(path.clone(), file);
+    }
+    Ok(self.files.get_mut(&path).unwrap())
+  }
+
+  /// Compare `actual` to the snapshot `key` of the test module `origin`.
+  /// With `update`, the snapshot is written instead if it's missing or
+  /// different. Returns the error message of a failed assertion.
+  pub fn assert(
+    &mut self,
+    origin: &str,
+    key: &str,
+    actual: &str,
+    update: bool,
+  ) -> Result<Option<String>, AnyError> {
+    let file = self.get_file(origin)?;
+    if !file.used.insert(key.to_string()) {
+      return Err(generic_error(format!(
+        "Snapshot \"{}\" is asserted more than once, tests must have unique names",
+        key
+      )));
+    }
+    match file.snapshots.get(key).cloned() {
+      Some(expected) if expected == actual => Ok(None),
+      _ if update => {
+        file.snapshots.insert(key.to_string(), actual.to_string());
+        file.changed = true;
+        Ok(None)
+      }
+      Some(expected) => Ok(Some(format!(
+        "Snapshot \"{}\" does not match:\n\n{}\nRun `deno test --update-snapshots` to update it.",
+        key,
+        diff(&expected, actual)?
+      ))),
+      None => Ok(Some(format!(
+        "Missing snapshot \"{}\" in {}\nRun `deno test --update-snapshots` to write it.",
+        key,
+        file.path.to_string_lossy()
+      ))),
+    }
+  }
+
+  /// Find the snapshots of `modules` that weren't asserted, removing them
+  /// with `update`, and write the changed snapshot files.
+  pub fn finish(
+    &mut self,
+    modules: &[SnapshotModule],
+    update: bool,
+  ) -> Result<Vec<ObsoleteSnapshots>, AnyError> {
+    let mut obsolete = Vec::new();
+    for module in modules {
+      match Url::parse(&module.origin) {
+        Ok(url) if url.scheme() == "file" => {}
+        _ => continue,
+      }
+      let file = self.get_file(&module.origin)?;
+      let names = file
+        .snapshots
+        .keys()
+        .filter(|key| !file.used.contains(*key))
+        .filter(|key| !module.skipped.contains(&get_test_name(key)))
+        .cloned()
+        .collect::<Vec<_>>();
+      if names.is_empty() {
+        continue;
+      }
+      if update {
+        for name in &names {
+          file.snapshots.shift_remove(name);
+        }
+        file.changed = true;
+      }
+      obsolete.push(ObsoleteSnapshots {
+        file: file.path.to_string_lossy().to_string(),
+        names,
+        removed: update,
+      });
+    }
+    for file in self.files.values_mut() {
+      if file.changed {
+        file.write()?;
+        file.changed = false;
+      }
+    }
+    Ok(obsolete)
+  }
+}
+
+/// Keys are the test name followed by the index of the assertion.
+fn get_test_name(key: &str) -> String {
+  match key.rfind(' ') {
+    Some(index) => key[..index].to_string(),
+    None => key.to_string(),
+  }
+}
+
+fn get_snapshot_path(origin: &str) -> Result<PathBuf, AnyError> {
+  let url = Url::parse(origin)?;
+  let path = match url.to_file_path() {
+    Ok(path) if url.scheme() == "file" => path,
+    _ => {
+      return Err(generic_error(format!(
+        "Snapshots are only supported in local test modules: {}",
+        origin
+      )))
+    }
+  };
+  let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+  Ok(
+    path
+      .parent()
+      .unwrap()
+      .join("__snapshots__")
+      .join(format!("{}.snap", file_name)),
+  )
+}
+
+fn escape_template(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace('`', "\\`")
+    .replace("${", "\\${")
+}
+
+fn serialize_snapshots(snapshots: &IndexMap<String, String>) -> String {
+  let mut source = SNAPSHOT_HEADER.to_string();
+  for (key, value) in snapshots {
+    source.push_str(&format!(
+      "\nsnapshot[`{}`] = `\n{}\n`;\n",
+      escape_template(key),
+      escape_template(value)
+    ));
+  }
+  source
+}
+
+/// Parses the template literal starting at `chars`, after its opening
+/// backtick.
+fn parse_template(chars: &mut std::str::Chars) -> Result<String, AnyError> {
+  let mut text = String::new();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => match chars.next() {
+        Some(c) => text.push(c),
+        None => break,
+      },
+      '`' => return Ok(text),
+      c => text.push(c),
+    }
+  }
+  Err(generic_error("Unterminated template literal"))
+}
+
+fn expect(chars: &mut std::str::Chars, expected: &str) -> Result<(), AnyError> {
+  let rest = chars.as_str().trim_start();
+  if !rest.starts_with(expected) {
+    return Err(generic_error(format!("Expected \"{}\"", expected)));
+  }
+  *chars = rest[expected.len()..].chars();
+  Ok(())
+}
+
+fn parse_snapshots(source: &str) -> Result<IndexMap<String, String>, AnyError> {
+  let mut snapshots = IndexMap::new();
+  let mut chars = source.chars();
+  expect(&mut chars, SNAPSHOT_HEADER.trim_end())?;
+  while !chars.as_str().trim().is_empty() {
+    expect(&mut chars, "snapshot[`")?;
+    let key = parse_template(&mut chars)?;
+    expect(&mut chars, "] = `")?;
+    let value = parse_template(&mut chars)?;
+    expect(&mut chars, ";")?;
+    let value = value.strip_prefix('\n').unwrap_or(&value);
+    let value = value.strip_suffix('\n').unwrap_or(value);
+    snapshots.insert(key, value.to_string());
+  }
+  Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_serialize_and_parse_snapshots() {
+    let mut snapshots = IndexMap::new();
+    snapshots.insert("parses 1".to_string(), "{ a: 1 }".to_string());
+    snapshots.insert(
+      "escapes `backticks` 1".to_string(),
+      "`${a}` \\n\nsecond line".to_string(),
+    );
+    let source = serialize_snapshots(&snapshots);
+    assert_eq!(
+      source,
+      concat!(
+        "export const snapshot = {};\n",
+        "\n",
+        "snapshot[`parses 1`] = `\n{ a: 1 }\n`;\n",
+        "\n",
+        "snapshot[`escapes \\`backticks\\` 1`] = `\n",
+        "\\`\\${a}\\` \\\\n\nsecond line\n`;\n",
+      )
+    );
+    assert_eq!(parse_snapshots(&source).unwrap(), snapshots);
+  }
+
+  #[test]
+  fn test_parse_invalid_snapshots() {
+    assert!(parse_snapshots("snapshot[`a 1`] = `a`;").is_err());
+    let unterminated = "export const snapshot = {};\nsnapshot[`a 1`] = `a";
+    assert!(parse_snapshots(unterminated).is_err());
+  }
+
+  #[test]
+  fn test_get_test_name() {
+    assert_eq!(get_test_name("parses the config 1"), "parses the config");
+    assert_eq!(get_test_name("a 12"), "a");
+  }
+
+  #[test]
+  fn test_get_snapshot_path() {
+    let origin = Url::from_file_path(test_util::root_path().join("a_test.ts"))
+      .unwrap()
+      .to_string();
+    assert_eq!(
+      get_snapshot_path(&origin).unwrap(),
+      test_util::root_path()
+        .join("__snapshots__")
+        .join("a_test.ts.snap")
+    );
+    assert!(get_snapshot_path("https://deno.land/a_test.ts").is_err());
+  }
+}
@@ -0,0 +1,29 @@
+Deno.bench("sum", () => {
+  let sum = 0;
+  for (let i = 0; i < 100; i++) {
+    sum += i;
+  }
+});
+
+Deno.bench("resolve", async () => {
+  await Promise.resolve();
+});
+
+Deno.bench({
+  name: "fixed iterations",
+  n: 5,
+  warmup: 1,
+  fn() {
+    console.log("iteration");
+  },
+});
+
+Deno.bench({
+  name: "ignored",
+  ignore: true,
+  fn() {},
+});
+
+Deno.bench("fails", () => {
+  throw new Error("bench failed");
+});
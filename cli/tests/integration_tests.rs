@@ -475,6 +475,82 @@ fn fmt_test() {
   assert_eq!(expected, actual);
 }
 
+#[test]
+fn fmt_incremental_cache() {
+  let t = TempDir::new().expect("tempdir fail");
+  let deno_dir = TempDir::new().expect("tempdir fail");
+  let badly_formatted_original =
+    util::root_path().join("cli/tests/badly_formatted.mjs");
+  let badly_formatted = t.path().join("badly_formatted.js");
+  std::fs::copy(&badly_formatted_original, &badly_formatted)
+    .expect("Failed to copy file");
+  let run_fmt = |check: bool| {
+    let mut cmd = util::deno_cmd();
+    cmd
+      .current_dir(util::root_path())
+      .env("DENO_DIR", deno_dir.path())
+      .arg("fmt");
+    if check {
+      cmd.arg("--check");
+    }
+    cmd
+      .arg(&badly_formatted)
+      .stderr(std::process::Stdio::null())
+      .spawn()
+      .expect("Failed to spawn script")
+      .wait()
+      .expect("Failed to wait for child process")
+  };
+  // Files that are not formatted must not be cached.
+  assert!(!run_fmt(true).success());
+  assert!(!run_fmt(true).success());
+  assert!(run_fmt(false).success());
+  assert!(deno_dir.path().join("fmt_incremental_cache").is_dir());
+  assert!(run_fmt(true).success());
+  // Changing the file invalidates its entry.
+  std::fs::copy(&badly_formatted_original, &badly_formatted)
+    .expect("Failed to copy file");
+  assert!(!run_fmt(true).success());
+}
+
+#[test]
+fn lint_fix() {
+  let t = TempDir::new().expect("tempdir fail");
+  let config = t.path().join("deno.jsonc");
+  let fixable = t.path().join("fixable.ts");
+  std::fs::write(
+    &config,
+    r#"{ "lint": { "rules": { "include": ["no-extra-semi", "prefer-const", "ban-untagged-ignore"] } } }"#,
+  )
+  .expect("Failed to write config");
+  std::fs::write(
+    &fixable,
+    "let a = 1;;\n// deno-lint-ignore\nlet b = 2;\nlet c = 3;\nc = 4;\nconsole.log(a, b, c);\n",
+  )
+  .expect("Failed to write file");
+  let output = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("lint")
+    .arg("--unstable")
+    .arg("--fix")
+    .arg("--config")
+    .arg(&config)
+    .arg(&fixable)
+    .env("NO_COLOR", "1")
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  assert!(stderr.contains("Fixed (no-extra-semi) Remove the extra semicolon"));
+  assert!(stderr.contains("Fixed (prefer-const) Replace `let` with `const`"));
+  assert!(stderr.contains("Fixed (ban-untagged-ignore)"));
+  let fixed = std::fs::read_to_string(&fixable).expect("Failed to read file");
+  assert_eq!(
+    fixed,
+    "const a = 1;\nconst b = 2;\nlet c = 3;\nc = 4;\nconsole.log(a, b, c);\n"
+  );
+}
+
 #[test]
 fn fmt_stdin_error() {
   use std::io::Write;
@@ -1127,6 +1203,130 @@ fn run_watch() {
   drop(t);
 }
 
+#[test]
+fn test_watch() {
+  let t = TempDir::new().expect("tempdir fail");
+  let foo = t.path().join("foo.ts");
+  std::fs::write(&foo, "export const foo = 1;\n").expect("error writing file");
+  std::fs::write(
+    t.path().join("a_test.ts"),
+    "import { foo } from \"./foo.ts\";\nDeno.test(\"a\", () => {\n  if (foo !== 1) throw new Error(\"foo\");\n});\n",
+  )
+  .expect("error writing file");
+  std::fs::write(t.path().join("b_test.ts"), "Deno.test(\"b\", () => {});\n")
+    .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("test")
+    .arg("--watch")
+    .arg("--unstable")
+    .arg("--no-check")
+    .env("NO_COLOR", "1")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::null())
+    .spawn()
+    .expect("failed to spawn script");
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  let mut next_run = || {
+    let mut lines = vec![];
+    for line in &mut stdout_lines {
+      let is_end = line.starts_with("test result");
+      lines.push(line);
+      if is_end {
+        break;
+      }
+    }
+    lines.join("\n")
+  };
+
+  let output = next_run();
+  assert!(output.contains("a_test.ts"));
+  assert!(output.contains("b_test.ts"));
+  assert!(output.contains("test result: ok. 2 passed; 0 failed"));
+
+  // Give the watcher time to start watching the files before they change,
+  // otherwise the first write can be missed.
+  std::thread::sleep(std::time::Duration::from_secs(1));
+
+  // Only the test module importing the changed file is run again, and the
+  // failure doesn't terminate the process.
+  std::fs::write(&foo, "export const foo = 2;\n").expect("error writing file");
+  let output = next_run();
+  assert!(output.contains("a_test.ts"));
+  assert!(!output.contains("b_test.ts"));
+  assert!(output.contains("test result: FAILED. 0 passed; 1 failed"));
+
+  std::thread::sleep(std::time::Duration::from_secs(1));
+
+  std::fs::write(&foo, "export const foo = 1;\n").expect("error writing file");
+  let output = next_run();
+  assert!(output.contains("test result: ok. 1 passed; 0 failed"));
+
+  child.kill().unwrap();
+  drop(t);
+}
+
+#[test]
+fn test_watch_doc() {
+  let t = TempDir::new().expect("tempdir fail");
+  let add = t.path().join("add.ts");
+  let add_source = "/**\n * ```ts run\n * if (add(1, 2) !== 3) throw new Error(\"add\");\n * ```\n */\nexport function add(a: number, b: number) {\n  return a + b;\n}\n";
+  std::fs::write(&add, add_source).expect("error writing file");
+  std::fs::write(t.path().join("b_test.ts"), "Deno.test(\"b\", () => {});\n")
+    .expect("error writing file");
+
+  let mut child = util::deno_cmd()
+    .current_dir(t.path())
+    .arg("test")
+    .arg("--watch")
+    .arg("--doc")
+    .arg("--unstable")
+    .arg("--no-check")
+    .env("NO_COLOR", "1")
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::null())
+    .spawn()
+    .expect("failed to spawn script");
+
+  let stdout = child.stdout.as_mut().unwrap();
+  let mut stdout_lines =
+    std::io::BufReader::new(stdout).lines().map(|r| r.unwrap());
+  let mut next_run = || {
+    let mut lines = vec![];
+    for line in &mut stdout_lines {
+      let is_end = line.starts_with("test result");
+      lines.push(line);
+      if is_end {
+        break;
+      }
+    }
+    lines.join("\n")
+  };
+
+  let output = next_run();
+  assert!(output.contains("doc_test"));
+  assert!(output.contains("b_test.ts"));
+  assert!(output.contains("test result: ok. 2 passed; 0 failed"));
+
+  std::thread::sleep(std::time::Duration::from_secs(1));
+
+  // The doc tests are prepared again from the changed file, and only they
+  // are run again.
+  std::fs::write(&add, add_source.replace("a + b", "a - b"))
+    .expect("error writing file");
+  let output = next_run();
+  assert!(output.contains("doc_test"));
+  assert!(!output.contains("b_test.ts"));
+  assert!(output.contains("test result: FAILED. 0 passed; 1 failed"));
+
+  child.kill().unwrap();
+  drop(t);
+}
+
 #[test]
 fn repl_test_console_log() {
   let (out, err) = util::run_and_collect_output(
@@ -1225,6 +1425,15 @@ fn repl_test_close_command() {
   assert!(err.is_empty());
 }
 
+// Completion needs a terminal, so the input is written to a pty.
+#[cfg(unix)]
+#[test]
+fn repl_test_completion() {
+  let input =
+    b"Deno.exi\t\nconsol\t.log(\"a\" + \"b\")\n\"abc\".toUpperC\t()\nclose()\n";
+  util::test_pty("repl", "repl_completion.out", input);
+}
+
 #[test]
 fn repl_test_function() {
   let (out, err) = util::run_and_collect_output(
@@ -1407,6 +1616,57 @@ fn repl_test_assign_underscore_error() {
   assert_eq!(err, "Thrown: 2\n");
 }
 
+#[test]
+fn repl_test_typescript() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec![
+      "const a: number = 1;",
+      "interface Point { x: number; y: number }",
+      "function norm(p: Point): number {",
+      "  return Math.abs(p.x) + Math.abs(p.y);",
+      "}",
+      "norm({ x: a, y: -2 } as Point)",
+    ]),
+    Some(vec![("NO_COLOR".to_owned(), "1".to_owned())]),
+    false,
+  );
+  assert!(out.ends_with("undefined\nundefined\nundefined\n3\n"));
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_typescript_enum() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec!["enum Color {\nRed,\nGreen,\n}", "Color[Color.Green]"]),
+    Some(vec![("NO_COLOR".to_owned(), "1".to_owned())]),
+    false,
+  );
+  assert!(out.ends_with("\"Green\"\n"));
+  assert!(err.is_empty());
+}
+
+#[test]
+fn repl_test_typescript_error_location() {
+  let (out, err) = util::run_and_collect_output(
+    true,
+    "repl",
+    Some(vec![
+      "const n: number = 1;",
+      "function fail(message: string): never {\nthrow new Error(message);\n}",
+      "fail(\"oops\")",
+    ]),
+    Some(vec![("NO_COLOR".to_owned(), "1".to_owned())]),
+    false,
+  );
+  assert!(out.ends_with(REPL_MSG));
+  assert!(err.contains("Uncaught Error: oops"));
+  assert!(err.contains("$deno$repl_1.ts:2:7"));
+}
+
 #[test]
 fn deno_test_no_color() {
   let (out, _) = util::run_and_collect_output(
@@ -1582,6 +1842,142 @@ itest!(deno_test_fail_fast {
   output: "deno_test_fail_fast.out",
 });
 
+itest!(deno_test_jobs {
+  args: "test --no-check --jobs=2 test_jobs/a_test.ts test_jobs/b_test.ts",
+  exit_code: 1,
+  output: "test_jobs/expected.out",
+});
+
+itest!(deno_test_reporter_tap {
+  args:
+    "test --no-check --reporter=tap test_jobs/a_test.ts test_jobs/b_test.ts",
+  exit_code: 1,
+  output: "test_jobs/expected_tap.out",
+});
+
+itest!(deno_test_reporter_junit {
+  args:
+    "test --no-check --reporter=junit test_jobs/a_test.ts test_jobs/b_test.ts",
+  exit_code: 1,
+  output: "test_jobs/expected_junit.out",
+});
+
+itest!(deno_test_reporter_json {
+  args: "test --no-check --reporter=json test_jobs/b_test.ts",
+  exit_code: 1,
+  output: "test_jobs/expected_json.out",
+});
+
+#[test]
+fn deno_test_reporter_output_pretty() {
+  let t = TempDir::new().expect("tempdir fail");
+  let output_path = t.path().join("results.txt");
+  let status = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("test")
+    .arg("--no-check")
+    .arg("--reporter=pretty")
+    .arg(format!(
+      "--reporter-output={}",
+      output_path.to_string_lossy()
+    ))
+    .arg("test_jobs/a_test.ts")
+    .arg("test_jobs/error_test.ts")
+    .env("NO_COLOR", "1")
+    .stdout(std::process::Stdio::null())
+    .stderr(std::process::Stdio::null())
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(!status.success());
+  let results = std::fs::read_to_string(&output_path).unwrap();
+  assert!(results.contains("error: "));
+  assert!(results.contains("error while loading the module"));
+  assert!(results.contains("test result: FAILED. 2 passed; 1 failed"));
+}
+
+itest!(deno_test_steps {
+  args: "test test_steps/steps_test.ts",
+  exit_code: 1,
+  output: "test_steps/expected.out",
+});
+
+itest!(deno_test_steps_reporter_tap {
+  args: "test --reporter=tap test_steps/steps_test.ts",
+  exit_code: 1,
+  output: "test_steps/expected_tap.out",
+});
+
+itest!(deno_test_permissions {
+  args: "test --allow-read test_permissions/permissions_test.ts",
+  exit_code: 1,
+  output: "test_permissions/expected.out",
+});
+
+itest!(deno_test_permissions_escape {
+  args:
+    "test --unstable --allow-read --allow-net test_permissions/escape_test.ts",
+  output: "test_permissions/escape_test.out",
+});
+
+itest!(deno_test_leak {
+  args: "test --allow-read test_trace_ops/leak_test.ts",
+  exit_code: 1,
+  output: "test_trace_ops/leak_test_no_trace.out",
+});
+
+itest!(deno_test_trace_ops {
+  args: "test --allow-read --trace-ops test_trace_ops/leak_test.ts",
+  exit_code: 1,
+  output: "test_trace_ops/leak_test.out",
+});
+
+itest!(deno_test_timeout {
+  args: "test --allow-read test_timeout/hang_test.ts",
+  exit_code: 1,
+  output: "test_timeout/hang_test.out",
+});
+
+itest!(deno_test_timeout_trace_ops {
+  args: "test --allow-read --trace-ops test_timeout/hang_test.ts",
+  exit_code: 1,
+  output: "test_timeout/hang_test_trace_ops.out",
+});
+
+itest!(deno_test_timeout_default {
+  args: "test --test-timeout=200 test_timeout/never_settled_test.ts",
+  exit_code: 1,
+  output: "test_timeout/never_settled_test.out",
+});
+
+itest!(deno_test_snapshot {
+  args: "test test_snapshot/snapshot_test.ts",
+  output: "test_snapshot/snapshot_test.out",
+});
+
+itest!(deno_test_snapshot_mismatch {
+  args: "test test_snapshot/mismatch_test.ts",
+  exit_code: 1,
+  output: "test_snapshot/mismatch_test.out",
+});
+
+itest!(deno_test_doc {
+  args: "test --doc test_doc/",
+  output: "test_doc/expected.out",
+});
+
+itest!(deno_test_doc_type_error {
+  args: "test --doc test_doc_error/",
+  exit_code: 1,
+  output: "test_doc_error/expected.out",
+});
+
+itest!(deno_test_shuffle {
+  args: "test --shuffle=1234 test_shuffle/shuffle_test.ts",
+  output: "test_shuffle/expected.out",
+});
+
 itest!(deno_test_only {
   args: "test deno_test_only.ts",
   exit_code: 1,
@@ -1594,6 +1990,17 @@ itest!(deno_test_no_check {
   output: "deno_test.out",
 });
 
+itest!(deno_bench {
+  args: "bench --unstable bench/example_bench.ts",
+  exit_code: 1,
+  output: "bench/expected.out",
+});
+
+itest!(deno_bench_json {
+  args: "bench --unstable --json --filter=fixed bench/",
+  output: "bench/expected_json.out",
+});
+
 #[test]
 fn timeout_clear() {
   // https://github.com/denoland/deno/issues/7599
@@ -1953,6 +2360,12 @@ itest!(fmt_check_formatted_files {
   exit_code: 0,
 });
 
+itest!(fmt_check_formatted_md_json_files {
+  args: "fmt --check fmt/formatted3.md fmt/formatted4.jsonc",
+  output: "fmt/expected_fmt_check_formatted_md_json_files.out",
+  exit_code: 0,
+});
+
 itest!(fmt_check_ignore {
   args: "fmt --check --unstable --ignore=fmt/formatted1.js fmt/",
   output: "fmt/expected_fmt_check_ignore.out",
@@ -1977,6 +2390,24 @@ itest!(fmt_stdin_check_not_formatted {
   output_str: Some("Not formatted stdin\n"),
 });
 
+itest!(fmt_stdin_options {
+  args: "fmt --options-single-quote --options-indent-width=4 -",
+  input: Some("function f() {\n  return \"a\";\n}\n"),
+  output_str: Some("function f() {\n    return 'a';\n}\n"),
+});
+
+itest!(fmt_stdin_config {
+  args: "fmt --config fmt/deno.jsonc -",
+  input: Some("const a = \"a\"\n"),
+  output_str: Some("const a = 'a';\n"),
+});
+
+itest!(fmt_stdin_config_overridden_by_options {
+  args: "fmt --config fmt/deno.jsonc --options-use-tabs -",
+  input: Some("function f() {\n  return 1;\n}\n"),
+  output_str: Some("function f() {\n\treturn 1;\n}\n"),
+});
+
 itest!(circular1 {
   args: "run --reload circular1.js",
   output: "circular1.js.out",
@@ -2513,6 +2944,137 @@ itest!(deno_test_coverage {
   exit_code: 0,
 });
 
+itest!(deno_test_coverage_jobs {
+  args: "test --coverage --unstable --jobs=2 test_coverage.ts",
+  output: "test_coverage_jobs.out",
+  exit_code: 0,
+});
+
+itest!(deno_test_coverage_threshold {
+  args:
+    "test --coverage --unstable --coverage-threshold=lines:80 test_coverage.ts",
+  output: "test_coverage_threshold.out",
+  exit_code: 1,
+});
+
+itest!(deno_test_coverage_jobs_threshold {
+  args:
+    "test --coverage --unstable --jobs=2 --coverage-threshold=lines:80 test_coverage.ts",
+  output: "test_coverage_jobs_threshold.out",
+  exit_code: 1,
+});
+
+#[test]
+fn deno_run_coverage() {
+  let t = TempDir::new().expect("tempdir fail");
+  let profile_dir = t.path().join("cov_profile");
+  let coverage_arg = format!("--coverage={}", profile_dir.to_string_lossy());
+  let status = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("test")
+    .arg("--unstable")
+    .arg(&coverage_arg)
+    .arg("test_coverage.ts")
+    .stdout(std::process::Stdio::null())
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+  // The profile is saved before exiting with the code given to Deno.exit().
+  let output = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("run")
+    .arg("--unstable")
+    .arg(&coverage_arg)
+    .arg("run_coverage.ts")
+    .output()
+    .expect("Failed to spawn script");
+  assert_eq!(output.status.code(), Some(3));
+  assert_eq!(String::from_utf8(output.stdout).unwrap(), "Foo\n");
+  assert_eq!(std::fs::read_dir(&profile_dir).unwrap().count(), 2);
+
+  let output = util::deno_cmd()
+    .arg("coverage")
+    .arg("--unstable")
+    .arg("--reporter=lcov")
+    .arg(&profile_dir)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let lcov = String::from_utf8(output.stdout).unwrap();
+  let mod1_path = util::tests_path().join("subdir").join("mod1.ts");
+  let mod1_record = lcov
+    .split("end_of_record\n")
+    .find(|record| {
+      record.starts_with(&format!("SF:{}\n", mod1_path.to_string_lossy()))
+    })
+    .expect("No record of mod1.ts");
+  assert!(mod1_record.contains("FNDA:1,returnsHi\n"));
+  assert!(mod1_record.contains("FNDA:1,returnsFoo2\n"));
+  assert!(mod1_record.contains("LH:7\nLF:13\n"));
+  let run_path = util::tests_path().join("run_coverage.ts");
+  assert!(lcov.contains(&format!("SF:{}\n", run_path.to_string_lossy())));
+}
+
+#[test]
+fn deno_test_coverage_lcov() {
+  let t = TempDir::new().expect("tempdir fail");
+  let profile_dir = t.path().join("cov_profile");
+  for _ in 0..2 {
+    let status = util::deno_cmd()
+      .current_dir(util::tests_path())
+      .arg("test")
+      .arg("--unstable")
+      .arg(format!("--coverage={}", profile_dir.to_string_lossy()))
+      .arg("test_coverage.ts")
+      .stdout(std::process::Stdio::null())
+      .spawn()
+      .expect("Failed to spawn script")
+      .wait()
+      .expect("Failed to wait for child process");
+    assert!(status.success());
+  }
+  assert_eq!(std::fs::read_dir(&profile_dir).unwrap().count(), 2);
+
+  let output = util::deno_cmd()
+    .arg("coverage")
+    .arg("--unstable")
+    .arg("--reporter=lcov")
+    .arg(&profile_dir)
+    .output()
+    .expect("Failed to spawn script");
+  assert!(output.status.success());
+  let lcov = String::from_utf8(output.stdout).unwrap();
+  let mod1_path = util::tests_path().join("subdir").join("mod1.ts");
+  let mod1_record = lcov
+    .split("end_of_record\n")
+    .find(|record| {
+      record.starts_with(&format!("SF:{}\n", mod1_path.to_string_lossy()))
+    })
+    .expect("No record of mod1.ts");
+  assert!(mod1_record.contains("FN:3,returnsHi\n"));
+  assert!(mod1_record.contains("FNDA:2,returnsHi\n"));
+  assert!(mod1_record.contains("FNDA:0,throwsError\n"));
+  assert!(mod1_record.contains("LH:4\nLF:13\n"));
+  assert_eq!(lcov.matches("end_of_record\n").count(), 3);
+
+  let html_dir = t.path().join("html");
+  let status = util::deno_cmd()
+    .arg("coverage")
+    .arg("--unstable")
+    .arg("--reporter=html")
+    .arg(format!("--output={}", html_dir.to_string_lossy()))
+    .arg(&profile_dir)
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+  assert!(html_dir.join("index.html").exists());
+  assert!(html_dir.join("0.html").exists());
+}
+
 itest!(deno_lint {
   args: "lint --unstable lint/file1.js lint/file2.ts lint/ignored_file.ts",
   output: "lint/expected.out",
@@ -2532,6 +3094,27 @@ itest!(deno_lint_json {
   exit_code: 1,
 });
 
+itest!(deno_lint_sarif {
+  args:
+    "lint --unstable --reporter=sarif lint/file1.js lint/file2.ts lint/ignored_file.ts lint/malformed.js",
+  output: "lint/expected_sarif.out",
+  exit_code: 1,
+});
+
+itest!(deno_lint_checkstyle {
+  args:
+    "lint --unstable --reporter=checkstyle lint/file1.js lint/file2.ts lint/ignored_file.ts lint/malformed.js",
+  output: "lint/expected_checkstyle.out",
+  exit_code: 1,
+});
+
+itest!(deno_lint_junit {
+  args:
+    "lint --unstable --reporter=junit lint/file1.js lint/file2.ts lint/ignored_file.ts lint/malformed.js",
+  output: "lint/expected_junit.out",
+  exit_code: 1,
+});
+
 itest!(deno_lint_ignore {
   args: "lint --unstable --ignore=lint/file1.js,lint/malformed.js lint/",
   output: "lint/expected_ignore.out",
@@ -2565,6 +3148,42 @@ itest!(deno_lint_rules {
 });
 
 // Make sure that the rules are printed if quiet option is enabled.
+itest!(deno_lint_config {
+  args: "lint --unstable --config lint_config/deno.jsonc",
+  output: "lint_config/expected.out",
+  exit_code: 1,
+});
+
+itest!(deno_lint_config_rules {
+  args: "lint --unstable --config lint_config/deno.jsonc --rules",
+  output: "lint_config/expected_rules.out",
+});
+
+itest!(deno_lint_plugin {
+  args: "lint --unstable --config lint_plugin/deno.jsonc lint_plugin/mod.ts",
+  output: "lint_plugin/expected.out",
+  exit_code: 1,
+});
+
+itest!(deno_lint_plugin_rules {
+  args: "lint --unstable --config lint_plugin/deno.jsonc --rules",
+  output: "lint_plugin/expected_rules.out",
+});
+
+itest!(deno_lint_plugin_import {
+  args:
+    "lint --unstable --config lint_plugin/deno_import.jsonc lint_plugin/mod.ts",
+  output: "lint_plugin/expected_import.out",
+  exit_code: 1,
+});
+
+itest!(deno_lint_plugin_timeout {
+  args:
+    "lint --unstable --config lint_plugin/deno_slow.jsonc lint_plugin/mod.ts",
+  output: "lint_plugin/expected_slow.out",
+  exit_code: 1,
+});
+
 itest!(deno_lint_rules_quiet {
   args: "lint --unstable --rules -q",
   output: "lint/expected_rules.out",
@@ -2581,6 +3200,87 @@ itest!(deno_doc {
   output: "deno_doc.out",
 });
 
+#[test]
+fn deno_doc_html() {
+  let t = TempDir::new().expect("tempdir fail");
+  let output = t.path().join("docs");
+  let status = util::deno_cmd()
+    .current_dir(util::tests_path())
+    .arg("doc")
+    .arg("--html")
+    .arg(format!("--output={}", output.to_string_lossy()))
+    .arg("deno_doc.ts")
+    .stderr(std::process::Stdio::null())
+    .spawn()
+    .expect("Failed to spawn script")
+    .wait()
+    .expect("Failed to wait for child process");
+  assert!(status.success());
+  let index = std::fs::read_to_string(output.join("index.html")).unwrap();
+  assert!(index.contains("href=\"deno_doc_ts.html\""));
+  let page = std::fs::read_to_string(output.join("deno_doc_ts.html")).unwrap();
+  assert!(page.contains("id=\"foo\""));
+  assert!(page.contains("Some JSDoc"));
+  let search_index =
+    std::fs::read_to_string(output.join("search_index.js")).unwrap();
+  assert!(search_index.contains("\"href\":\"deno_doc_ts.html#foo\""));
+  assert!(output.join("styles.css").is_file());
+}
+
+#[test]
+fn deno_test_update_snapshots() {
+  let t = TempDir::new().expect("tempdir fail");
+  let test_file = t.path().join("a_test.ts");
+  let snapshot_file = t.path().join("__snapshots__").join("a_test.ts.snap");
+  let run_tests = |update: bool| {
+    let mut cmd = util::deno_cmd();
+    cmd.current_dir(t.path()).arg("test");
+    if update {
+      cmd.arg("--update-snapshots");
+    }
+    cmd
+      .arg("a_test.ts")
+      .stdout(std::process::Stdio::null())
+      .stderr(std::process::Stdio::null())
+      .spawn()
+      .expect("Failed to spawn script")
+      .wait()
+      .expect("Failed to wait for child process")
+  };
+
+  std::fs::write(
+    &test_file,
+    "Deno.test(\"a\", (t) => t.assertSnapshot([1]));\n\
+     Deno.test(\"b\", (t) => t.assertSnapshot(\"b\"));\n",
+  )
+  .unwrap();
+  assert!(!run_tests(false).success());
+  assert!(!snapshot_file.exists());
+  assert!(run_tests(true).success());
+  assert_eq!(
+    std::fs::read_to_string(&snapshot_file).unwrap(),
+    "export const snapshot = {};\n\n\
+     snapshot[`a 1`] = `\n[\n  1,\n]\n`;\n\n\
+     snapshot[`b 1`] = `\nb\n`;\n"
+  );
+  assert!(run_tests(false).success());
+
+  // Obsolete snapshots are removed, and the file once it's empty.
+  std::fs::write(
+    &test_file,
+    "Deno.test(\"a\", (t) => t.assertSnapshot([1]));\n",
+  )
+  .unwrap();
+  assert!(run_tests(true).success());
+  assert_eq!(
+    std::fs::read_to_string(&snapshot_file).unwrap(),
+    "export const snapshot = {};\n\nsnapshot[`a 1`] = `\n[\n  1,\n]\n`;\n"
+  );
+  std::fs::write(&test_file, "Deno.test(\"a\", () => {});\n").unwrap();
+  assert!(run_tests(true).success());
+  assert!(!snapshot_file.exists());
+}
+
 itest!(compiler_js_error {
   args: "run --unstable compiler_js_error.ts",
   output: "compiler_js_error.ts.out",
@@ -0,0 +1,4 @@
+// TODO: find a better value
+export function a(): any {
+  return 1;
+}
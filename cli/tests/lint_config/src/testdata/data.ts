@@ -0,0 +1,3 @@
+export function c(): any {
+  return 3;
+}
@@ -0,0 +1,3 @@
+export function b(): any {
+  return 2;
+}
@@ -0,0 +1,3 @@
+import "./rules.ts";
+
+export default {};
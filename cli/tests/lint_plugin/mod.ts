@@ -0,0 +1,5 @@
+import { chunk } from "lodash";
+// deno-lint-ignore no-lodash
+import { join } from "lodash";
+
+console.log(chunk, join);
@@ -0,0 +1,18 @@
+// A lint plugin banning imports of lodash.
+export default {
+  rules: {
+    "no-lodash": {
+      // deno-lint-ignore no-explicit-any
+      create(context: any) {
+        return {
+          // deno-lint-ignore no-explicit-any
+          ImportDeclaration(node: any) {
+            if (node.source.value === "lodash") {
+              context.report({ node, message: "Don't import lodash" });
+            }
+          },
+        };
+      },
+    },
+  },
+};
@@ -0,0 +1,12 @@
+// A lint plugin whose rule never finishes.
+export default {
+  rules: {
+    "no-progress": {
+      create() {
+        for (;;) {
+          // Spin until the linter gives up.
+        }
+      },
+    },
+  },
+};
@@ -0,0 +1,4 @@
+import { returnsFoo2 } from "./subdir/mod1.ts";
+
+console.log(returnsFoo2());
+Deno.exit(3);
@@ -0,0 +1,20 @@
+/**
+ * Add two numbers.
+ *
+ * ```ts
+ * const sum: number = add(1, 2);
+ * ```
+ *
+ * ```ts run
+ * if (add(1, 2) !== 3) {
+ *   throw new Error("Wrong sum");
+ * }
+ * ```
+ *
+ * ```ts ignore
+ * add("not", "checked");
+ * ```
+ */
+export function add(a: number, b: number): number {
+  return a + b;
+}
@@ -0,0 +1,10 @@
+/**
+ * Multiply two numbers.
+ *
+ * ```ts
+ * const product: string = multiply(2, 3);
+ * ```
+ */
+export function multiply(a: number, b: number): number {
+  return a * b;
+}
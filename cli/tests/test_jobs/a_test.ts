@@ -0,0 +1,6 @@
+Deno.test("slow", async function () {
+  await new Promise((resolve) => setTimeout(resolve, 200));
+  console.log("output of slow");
+});
+
+Deno.test("fast", function () {});
@@ -0,0 +1,5 @@
+Deno.test("fail", function () {
+  throw new Error("fail assertion");
+});
+
+Deno.test("success", function () {});
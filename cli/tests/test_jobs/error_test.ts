@@ -0,0 +1 @@
+throw new Error("error while loading the module");
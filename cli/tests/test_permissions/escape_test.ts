@@ -0,0 +1,58 @@
+async function assertState(
+  descriptor: Deno.PermissionDescriptor,
+  expected: Deno.PermissionState,
+) {
+  const { state } = await Deno.permissions.query(descriptor);
+  if (state !== expected) {
+    throw new Error(
+      `expected ${JSON.stringify(descriptor)} to be ${expected}, got ${state}`,
+    );
+  }
+}
+
+Deno.test({
+  name: "the test can't restore the permissions of the worker",
+  permissions: { read: false },
+  async fn() {
+    for (const args of [{ id: 0 }, { token: "0" }]) {
+      try {
+        // @ts-expect-error
+        Deno.core.jsonOpSync("op_restore_test_permissions", args);
+      } catch {
+        // The arguments are invalid.
+      }
+    }
+    await assertState({ name: "read" }, "denied");
+  },
+});
+
+Deno.test({
+  name: "the test can't request more permissions",
+  permissions: { read: ["test_permissions"], net: ["localhost"] },
+  async fn() {
+    const requests: Deno.PermissionDescriptor[] = [
+      { name: "read" },
+      { name: "read", path: "." },
+      { name: "net" },
+      { name: "net", url: "https://deno.land" },
+    ];
+    for (const descriptor of requests) {
+      const { state } = await Deno.permissions.request(descriptor);
+      if (state !== "denied") {
+        throw new Error(
+          `expected ${JSON.stringify(descriptor)} to be denied, got ${state}`,
+        );
+      }
+    }
+    await assertState(
+      { name: "read", path: "test_permissions/escape_test.ts" },
+      "granted",
+    );
+    await assertState({ name: "net", url: "http://localhost" }, "granted");
+  },
+});
+
+Deno.test("the permissions of the worker are restored", async () => {
+  await assertState({ name: "read" }, "granted");
+  await assertState({ name: "net" }, "granted");
+});
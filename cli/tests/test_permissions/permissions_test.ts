@@ -0,0 +1,31 @@
+Deno.test({
+  name: "read is denied",
+  permissions: { read: false },
+  async fn() {
+    const status = await Deno.permissions.query({ name: "read" });
+    if (status.state !== "denied") {
+      throw new Error(`expected read to be denied, got ${status.state}`);
+    }
+    try {
+      Deno.readTextFileSync("test_permissions/permissions_test.ts");
+      throw new Error("expected the read to fail");
+    } catch (error) {
+      if (!(error instanceof Deno.errors.PermissionDenied)) {
+        throw error;
+      }
+    }
+  },
+});
+
+Deno.test("permissions are restored", async () => {
+  const status = await Deno.permissions.query({ name: "read" });
+  if (status.state !== "granted") {
+    throw new Error(`expected read to be granted, got ${status.state}`);
+  }
+});
+
+Deno.test({
+  name: "permissions can't be escalated",
+  permissions: { net: true },
+  fn() {},
+});
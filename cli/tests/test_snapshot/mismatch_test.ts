@@ -0,0 +1,7 @@
+Deno.test("mismatch", (t) => {
+  t.assertSnapshot({ a: 2 });
+});
+
+Deno.test("missing", (t) => {
+  t.assertSnapshot([]);
+});
@@ -0,0 +1,16 @@
+Deno.test("serializes objects", (t) => {
+  t.assertSnapshot({ b: [1, 2], a: "one" });
+});
+
+Deno.test("serializes primitives", (t) => {
+  t.assertSnapshot("`template` ${literal}");
+  t.assertSnapshot(42);
+});
+
+Deno.test({
+  name: "ignored test keeps its snapshots",
+  ignore: true,
+  fn(t) {
+    t.assertSnapshot(undefined);
+  },
+});
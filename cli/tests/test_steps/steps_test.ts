@@ -0,0 +1,14 @@
+Deno.test("parent", async (t) => {
+  await t.step("step 1", () => {});
+  await t.step("step 2", async (t) => {
+    await t.step("nested", () => {});
+  });
+  await t.step({ name: "ignored step", ignore: true, fn: () => {} });
+});
+
+Deno.test("failing step", async (t) => {
+  await t.step("ok step", () => {});
+  await t.step("bad step", () => {
+    throw new Error("step failed");
+  });
+});
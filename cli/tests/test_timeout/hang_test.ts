@@ -0,0 +1,10 @@
+Deno.test({
+  name: "hang",
+  timeout: 100,
+  async fn() {
+    await Deno.open("test_timeout/hang_test.ts");
+    await new Promise((resolve) => setTimeout(resolve, 10000));
+  },
+});
+
+Deno.test("after hang", () => {});
@@ -0,0 +1,3 @@
+Deno.test("never settled", async () => {
+  await new Promise(() => {});
+});
@@ -0,0 +1,3 @@
+Deno.test("leaks a file", () => {
+  Deno.openSync("test_trace_ops/leak_test.ts");
+});
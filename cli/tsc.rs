@@ -273,6 +273,9 @@ pub struct TsCompilerInner {
   pub use_disk_cache: bool,
   /// This setting is controlled by `compilerOptions.checkJs`
   pub compile_js: bool,
+  /// Source maps of the scripts transpiled outside of the module graph, such
+  /// as the entries of the REPL, which aren't saved to the disk cache.
+  emitted_source_maps: Mutex<HashMap<Url, Vec<u8>>>,
 }
 
 #[derive(Clone)]
@@ -349,9 +352,24 @@ impl TsCompiler {
       config,
       compiled: Mutex::new(HashSet::new()),
       use_disk_cache,
+      emitted_source_maps: Mutex::new(HashMap::new()),
     })))
   }
 
+  /// Keep the source map of a script transpiled outside of the module graph,
+  /// for the errors it throws to refer to its source.
+  pub fn save_emitted_source_map(&self, url: Url, source_map: Vec<u8>) {
+    let mut emitted_source_maps = self.emitted_source_maps.lock().unwrap();
+    emitted_source_maps.insert(url, source_map);
+  }
+
+  /// Forget the source map of a script transpiled outside of the module
+  /// graph, which won't throw errors anymore.
+  pub fn remove_emitted_source_map(&self, url: &Url) {
+    let mut emitted_source_maps = self.emitted_source_maps.lock().unwrap();
+    emitted_source_maps.remove(url);
+  }
+
   /// Mark given module URL as compiled to avoid multiple compilations of same
   /// module in single run.
   fn mark_compiled(&self, url: &Url) {
@@ -905,6 +923,14 @@ impl TsCompiler {
       if module_specifier.as_url().scheme() == "deno" {
         return None;
       }
+      if let Some(source_map) = self
+        .emitted_source_maps
+        .lock()
+        .unwrap()
+        .get(module_specifier.as_url())
+      {
+        return Some(source_map.clone());
+      }
       return match self.get_source_map_file(&module_specifier) {
         Ok(out) => Some(out.source_code.into_bytes()),
         Err(_) => {
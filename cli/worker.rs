@@ -1,5 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::flags::DenoSubcommand;
 use crate::fmt_errors::JsError;
 use crate::global_state::GlobalState;
 use crate::inspector::DenoInspector;
@@ -7,6 +8,7 @@ use crate::js;
 use crate::metrics::Metrics;
 use crate::ops;
 use crate::ops::io::get_stdio;
+use crate::ops::os::DeferredExit;
 use crate::permissions::Permissions;
 use crate::state::CliModuleLoader;
 use deno_core::error::AnyError;
@@ -134,7 +136,11 @@ impl Worker {
           &mut isolate,
           Some(inspector_server.clone()),
         ))
-      } else if global_state.flags.coverage {
+      } else if global_state.flags.coverage
+        || (is_main && global_state.flags.subcommand == DenoSubcommand::Repl)
+      {
+        // The REPL queries the isolate through the inspector to complete
+        // the names typed by the user.
         Some(DenoInspector::new(&mut isolate, None))
       } else {
         None
@@ -312,6 +318,7 @@ impl MainWorker {
       ops::repl::init(&mut worker);
       ops::runtime_compiler::init(&mut worker);
       ops::signal::init(&mut worker);
+      ops::testing::init(&mut worker);
       ops::tls::init(&mut worker);
       ops::tty::init(&mut worker);
       ops::websocket::init(&mut worker);
@@ -336,6 +343,17 @@ impl MainWorker {
       .expect("Failed to execute bootstrap script");
     Self(worker)
   }
+
+  /// Make `Deno.exit()` terminate the execution of the worker instead of
+  /// exiting the process, so that the caller can clean up before exiting.
+  pub fn defer_exit(&mut self) -> DeferredExit {
+    let deferred_exit = DeferredExit {
+      isolate_handle: self.0.isolate.thread_safe_handle(),
+      code: Default::default(),
+    };
+    self.op_state().borrow_mut().put(deferred_exit.clone());
+    deferred_exit
+  }
 }
 
 impl Deref for MainWorker {
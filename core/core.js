@@ -212,6 +212,76 @@ SharedQueue Binary Layout
     return JSON.parse(s);
   }
 
+  // With op call tracing, the async ops dispatched from JS that haven't
+  // completed are kept with the stack trace of their dispatch, as well as the
+  // op call that created each resource.
+  const pendingOpCalls = new Map();
+  const resourceOpCalls = new Map();
+  let opCallTracingEnabled = false;
+
+  function enableOpCallTracing() {
+    opCallTracingEnabled = true;
+  }
+
+  function isOpCallTracingEnabled() {
+    return opCallTracingEnabled;
+  }
+
+  // Returns the op call to pass to `opCallCompleted()` once the async op has
+  // completed, or null if op calls aren't traced.
+  function opCallStarted(opName) {
+    if (!opCallTracingEnabled) {
+      return null;
+    }
+    const opCall = { opName, stack: captureOpCallStack() };
+    pendingOpCalls.set(opCall, getResourceIds());
+    return opCall;
+  }
+
+  function opCallCompleted(opCall) {
+    if (opCall == null) {
+      return;
+    }
+    const rids = pendingOpCalls.get(opCall);
+    pendingOpCalls.delete(opCall);
+    traceResources(opCall, rids);
+  }
+
+  function captureOpCallStack() {
+    // Skip the message of the error and the frames of this file.
+    const stack = new Error().stack.split("\n").slice(1);
+    return stack
+      .map((line) => line.trim())
+      .filter((line) => !/[\s(]core\.js:/.test(line))
+      .join("\n");
+  }
+
+  function getResourceIds() {
+    const res = decodeJson(dispatch("op_resources", encodeJson({})));
+    return new Set(Object.keys(res.ok));
+  }
+
+  // Resources created while the op ran are attributed to it, unless an op
+  // that completed earlier created them.
+  function traceResources(opCall, preRids) {
+    for (const rid of getResourceIds()) {
+      if (!preRids.has(rid) && !resourceOpCalls.has(rid)) {
+        resourceOpCalls.set(rid, opCall);
+      }
+    }
+  }
+
+  // The traced async ops that haven't completed, as `{ opName, stack }`
+  // objects.
+  function pendingOps() {
+    return [...pendingOpCalls.keys()];
+  }
+
+  // The op call that created the resource, if it was traced.
+  function resourceOpCall(rid) {
+    return resourceOpCalls.get(String(rid));
+  }
+
   let nextPromiseId = 1;
   const promiseTable = {};
 
@@ -220,7 +290,13 @@ SharedQueue Binary Layout
 
     args.promiseId = nextPromiseId++;
     const argsBuf = encodeJson(args);
-    dispatch(opName, argsBuf, ...zeroCopy);
+    const opCall = opCallStarted(opName);
+    try {
+      dispatch(opName, argsBuf, ...zeroCopy);
+    } catch (err) {
+      pendingOpCalls.delete(opCall);
+      throw err;
+    }
     let resolve, reject;
     const promise = new Promise((resolve_, reject_) => {
       resolve = resolve_;
@@ -230,6 +306,7 @@ SharedQueue Binary Layout
     promise.reject = reject;
     promiseTable[args.promiseId] = promise;
     const res = await promise;
+    opCallCompleted(opCall);
     if ("ok" in res) {
       return res.ok;
     } else {
@@ -239,8 +316,12 @@ SharedQueue Binary Layout
 
   function jsonOpSync(opName, args = {}, ...zeroCopy) {
     const argsBuf = encodeJson(args);
+    const preRids = opCallTracingEnabled ? getResourceIds() : null;
     const res = dispatch(opName, argsBuf, ...zeroCopy);
     const r = decodeJson(res);
+    if (preRids != null) {
+      traceResources({ opName, stack: captureOpCallStack() }, preRids);
+    }
     if ("ok" in r) {
       return r.ok;
     } else {
@@ -262,6 +343,7 @@ SharedQueue Binary Layout
 
   function close(rid) {
     jsonOpSync("op_close", { rid });
+    resourceOpCalls.delete(String(rid));
   }
 
   Object.assign(window.Deno.core, {
@@ -275,6 +357,12 @@ SharedQueue Binary Layout
     resources,
     registerErrorClass,
     getErrorClass,
+    enableOpCallTracing,
+    isOpCallTracingEnabled,
+    opCallStarted,
+    opCallCompleted,
+    pendingOps,
+    resourceOpCall,
     // sharedQueue is private but exposed for testing.
     sharedQueue: {
       MAX_RECORDS,